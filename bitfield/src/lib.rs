@@ -42,7 +42,7 @@ bitfield_impl! { u8 u16 u32 u64 usize }
 
 impl<T: BitField> BitArray<T> for [T] {
     fn bit_length(&self) -> usize {
-        self.len() * (T::bit_length() as usize)
+        self.len() * T::bit_length()
     }
 
     fn get_bit(&self, bit: usize) -> bool {
@@ -61,8 +61,8 @@ impl<T: BitField> BitArray<T> for [T] {
 #[cfg(test)]
 #[test]
 fn bit() {
-    assert_eq!(false, 0b11111110u8.get_bit(0));
-    assert_eq!(false, 0b11110111u8.get_bit(3));
+    assert!(!0b11111110u8.get_bit(0));
+    assert!(!0b11110111u8.get_bit(3));
     assert_eq!(&mut 0b11111101u8, 0b11111111.set_bit(1, false));
     assert_eq!(&mut 0b11011111u8, 0b11111111.set_bit(5, false));
 }
@@ -70,8 +70,8 @@ fn bit() {
 #[cfg(test)]
 #[test]
 fn bitfield() {
-    assert_eq!(false, 0b11111111_11111101u16.get_bit(1));
-    assert_eq!(false, 0b10111111_11111111u16.get_bit(14));
+    assert!(!0b11111111_11111101u16.get_bit(1));
+    assert!(!0b10111111_11111111u16.get_bit(14));
     assert_eq!(
         &mut 0b11111111_11110111u16,
         0b11111111_11111111.set_bit(3, false)
@@ -126,7 +126,7 @@ nibblefield_impl! { u8 u16 u32 u64 usize }
 
 impl<T: NibbleField> NibbleArray<T> for [T] {
     fn nibble_length(&self) -> usize {
-        self.len() * (T::nibble_length() as usize)
+        self.len() * T::nibble_length()
     }
 
     fn get_nibble(&self, index: usize) -> u8 {
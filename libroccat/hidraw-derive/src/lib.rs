@@ -1,110 +1,233 @@
-#![recursion_limit = "128"]
+#![recursion_limit = "256"]
 
 extern crate proc_macro;
 
 use crate::proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
 use quote::quote;
-use syn::{Data, DataStruct, DeriveInput, Expr, Field, Ident, Lit, Meta, MetaNameValue, Type};
+use syn::{
+    Data, DataEnum, DataStruct, DeriveInput, Fields, Ident, Lit, Meta, MetaList, MetaNameValue,
+    NestedMeta, Type,
+};
 
-#[proc_macro_derive(HidrawRead, attributes(hidraw_constant, hidraw_bytesum))]
-pub fn derive_hid_read(input: TokenStream) -> TokenStream {
+#[proc_macro_derive(ReportField)]
+pub fn derive_report_field(input: TokenStream) -> TokenStream {
     let input: DeriveInput = syn::parse(input).unwrap();
     let name = &input.ident;
-    let (const_field_names, const_field_vals) = get_const_fields(&input);
-    let check_bytesum =
-        if let Some((bytesum_field_name, bytesum_field_type)) = get_bytesum_field(&input) {
+
+    let output = match input.data {
+        Data::Struct(_) => {
+            let fields = get_fields(&input);
+            let names = fields.iter().map(|field| &field.name).collect::<Vec<_>>();
+            let names2 = names.clone();
+            let types = fields.iter().map(|field| &field.ty).collect::<Vec<_>>();
+            let types2 = types.clone();
+            let offsets = fields.iter().map(|field| &field.offset).collect::<Vec<_>>();
+            let offsets2 = offsets.clone();
+
             quote! {
-                {
-                    const BYTES_SIZE: usize = ::std::mem::size_of::<#name>() -
-                        ::std::mem::size_of::<#bytesum_field_type>();
-                    let bytes: [u8; BYTES_SIZE] = ::std::mem::transmute_copy(&data);
-                    if data.#bytesum_field_name ==
-                        bytes.iter().map(|b| *b as #bytesum_field_type).sum()
-                    {
-                        return Ok(data)
+                impl crate::report::ReportField for #name {
+                    const SIZE: usize = 0 #(+ <#types as crate::report::ReportField>::SIZE)*;
+
+                    fn encode(&self, bytes: &mut [u8]) {
+                        #(
+                            let value = self.#names;
+                            crate::report::ReportField::encode(&value, &mut bytes[#offsets..]);
+                        )*
+                    }
+
+                    fn decode(bytes: &[u8]) -> Result<Self, crate::Error> {
+                        Ok(#name {
+                            #(
+                                #names2: <#types2 as crate::report::ReportField>::decode(
+                                    &bytes[#offsets2..],
+                                )?,
+                            )*
+                        })
                     }
                 }
             }
-        } else {
-            quote!(return Ok(data))
-        };
+        }
+        Data::Enum(DataEnum { ref variants, .. }) => {
+            let variants = variants
+                .iter()
+                .map(|variant| match variant.fields {
+                    Fields::Unit => &variant.ident,
+                    _ => panic!("hidraw: Only fieldless enums are supported"),
+                })
+                .collect::<Vec<_>>();
+            let variants2 = variants.clone();
+            let names = ::std::iter::repeat(name);
+            let names2 = names.clone();
+            let type_name = name.to_string();
 
-    let output = quote! {
-        impl #name {
-            pub unsafe fn read(interface: &::std::fs::File) -> Result<#name, ::failure::Error> {
-                nix::ioctl_readwrite!(__hidraw_read, b'H', 0x07, #name);
-
-                use std::os::unix::io::AsRawFd;
-
-                let mut data = #name {
-                    #(#const_field_names: #const_field_vals,)*
-                    .. ::std::mem::uninitialized()
-                };
-
-                let mut errors = 0;
-                loop {
-                    match __hidraw_read(interface.as_raw_fd(), &mut data) {
-                        Ok(_) => #check_bytesum,
-                        Err(error) => {
-                            if errors < 10 {
-                                errors += 1;
-                            } else {
-                                return Err(error.into());
-                            }
+            quote! {
+                impl crate::report::ReportField for #name {
+                    const SIZE: usize = 1;
+
+                    fn encode(&self, bytes: &mut [u8]) {
+                        bytes[0] = *self as u8;
+                    }
+
+                    fn decode(bytes: &[u8]) -> Result<Self, crate::Error> {
+                        match bytes[0] {
+                            #(value if value == #names::#variants as u8 => Ok(#names2::#variants2),)*
+                            value => Err(crate::Error::InvalidValue {
+                                type_name: #type_name,
+                                value,
+                            }),
                         }
                     }
                 }
             }
         }
+        Data::Union(_) => panic!("hidraw: Unions are not supported"),
     };
 
     output.into()
 }
 
-#[proc_macro_derive(HidrawWrite, attributes(hidraw_constant, hidraw_bytesum))]
-pub fn derive_hid_write(input: TokenStream) -> TokenStream {
+#[proc_macro_derive(HidrawRead, attributes(hidraw_report, hidraw_bytesum))]
+pub fn derive_hid_read(input: TokenStream) -> TokenStream {
     let input: DeriveInput = syn::parse(input).unwrap();
     let name = &input.ident;
-    let (const_field_names, const_field_vals) = get_const_fields(&input);
-    let assign_bytesum =
-        if let Some((bytesum_field_name, bytesum_field_type)) = get_bytesum_field(&input) {
-            quote! {
-                const BYTES_SIZE: usize = ::std::mem::size_of::<#name>() -
-                    ::std::mem::size_of::<#bytesum_field_type>();
-                let bytes: [u8; BYTES_SIZE] = ::std::mem::transmute_copy(&data);
-                data.#bytesum_field_name = bytes.iter().map(|b| *b as #bytesum_field_type).sum();
+    let report = get_report_attributes(&input);
+    let id = report.id;
+    let len = report.len;
+    let header_len = report.header_len();
+    let write_header = report.write_header();
+    let assert_len = report.assert_len(name);
+    let check_bytesum = if let Some(bytesum) = get_bytesum_field(&input) {
+        let ty = &bytesum.ty;
+        let offset = bytesum.offset;
+        let offset = quote!(#header_len + #offset);
+        let calculate_bytesum = calculate_bytesum(ty, &offset);
+        quote! {
+            let bytesum = #calculate_bytesum;
+            let sent = <#ty as crate::report::ReportField>::decode(&bytes[#offset..])?;
+            if sent != bytesum {
+                return Err(crate::transport::ChecksumMismatch {
+                    report_id: bytes[0],
+                    expected: bytesum.into(),
+                    actual: sent.into(),
+                }
+                .into());
             }
-        } else {
-            quote!()
-        };
+        }
+    } else {
+        quote!()
+    };
 
     let output = quote! {
+        #assert_len
+
         impl #name {
-            pub unsafe fn write(self, interface: &::std::fs::File) -> Result<(), ::failure::Error> {
-                nix::ioctl_readwrite!(__hidraw_write, b'H', 0x06, #name);
+            pub const REPORT_ID: u8 = #id;
+            pub const LEN: usize = #len;
 
-                use std::os::unix::io::AsRawFd;
+            /// Decodes a report, checking its ID, length, bytesum and enum values
+            pub fn from_bytes(bytes: &[u8]) -> Result<#name, crate::Error> {
+                if bytes.len() != Self::LEN {
+                    return Err(crate::Error::ReportLength {
+                        report_id: bytes.first().cloned().unwrap_or_default(),
+                        expected: Self::LEN,
+                        actual: bytes.len(),
+                    });
+                }
+                if bytes[0] != Self::REPORT_ID {
+                    return Err(crate::Error::ReportId {
+                        expected: Self::REPORT_ID,
+                        actual: bytes[0],
+                    });
+                }
 
-                let mut data = #name {
-                    #(#const_field_names: #const_field_vals,)*
-                    .. self
-                };
+                #check_bytesum
+                <#name as crate::report::ReportField>::decode(&bytes[#header_len..])
+            }
+
+            pub fn read<T>(interface: &T) -> Result<#name, crate::Error>
+            where
+                T: crate::transport::Transport + ?Sized,
+            {
+                Self::read_with_retry(interface, &crate::transport::default_retry_policy())
+            }
+
+            pub fn read_with_retry<T>(
+                interface: &T,
+                retry: &crate::transport::RetryPolicy,
+            ) -> Result<#name, crate::Error>
+            where
+                T: crate::transport::Transport + ?Sized,
+            {
+                retry.retry(|| {
+                    let mut bytes = vec![0u8; Self::LEN];
+                    #write_header
+
+                    let len = interface.get_feature_report(&mut bytes)?;
+                    Self::from_bytes(&bytes[..len])
+                })
+            }
+        }
+    };
+
+    output.into()
+}
 
+#[proc_macro_derive(HidrawWrite, attributes(hidraw_report, hidraw_bytesum))]
+pub fn derive_hid_write(input: TokenStream) -> TokenStream {
+    let input: DeriveInput = syn::parse(input).unwrap();
+    let name = &input.ident;
+    let report = get_report_attributes(&input);
+    let len = report.len;
+    let header_len = report.header_len();
+    let write_header = report.write_header();
+    let assert_len = report.assert_len(name);
+    let assign_bytesum = if let Some(bytesum) = get_bytesum_field(&input) {
+        let ty = &bytesum.ty;
+        let offset = bytesum.offset;
+        let offset = quote!(#header_len + #offset);
+        let calculate_bytesum = calculate_bytesum(ty, &offset);
+        quote! {
+            let bytesum = #calculate_bytesum;
+            crate::report::ReportField::encode(&bytesum, &mut bytes[#offset..]);
+        }
+    } else {
+        quote!()
+    };
+
+    let output = quote! {
+        #assert_len
+
+        impl #name {
+            /// Encodes the report, filling in its header and bytesum
+            pub fn to_bytes(&self) -> Vec<u8> {
+                let mut bytes = vec![0u8; #len];
+                #write_header
+                crate::report::ReportField::encode(self, &mut bytes[#header_len..]);
                 #assign_bytesum
+                bytes
+            }
 
-                let mut errors = 0;
-                loop {
-                    match __hidraw_write(interface.as_raw_fd(), &mut data) {
-                        Ok(_) => return Ok(()),
-                        Err(error) => {
-                            if errors < 10 {
-                                errors += 1;
-                            } else {
-                                return Err(error.into());
-                            }
-                        }
-                    }
-                }
+            pub fn write<T>(&self, interface: &T) -> Result<(), crate::Error>
+            where
+                T: crate::transport::Transport + ?Sized,
+            {
+                self.write_with_retry(interface, &crate::transport::default_retry_policy())
+            }
+
+            pub fn write_with_retry<T>(
+                &self,
+                interface: &T,
+                retry: &crate::transport::RetryPolicy,
+            ) -> Result<(), crate::Error>
+            where
+                T: crate::transport::Transport + ?Sized,
+            {
+                let bytes = self.to_bytes();
+                retry.retry(|| {
+                    interface.set_feature_report(&bytes)?;
+                    Ok(())
+                })
             }
         }
     };
@@ -112,61 +235,158 @@ pub fn derive_hid_write(input: TokenStream) -> TokenStream {
     output.into()
 }
 
-fn get_const_fields(input: &DeriveInput) -> (Vec<Ident>, Vec<Expr>) {
+struct ReportFieldInfo {
+    name: Ident,
+    ty: Type,
+    /// Byte offset of the field as a constant expression
+    offset: TokenStream2,
+}
+
+fn get_fields(input: &DeriveInput) -> Vec<ReportFieldInfo> {
     match input.data {
         Data::Struct(DataStruct { ref fields, .. }) => {
-            fields.iter().flat_map(get_const_field).unzip()
+            let mut previous_types = Vec::new();
+            fields
+                .iter()
+                .map(|field| {
+                    let previous = previous_types.iter();
+                    let offset = quote!(0 #(+ <#previous as crate::report::ReportField>::SIZE)*);
+                    previous_types.push(field.ty.clone());
+                    ReportFieldInfo {
+                        name: field
+                            .ident
+                            .clone()
+                            .expect("hidraw: Only named fields are supported"),
+                        ty: field.ty.clone(),
+                        offset,
+                    }
+                })
+                .collect()
         }
         _ => panic!("Hidraw derive only supports structs"),
     }
 }
 
-fn get_const_field(field: &Field) -> Option<(Ident, Expr)> {
-    let name = field
-        .ident
-        .as_ref()
-        .expect("hidraw: Only named fields are supported");
+/// Wrapping sum of all bytes before the bytesum field
+fn calculate_bytesum(ty: &Type, offset: &TokenStream2) -> TokenStream2 {
+    quote! {
+        bytes[..#offset]
+            .iter()
+            .fold(0 as #ty, |sum, b| sum.wrapping_add(*b as #ty))
+    }
+}
+
+/// Parsed `#[hidraw_report(id = .., len = .., size = "u8" | "u16" | "none")]`
+struct ReportAttributes {
+    id: u8,
+    len: usize,
+    /// Type of the length field following the report ID, if there is one
+    size: Option<Ident>,
+}
+
+impl ReportAttributes {
+    fn header_len(&self) -> usize {
+        match self.size {
+            Some(ref size) if size == "u16" => 3,
+            Some(_) => 2,
+            None => 1,
+        }
+    }
+
+    /// Writes the report ID and length field into `bytes`
+    fn write_header(&self) -> TokenStream2 {
+        let id = self.id;
+        let write_size = self.size.as_ref().map(|size| {
+            let len = self.len;
+            quote! {
+                crate::report::ReportField::encode(&(#len as #size), &mut bytes[1..]);
+            }
+        });
+
+        quote! {
+            bytes[0] = #id;
+            #write_size
+        }
+    }
+
+    /// Fails to compile if the declared length doesn't match the struct
+    fn assert_len(&self, name: &Ident) -> TokenStream2 {
+        let header_len = self.header_len();
+        let len = self.len;
+        let message = format!(
+            "hidraw: {} declares a length of {} bytes, but its fields don't add up to that",
+            name, len
+        );
 
-    field
+        quote! {
+            const _: () = assert!(
+                #header_len + ::std::mem::size_of::<#name>() == #len
+                    && #header_len + <#name as crate::report::ReportField>::SIZE == #len,
+                #message
+            );
+        }
+    }
+}
+
+fn get_report_attributes(input: &DeriveInput) -> ReportAttributes {
+    let nested = input
         .attrs
         .iter()
-        .flat_map(|attr| match attr.interpret_meta() {
-            Some(Meta::NameValue(MetaNameValue {
-                ref ident, ref lit, ..
-            })) if ident == "hidraw_constant" => match *lit {
-                Lit::Str(ref lit_str) => {
-                    let expr = syn::parse_str(&lit_str.value()).unwrap();
-                    Some((name.clone(), expr))
-                }
-                _ => panic!("hidraw: Unsupported constant literal"),
-            },
+        .filter_map(|attr| match attr.interpret_meta() {
+            Some(Meta::List(MetaList {
+                ref ident, nested, ..
+            })) if ident == "hidraw_report" => Some(nested),
             _ => None,
         })
         .next()
-}
+        .expect("hidraw: Missing #[hidraw_report(id = .., len = ..)] attribute");
 
-fn get_bytesum_field(input: &DeriveInput) -> Option<(Ident, Type)> {
-    match input.data {
-        Data::Struct(DataStruct { ref fields, .. }) => {
-            let last = fields.iter().last().unwrap();
-            for attr in &last.attrs {
-                match attr.interpret_meta() {
-                    Some(Meta::Word(ident)) => {
-                        if ident == "hidraw_bytesum" {
-                            return Some((
-                                last.ident
-                                    .as_ref()
-                                    .expect("Only named fields are supported")
-                                    .clone(),
-                                last.ty.clone(),
-                            ));
-                        }
+    let (mut id, mut len, mut size) = (None, None, Some(Ident::new("u8", input.ident.span())));
+    for meta in nested {
+        match meta {
+            NestedMeta::Meta(Meta::NameValue(MetaNameValue {
+                ref ident, ref lit, ..
+            })) => match (ident.to_string().as_str(), lit) {
+                ("id", Lit::Int(value)) => id = Some(value.value() as u8),
+                ("len", Lit::Int(value)) => len = Some(value.value() as usize),
+                ("size", Lit::Str(value)) => {
+                    size = match value.value().as_str() {
+                        "u8" | "u16" => Some(Ident::new(&value.value(), value.span())),
+                        "none" => None,
+                        _ => panic!("hidraw: size must be \"u8\", \"u16\" or \"none\""),
                     }
-                    _ => (),
                 }
-            }
+                _ => panic!("hidraw: Unknown report attribute {}", ident),
+            },
+            _ => panic!("hidraw: Malformed report attribute"),
         }
+    }
+
+    ReportAttributes {
+        id: id.expect("hidraw: Missing report id"),
+        len: len.expect("hidraw: Missing report len"),
+        size,
+    }
+}
+
+fn get_bytesum_field(input: &DeriveInput) -> Option<ReportFieldInfo> {
+    let is_bytesum = match input.data {
+        Data::Struct(DataStruct { ref fields, .. }) => fields
+            .iter()
+            .last()
+            .unwrap()
+            .attrs
+            .iter()
+            .any(|attr| match attr.interpret_meta() {
+                Some(Meta::Word(ident)) => ident == "hidraw_bytesum",
+                _ => false,
+            }),
         _ => panic!("Hidraw derive only supports structs"),
+    };
+
+    if is_bytesum {
+        get_fields(input).pop()
+    } else {
+        None
     }
-    return None;
 }
@@ -0,0 +1,210 @@
+use crate::{
+    device::{
+        ryosmkfx::{
+            CustomLights, DeviceInfo, EventStream, KeysEasyzone, KeysExtra, KeysFunction,
+            KeysMacro, KeysPrimary, KeysThumbster, Lights, Macro, RyosMkFx,
+        },
+        RoccatDevice,
+    },
+    transport::Transport,
+    Error,
+};
+use futures::channel::oneshot;
+use std::{
+    future::Future,
+    io,
+    panic::{self, AssertUnwindSafe},
+    sync::{mpsc, Arc, Mutex, OnceLock},
+    thread,
+};
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// Makes a device's blocking requests awaitable
+///
+/// Feature reports can only be transferred with blocking ioctls, so the requests of every
+/// `AsyncDevice` run one at a time on a single worker thread, in the order they were made.
+/// Events don't need a thread: `events` is woken whenever the Events interface becomes readable.
+pub struct AsyncDevice<D> {
+    device: Arc<D>,
+}
+
+impl<D: Send + Sync + 'static> AsyncDevice<D> {
+    pub fn new(device: D) -> Self {
+        Self {
+            device: Arc::new(device),
+        }
+    }
+
+    /// The device itself, for calls that don't block like subscribing to events
+    pub fn device(&self) -> &D {
+        &self.device
+    }
+
+    /// Runs `request` on the worker thread
+    pub fn call<R, F>(&self, request: F) -> impl Future<Output = Result<R, Error>>
+    where
+        F: FnOnce(&D) -> Result<R, Error> + Send + 'static,
+        R: Send + 'static,
+    {
+        let (sender, receiver) = oneshot::channel();
+        let device = Arc::clone(&self.device);
+        let sent = worker().lock().unwrap().send(Box::new(move || {
+            let _ = sender.send(request(&device));
+        }));
+        async move {
+            sent.map_err(|_| request_failed())?;
+            receiver.await.map_err(|_| request_failed())?
+        }
+    }
+}
+
+impl<D: RoccatDevice + Sync + 'static> AsyncDevice<D> {
+    pub fn get_profile(&self) -> impl Future<Output = Result<u8, Error>> {
+        self.call(|device| device.get_profile())
+    }
+
+    pub fn set_profile(&self, profile: u8) -> impl Future<Output = Result<(), Error>> {
+        self.call(move |device| device.set_profile(profile))
+    }
+}
+
+impl<T: Transport + 'static> AsyncDevice<RyosMkFx<T>> {
+    /// Every event and read error from now on, see `RyosMkFx::event_stream`
+    pub fn events(&self) -> EventStream<T> {
+        self.device.event_stream()
+    }
+
+    pub fn get_info(&self) -> impl Future<Output = Result<DeviceInfo, Error>> {
+        self.call(|device| device.get_info())
+    }
+
+    pub fn get_lights(&self, profile: u8) -> impl Future<Output = Result<Lights, Error>> {
+        self.call(move |device| device.get_lights(profile))
+    }
+
+    pub fn set_lights(&self, lights: Lights) -> impl Future<Output = Result<(), Error>> {
+        self.call(move |device| device.set_lights(&lights))
+    }
+
+    pub fn get_custom_lights_active(&self) -> impl Future<Output = Result<bool, Error>> {
+        self.call(|device| device.get_custom_lights_active())
+    }
+
+    pub fn set_custom_lights_active(
+        &self,
+        active: bool,
+    ) -> impl Future<Output = Result<(), Error>> {
+        self.call(move |device| device.set_custom_lights_active(active))
+    }
+
+    pub fn get_custom_lights(&self) -> impl Future<Output = Result<CustomLights, Error>> {
+        self.call(|device| device.get_custom_lights())
+    }
+
+    pub fn set_custom_lights(
+        &self,
+        custom_lights: CustomLights,
+    ) -> impl Future<Output = Result<(), Error>> {
+        self.call(move |device| device.set_custom_lights(&custom_lights))
+    }
+
+    pub fn get_macro(&self, profile: u8, slot: u8) -> impl Future<Output = Result<Macro, Error>> {
+        self.call(move |device| device.get_macro(profile, slot))
+    }
+
+    pub fn set_macro(
+        &self,
+        profile: u8,
+        slot: u8,
+        macro_: Macro,
+    ) -> impl Future<Output = Result<(), Error>> {
+        self.call(move |device| device.set_macro(profile, slot, &macro_))
+    }
+
+    pub fn get_keys_primary(
+        &self,
+        profile: u8,
+    ) -> impl Future<Output = Result<KeysPrimary, Error>> {
+        self.call(move |device| device.get_keys_primary(profile))
+    }
+
+    pub fn set_keys_primary(&self, keys: KeysPrimary) -> impl Future<Output = Result<(), Error>> {
+        self.call(move |device| device.set_keys_primary(keys))
+    }
+
+    pub fn get_keys_function(
+        &self,
+        profile: u8,
+    ) -> impl Future<Output = Result<KeysFunction, Error>> {
+        self.call(move |device| device.get_keys_function(profile))
+    }
+
+    pub fn set_keys_function(&self, keys: KeysFunction) -> impl Future<Output = Result<(), Error>> {
+        self.call(move |device| device.set_keys_function(keys))
+    }
+
+    pub fn get_keys_macro(&self, profile: u8) -> impl Future<Output = Result<KeysMacro, Error>> {
+        self.call(move |device| device.get_keys_macro(profile))
+    }
+
+    pub fn set_keys_macro(&self, keys: KeysMacro) -> impl Future<Output = Result<(), Error>> {
+        self.call(move |device| device.set_keys_macro(keys))
+    }
+
+    pub fn get_keys_thumbster(
+        &self,
+        profile: u8,
+    ) -> impl Future<Output = Result<KeysThumbster, Error>> {
+        self.call(move |device| device.get_keys_thumbster(profile))
+    }
+
+    pub fn set_keys_thumbster(
+        &self,
+        keys: KeysThumbster,
+    ) -> impl Future<Output = Result<(), Error>> {
+        self.call(move |device| device.set_keys_thumbster(keys))
+    }
+
+    pub fn get_keys_extra(&self, profile: u8) -> impl Future<Output = Result<KeysExtra, Error>> {
+        self.call(move |device| device.get_keys_extra(profile))
+    }
+
+    pub fn set_keys_extra(&self, keys: KeysExtra) -> impl Future<Output = Result<(), Error>> {
+        self.call(move |device| device.set_keys_extra(keys))
+    }
+
+    pub fn get_keys_easyzone(
+        &self,
+        profile: u8,
+    ) -> impl Future<Output = Result<KeysEasyzone, Error>> {
+        self.call(move |device| device.get_keys_easyzone(profile))
+    }
+
+    pub fn set_keys_easyzone(&self, keys: KeysEasyzone) -> impl Future<Output = Result<(), Error>> {
+        self.call(move |device| device.set_keys_easyzone(keys))
+    }
+}
+
+/// Gets the queue of the worker thread, starting it on first use
+fn worker() -> &'static Mutex<mpsc::Sender<Job>> {
+    static WORKER: OnceLock<Mutex<mpsc::Sender<Job>>> = OnceLock::new();
+    WORKER.get_or_init(|| {
+        let (jobs, receiver) = mpsc::channel::<Job>();
+        thread::Builder::new()
+            .name("libroccat-worker".to_string())
+            .spawn(move || {
+                for job in receiver {
+                    // A panicking request fails on its own without stopping the others
+                    let _ = panic::catch_unwind(AssertUnwindSafe(job));
+                }
+            })
+            .expect("Can't start the worker thread");
+        Mutex::new(jobs)
+    })
+}
+
+/// A request panicked before it could answer
+fn request_failed() -> Error {
+    io::Error::other("Device request panicked").into()
+}
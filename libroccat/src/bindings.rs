@@ -0,0 +1,280 @@
+use crate::{
+    device::ryosmkfx::{Event, EventType},
+    Error,
+};
+use log::{debug, warn};
+use nix::{
+    libc,
+    sys::socket::{self, AddressFamily, SockFlag, SockType},
+};
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    mem,
+    os::unix::{ffi::OsStrExt, fs::OpenOptionsExt, io::FromRawFd, net::UnixStream},
+    path::{Path, PathBuf},
+    process::Command,
+    str::FromStr,
+    thread,
+    time::Duration,
+};
+
+const DEFAULT_MESSAGE: &str = "{event} {data}";
+
+/// How long a socket action waits for the listener to take its line
+const SOCKET_WRITE_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Which events a binding reacts to
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct EventPattern {
+    pub type_: EventType,
+    /// Data byte the event has to carry, `None` for any
+    pub data: Option<u8>,
+}
+
+impl EventPattern {
+    pub fn matches(&self, event: &Event) -> bool {
+        event.type_ == self.type_ && (self.data.is_none() || self.data == Some(event.data))
+    }
+}
+
+/// What to do on the host when a binding's event arrives
+#[derive(Clone, Debug, PartialEq)]
+pub enum Action {
+    /// Runs a command line with `sh -c` without waiting for it
+    Exec(String),
+    /// Writes a line to a named pipe, skipped if nobody is reading
+    Fifo { path: PathBuf, message: String },
+    /// Writes a line to a Unix stream socket, failing instead of blocking on a busy listener
+    Socket { path: PathBuf, message: String },
+}
+
+impl Action {
+    pub fn run(&self, event: &Event) -> Result<(), Error> {
+        match *self {
+            Action::Exec(ref command) => {
+                let mut child = Command::new("sh")
+                    .arg("-c")
+                    .arg(command)
+                    .env("ROCCAT_EVENT", event.type_.name())
+                    .env("ROCCAT_DATA", event.data.to_string())
+                    .spawn()?;
+                // Reap the command once it's done instead of waiting for it
+                thread::spawn(move || child.wait());
+                Ok(())
+            }
+            Action::Fifo {
+                ref path,
+                ref message,
+            } => {
+                // Opening for writing fails with `ENXIO` instead of blocking without a reader
+                let mut fifo = match OpenOptions::new()
+                    .write(true)
+                    .custom_flags(nix::fcntl::OFlag::O_NONBLOCK.bits())
+                    .open(path)
+                {
+                    Err(ref error)
+                        if error.raw_os_error() == Some(nix::errno::Errno::ENXIO as i32) =>
+                    {
+                        debug!("Nobody is reading {}", path.display());
+                        return Ok(());
+                    }
+                    fifo => fifo?,
+                };
+                fifo.write_all(format_message(message, event).as_bytes())?;
+                Ok(())
+            }
+            Action::Socket {
+                ref path,
+                ref message,
+            } => {
+                let mut socket = connect(path)?;
+                socket.write_all(format_message(message, event).as_bytes())?;
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Connects to a Unix stream socket and bounds how long writes may block
+///
+/// The connect itself doesn't block: a listener whose backlog is full fails it with `EAGAIN`.
+fn connect(path: &Path) -> io::Result<UnixStream> {
+    let fd = socket::socket(
+        AddressFamily::Unix,
+        SockType::Stream,
+        SockFlag::SOCK_NONBLOCK | SockFlag::SOCK_CLOEXEC,
+        None,
+    )
+    .map_err(nix_error)?;
+    // Owns the descriptor from here on, so it's closed on errors too
+    let stream = unsafe { UnixStream::from_raw_fd(fd) };
+
+    // Built by hand, nix 0.14 computes the address length in a way current Rust rejects
+    let path = path.as_os_str().as_bytes();
+    let mut address: libc::sockaddr_un = unsafe { mem::zeroed() };
+    if path.len() >= address.sun_path.len() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Socket path is too long",
+        ));
+    }
+    address.sun_family = libc::AF_UNIX as libc::sa_family_t;
+    for (dst, &src) in address.sun_path.iter_mut().zip(path) {
+        *dst = src as libc::c_char;
+    }
+    let len = mem::size_of::<libc::sa_family_t>() + path.len() + 1;
+    let address = &address as *const libc::sockaddr_un as *const libc::sockaddr;
+    if unsafe { libc::connect(fd, address, len as libc::socklen_t) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    stream.set_nonblocking(false)?;
+    stream.set_write_timeout(Some(SOCKET_WRITE_TIMEOUT))?;
+    Ok(stream)
+}
+
+fn nix_error(error: nix::Error) -> io::Error {
+    match error {
+        nix::Error::Sys(errno) => io::Error::from_raw_os_error(errno as i32),
+        error => io::Error::other(error),
+    }
+}
+
+/// Fills in `{event}` and `{data}`, ending the message with a newline
+fn format_message(message: &str, event: &Event) -> String {
+    let mut line = message
+        .replace("{event}", event.type_.name())
+        .replace("{data}", &event.data.to_string());
+    line.push('\n');
+    line
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Binding {
+    pub pattern: EventPattern,
+    pub action: Action,
+}
+
+/// Runs host actions for device events
+///
+/// Each line of a binding table binds an event to an action:
+///
+/// ```text
+/// # event        action  argument
+/// macro:3        exec    notify-send "Macro 3"
+/// quicklaunch    fifo    /run/user/1000/roccat.fifo
+/// profile        socket  /run/user/1000/roccat.sock profile {data}
+/// ```
+///
+/// Events are event type names like `timer_start`, optionally followed by `:` and the data byte
+/// in decimal or `0x` hexadecimal. `exec` runs its argument with `sh -c`, passing the event in
+/// `ROCCAT_EVENT` and `ROCCAT_DATA`. `fifo` and `socket` write a line to a named pipe or Unix
+/// stream socket, `{event} {data}` unless a message follows the path. Empty lines and lines
+/// starting with `#` are ignored.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Bindings {
+    pub bindings: Vec<Binding>,
+}
+
+impl Bindings {
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        fs::read_to_string(path)?.parse()
+    }
+
+    /// Gets the bindings for an event, in table order
+    pub fn matching<'a>(&'a self, event: &'a Event) -> impl Iterator<Item = &'a Binding> + 'a {
+        self.bindings
+            .iter()
+            .filter(move |binding| binding.pattern.matches(event))
+    }
+
+    /// Runs the actions bound to an event, logging the ones that fail
+    ///
+    /// Returns how many actions ran successfully.
+    pub fn handle(&self, event: &Event) -> usize {
+        let mut succeeded = 0;
+        for binding in self.matching(event) {
+            match binding.action.run(event) {
+                Ok(()) => succeeded += 1,
+                Err(error) => warn!(
+                    "Action for {} {} failed: {}",
+                    event.type_.name(),
+                    event.data,
+                    error
+                ),
+            }
+        }
+        succeeded
+    }
+}
+
+impl FromStr for Bindings {
+    type Err = Error;
+
+    fn from_str(table: &str) -> Result<Self, Error> {
+        let mut bindings = Vec::new();
+        for (index, line) in table.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = |message: &str| Error::InvalidBinding {
+                line: index + 1,
+                message: message.to_string(),
+            };
+
+            let (event, rest) = split_word(line);
+            let (action, argument) = split_word(rest);
+            let pattern = parse_pattern(event).map_err(|message| invalid(&message))?;
+            let action = match action {
+                "exec" if !argument.is_empty() => Action::Exec(argument.to_string()),
+                "fifo" | "socket" if !argument.is_empty() => {
+                    let (path, message) = split_word(argument);
+                    let path = PathBuf::from(path);
+                    let message = match message {
+                        "" => DEFAULT_MESSAGE.to_string(),
+                        message => message.to_string(),
+                    };
+                    if action == "fifo" {
+                        Action::Fifo { path, message }
+                    } else {
+                        Action::Socket { path, message }
+                    }
+                }
+                "exec" | "fifo" | "socket" => return Err(invalid("Missing action argument")),
+                "" => return Err(invalid("Missing action")),
+                action => return Err(invalid(&format!("Unknown action {}", action))),
+            };
+            bindings.push(Binding { pattern, action });
+        }
+        Ok(Self { bindings })
+    }
+}
+
+fn parse_pattern(pattern: &str) -> Result<EventPattern, String> {
+    let (name, data) = match pattern.split_once(':') {
+        Some((name, data)) => (name, Some(data)),
+        None => (pattern, None),
+    };
+    let type_ = EventType::from_name(name).ok_or_else(|| format!("Unknown event {}", name))?;
+    let data = match data {
+        Some(data) => Some(parse_byte(data).ok_or_else(|| format!("Invalid event data {}", data))?),
+        None => None,
+    };
+    Ok(EventPattern { type_, data })
+}
+
+fn parse_byte(value: &str) -> Option<u8> {
+    match value.strip_prefix("0x") {
+        Some(hex) => u8::from_str_radix(hex, 16).ok(),
+        None => value.parse().ok(),
+    }
+}
+
+/// Splits off the first whitespace separated word
+fn split_word(text: &str) -> (&str, &str) {
+    match text.split_once(char::is_whitespace) {
+        Some((word, rest)) => (word, rest.trim_start()),
+        None => (text, ""),
+    }
+}
@@ -0,0 +1,127 @@
+use crate::{
+    device::{ryosmkfx::KeyboardLayout, DeviceId, RoccatDevice},
+    selector::{Candidate, Selector},
+    udev, Error,
+};
+use log::warn;
+use std::{fs, path::Path, str::FromStr};
+
+/// A setting the device itself can't store
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Setting {
+    /// Layout printed on the keycaps, see `KeyLabels`
+    Layout(KeyboardLayout),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Entry {
+    pub selector: Selector,
+    pub setting: Setting,
+}
+
+/// Settings for each device, applied whenever it's opened
+///
+/// Each line of a configuration applies a setting to the device a selector picks:
+///
+/// ```text
+/// # device         setting  value
+/// ryosmkfx         layout   de
+/// serial:0123ABCD  layout   uk
+/// ```
+///
+/// Devices are picked like by `libroccat::open`, see `Selector`. `layout` takes a
+/// `KeyboardLayout` name. Later lines win over earlier ones for the same device. Empty lines and
+/// lines starting with `#` are ignored.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Config {
+    pub entries: Vec<Entry>,
+}
+
+impl Config {
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        fs::read_to_string(path)?.parse()
+    }
+
+    /// Gets the settings for the device with this ID among `candidates`, in file order
+    ///
+    /// `candidates` have to be ordered by bus path, like the devices `libroccat::open` picks from.
+    pub fn settings_for<'a>(
+        &'a self,
+        id: &'a DeviceId,
+        candidates: &'a [Candidate],
+    ) -> impl Iterator<Item = Setting> + 'a {
+        self.entries
+            .iter()
+            .filter(move |entry| entry.selector.selects(id, candidates))
+            .map(|entry| entry.setting)
+    }
+
+    /// Gets the layout set for the device with this ID, see `settings_for`
+    pub fn layout_for(&self, id: &DeviceId, candidates: &[Candidate]) -> Option<KeyboardLayout> {
+        self.settings_for(id, candidates)
+            .map(|setting| match setting {
+                Setting::Layout(layout) => layout,
+            })
+            .last()
+    }
+
+    /// Applies the settings for a device opened through udev
+    ///
+    /// Devices without an ID, like emulated ones, can't be selected and are left alone. Settings
+    /// the device has no use for are skipped with a warning.
+    pub fn apply(&self, device: &dyn RoccatDevice) -> Result<(), Error> {
+        let id = match device.id() {
+            Some(id) => id,
+            None => return Ok(()),
+        };
+        let context = libudev::Context::new()?;
+        let (candidates, _) = udev::supported(&context)?;
+        for setting in self.settings_for(id, &candidates) {
+            match setting {
+                Setting::Layout(layout) => match device.as_key_labels() {
+                    Some(labels) => labels.set_layout(layout),
+                    None => warn!("{} has no keyboard layout", device.name()),
+                },
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Config {
+    type Err = Error;
+
+    fn from_str(config: &str) -> Result<Self, Error> {
+        let mut entries = Vec::new();
+        for (index, line) in config.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = |message: &str| Error::InvalidConfig {
+                line: index + 1,
+                message: message.to_string(),
+            };
+
+            let mut words = line.split_whitespace();
+            let selector = words.next().unwrap_or_default();
+            let selector: Selector = selector
+                .parse()
+                .map_err(|_| invalid(&format!("Invalid device {}", selector)))?;
+            let setting = match (words.next(), words.next()) {
+                (Some("layout"), Some(name)) => Setting::Layout(
+                    KeyboardLayout::from_name(name)
+                        .ok_or_else(|| invalid(&format!("Unknown layout {}", name)))?,
+                ),
+                (Some("layout"), None) => return Err(invalid("Missing value")),
+                (Some(setting), _) => return Err(invalid(&format!("Unknown setting {}", setting))),
+                (None, _) => return Err(invalid("Missing setting")),
+            };
+            if words.next().is_some() {
+                return Err(invalid("Unexpected text after the value"));
+            }
+            entries.push(Entry { selector, setting });
+        }
+        Ok(Self { entries })
+    }
+}
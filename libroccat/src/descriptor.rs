@@ -0,0 +1,217 @@
+use crate::{transport::Transport, Error};
+use log::debug;
+use std::{collections::BTreeMap, fs, path::Path};
+
+const ITEM_TYPE_MAIN: u8 = 0;
+const ITEM_TYPE_GLOBAL: u8 = 1;
+const ITEM_TYPE_LOCAL: u8 = 2;
+
+const MAIN_INPUT: u8 = 0x8;
+const MAIN_OUTPUT: u8 = 0x9;
+const MAIN_COLLECTION: u8 = 0xa;
+const MAIN_FEATURE: u8 = 0xb;
+const MAIN_END_COLLECTION: u8 = 0xc;
+
+const GLOBAL_USAGE_PAGE: u8 = 0x0;
+const GLOBAL_REPORT_SIZE: u8 = 0x7;
+const GLOBAL_REPORT_ID: u8 = 0x8;
+const GLOBAL_REPORT_COUNT: u8 = 0x9;
+const GLOBAL_PUSH: u8 = 0xa;
+const GLOBAL_POP: u8 = 0xb;
+
+const LOCAL_USAGE: u8 = 0x0;
+
+const COLLECTION_APPLICATION: u32 = 0x01;
+
+const LONG_ITEM_PREFIX: u8 = 0xfe;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ReportKind {
+    Input,
+    Output,
+    Feature,
+}
+
+/// A report declared by a descriptor
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ReportInfo {
+    /// Report ID, 0 if the interface doesn't use report IDs
+    pub id: u8,
+    pub kind: ReportKind,
+    /// Length in bytes as seen through hidraw, including the report ID byte if there is one
+    pub len: usize,
+}
+
+/// Usage of a top level application collection
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ApplicationUsage {
+    pub usage_page: u16,
+    pub usage: u16,
+}
+
+/// The reports and application collections of a parsed HID report descriptor
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ReportDescriptor {
+    pub reports: Vec<ReportInfo>,
+    pub applications: Vec<ApplicationUsage>,
+}
+
+#[derive(Copy, Clone, Default)]
+struct GlobalState {
+    usage_page: u16,
+    report_size: u32,
+    report_count: u32,
+    report_id: u8,
+}
+
+impl ReportDescriptor {
+    /// Reads the descriptor of a `/dev/hidraw*` node from sysfs
+    pub fn from_sysfs<P: AsRef<Path>>(hidraw_path: P) -> Result<Self, Error> {
+        Self::parse(&read_sysfs(hidraw_path.as_ref())?)
+    }
+
+    /// Parses a raw report descriptor
+    pub fn parse(bytes: &[u8]) -> Result<Self, Error> {
+        let mut bits = BTreeMap::<(ReportKind, u8), u32>::new();
+        let mut applications = Vec::new();
+        let mut global = GlobalState::default();
+        let mut global_stack = Vec::new();
+        let mut usage = None;
+        let mut depth = 0usize;
+
+        let mut position = 0;
+        while position < bytes.len() {
+            let prefix = bytes[position];
+            if prefix == LONG_ITEM_PREFIX {
+                let data_len = *bytes.get(position + 1).ok_or_else(|| malformed(position))?;
+                position += 3 + data_len as usize;
+                continue;
+            }
+
+            let data_len = match prefix & 0x03 {
+                3 => 4,
+                len => len as usize,
+            };
+            let data = bytes
+                .get(position + 1..position + 1 + data_len)
+                .ok_or_else(|| malformed(position))?;
+            let value = data
+                .iter()
+                .rev()
+                .fold(0u32, |value, b| (value << 8) | u32::from(*b));
+            let (item_type, tag) = ((prefix >> 2) & 0x03, prefix >> 4);
+
+            match (item_type, tag) {
+                (ITEM_TYPE_MAIN, MAIN_INPUT)
+                | (ITEM_TYPE_MAIN, MAIN_OUTPUT)
+                | (ITEM_TYPE_MAIN, MAIN_FEATURE) => {
+                    let kind = match tag {
+                        MAIN_INPUT => ReportKind::Input,
+                        MAIN_OUTPUT => ReportKind::Output,
+                        _ => ReportKind::Feature,
+                    };
+                    *bits.entry((kind, global.report_id)).or_default() +=
+                        global.report_size * global.report_count;
+                    usage = None;
+                }
+                (ITEM_TYPE_MAIN, MAIN_COLLECTION) => {
+                    if depth == 0 && value == COLLECTION_APPLICATION {
+                        applications.push(ApplicationUsage {
+                            usage_page: global.usage_page,
+                            usage: usage.unwrap_or_default(),
+                        });
+                    }
+                    depth += 1;
+                    usage = None;
+                }
+                (ITEM_TYPE_MAIN, MAIN_END_COLLECTION) => {
+                    depth = depth.checked_sub(1).ok_or_else(|| malformed(position))?;
+                }
+                (ITEM_TYPE_GLOBAL, GLOBAL_USAGE_PAGE) => global.usage_page = value as u16,
+                (ITEM_TYPE_GLOBAL, GLOBAL_REPORT_SIZE) => global.report_size = value,
+                (ITEM_TYPE_GLOBAL, GLOBAL_REPORT_ID) => global.report_id = value as u8,
+                (ITEM_TYPE_GLOBAL, GLOBAL_REPORT_COUNT) => global.report_count = value,
+                (ITEM_TYPE_GLOBAL, GLOBAL_PUSH) => global_stack.push(global),
+                (ITEM_TYPE_GLOBAL, GLOBAL_POP) => {
+                    global = global_stack.pop().ok_or_else(|| malformed(position))?;
+                }
+                (ITEM_TYPE_LOCAL, LOCAL_USAGE) => {
+                    // Only the first usage before a collection names it
+                    usage.get_or_insert(value as u16);
+                }
+                _ => (),
+            }
+
+            position += 1 + data_len;
+        }
+
+        let reports = bits
+            .into_iter()
+            .map(|((kind, id), bits)| ReportInfo {
+                id,
+                kind,
+                len: (bits as usize).div_ceil(8) + if id == 0 { 0 } else { 1 },
+            })
+            .collect();
+
+        Ok(Self {
+            reports,
+            applications,
+        })
+    }
+
+    /// Looks up a report by kind and ID
+    pub fn report(&self, kind: ReportKind, id: u8) -> Option<&ReportInfo> {
+        self.reports
+            .iter()
+            .find(|report| report.kind == kind && report.id == id)
+    }
+
+    /// Fails with `Error::ReportLayout` unless the descriptor declares exactly this report
+    pub fn check_report(&self, kind: ReportKind, id: u8, len: usize) -> Result<(), Error> {
+        match self.report(kind, id) {
+            Some(report) if report.len == len => Ok(()),
+            report => Err(Error::ReportLayout {
+                kind,
+                report_id: id,
+                expected: len,
+                actual: report.map(|report| report.len),
+            }),
+        }
+    }
+}
+
+/// Checks that an interface declares each `(report ID, length)` with the given kind
+///
+/// Interfaces that can't provide a descriptor are accepted as they are.
+pub fn verify_reports<T: Transport + ?Sized>(
+    interface: &T,
+    kind: ReportKind,
+    reports: &[(u8, usize)],
+) -> Result<(), Error> {
+    let descriptor = match interface.report_descriptor()? {
+        Some(bytes) => ReportDescriptor::parse(&bytes)?,
+        None => {
+            debug!("Interface has no report descriptor, skipping layout checks");
+            return Ok(());
+        }
+    };
+
+    for &(id, len) in reports {
+        descriptor.check_report(kind, id, len)?;
+    }
+    Ok(())
+}
+
+/// Reads the raw descriptor of `/dev/hidrawN` from `/sys/class/hidraw/hidrawN`
+pub(crate) fn read_sysfs(hidraw_path: &Path) -> Result<Vec<u8>, Error> {
+    let name = hidraw_path.file_name().ok_or(Error::DeviceNotFound)?;
+    let path = Path::new("/sys/class/hidraw")
+        .join(name)
+        .join("device/report_descriptor");
+    Ok(fs::read(path)?)
+}
+
+fn malformed(position: usize) -> Error {
+    Error::MalformedDescriptor { position }
+}
@@ -1,3 +1,21 @@
+use crate::Error;
+use bitflags::bitflags;
+use hidraw_derive::ReportField;
+
+bitflags! {
+    /// Modifier keys held down while a shortcut's key is sent
+    #[derive(Default)]
+    pub struct ButtonModifiers: u8 {
+        const SHIFT = 0x01;
+        const CTRL = 0x02;
+        const ALT = 0x04;
+        const SUPER = 0x08;
+    }
+}
+
+/// A single modifier key, from before modifiers could be combined as flags
+#[deprecated(note = "use the `ButtonModifiers` flags")]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 #[repr(u8)]
 pub enum ButtonModifier {
     None = 0,
@@ -7,11 +25,327 @@ pub enum ButtonModifier {
     Super = 8,
 }
 
-#[derive(Clone, Debug)]
+#[allow(deprecated)]
+impl From<ButtonModifier> for ButtonModifiers {
+    fn from(modifier: ButtonModifier) -> Self {
+        Self::from_bits_truncate(modifier as u8)
+    }
+}
+
+/// Devices that number button functions the same way
+///
+/// Keyboards store plain keys as their HID usage in `ButtonConfig::type_`, so their functions
+/// are numbered above the HID keyboard page.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ButtonFamily {
+    Keyboard,
+    Mouse,
+}
+
+impl ButtonFamily {
+    pub fn name(self) -> &'static str {
+        match self {
+            ButtonFamily::Keyboard => "keyboard",
+            ButtonFamily::Mouse => "mouse",
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum MultimediaKey {
+    OpenPlayer,
+    PreviousTrack,
+    NextTrack,
+    PlayPause,
+    Stop,
+    Mute,
+    VolumeUp,
+    VolumeDown,
+}
+
+/// Function of a button or key, as stored in `ButtonConfig::type_`
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ButtonType {
+    Disabled,
+    /// Sends a HID keyboard usage, keyboards only
+    Key(u8),
+    /// Sends `ButtonConfig::key` with `ButtonConfig::modifier` held
+    Shortcut,
+    /// Plays the macro stored for the button itself, mice only
+    Macro,
+    /// Plays the macro stored in a slot starting at 1, keyboards only
+    MacroSlot(u8),
+    /// Starts the program set up in the driver
+    Quicklaunch,
+    Timer,
+    TimerStop,
+    ProfileCycle,
+    ProfileUp,
+    ProfileDown,
+    /// Switches to the Easyshift layer while held
+    Easyshift,
+    /// Toggles the Easyshift layer
+    EasyshiftLock,
+    /// Holds Easyshift on other Roccat Talk devices
+    TalkEasyshift,
+    /// Toggles Easyshift on other Roccat Talk devices
+    TalkEasyshiftLock,
+    /// Holds Easyshift on this and other Roccat Talk devices
+    TalkBothEasyshift,
+    OpenDriver,
+    WindowsKey,
+    Multimedia(MultimediaKey),
+    AppEmail,
+    AppBrowser,
+    AppCalculator,
+    AppMyComputer,
+    Click,
+    Menu,
+    UniversalScrolling,
+    DoubleClick,
+    BrowserForward,
+    BrowserBackward,
+    TiltLeft,
+    TiltRight,
+    ScrollUp,
+    ScrollDown,
+    CpiCycle,
+    CpiUp,
+    CpiDown,
+    SensitivityCycle,
+    SensitivityUp,
+    SensitivityDown,
+    /// A function the device ships with whose meaning isn't known, kept as its code
+    Unknown(u8),
+}
+
+// Keyboard codes are the Ryos key types and mouse codes the Tyon button types of roccat-tools
+// (erazor_de's code). The factory defaults the Ryos MK FX reports for its `Keys*` tables confirm
+// some of the keyboard codes, see `ryosmkfx::DEFAULT_KEYS_FUNCTION`, `DEFAULT_KEYS_MACRO` and
+// `DEFAULT_KEYS_THUMBSTER`. The others are unconfirmed, see `ButtonType::is_confirmed`.
+
+/// Number of keyboard macro slots, which are numbered starting at 1
+///
+/// One for each of M1–M5 on the normal and the Easyshift layer, which play slots 1–10 from the
+/// factory.
+pub const MACRO_SLOT_COUNT: u8 = 10;
+
+/// `MultimediaKey::ALL` in order, the factory defaults use each of them: 0xd2, 0xd1 and 0xd0 on
+/// Fn+F1–F3, 0xcc, 0xcf, 0xce and 0xcd on Fn+F5–F8 and 0xcb on Easyshift+T2
+const KEYBOARD_MULTIMEDIA: u8 = 0xcb;
+/// Slots 1–10 in order, the factory functions of M1–M5 and Easyshift+M1–M5
+const KEYBOARD_MACRO_SLOT: u8 = 0xd3;
+
+/// Functions besides keys, multimedia keys and macro slots, with the factory default using them
+#[rustfmt::skip]
+const KEYBOARD_TYPES: [(ButtonType, u8); 18] = [
+    (ButtonType::Disabled, 0x00),    // Fn+Print Screen, Fn+Scroll Lock
+    (ButtonType::ProfileUp, 0xb0),   // T3
+    (ButtonType::ProfileDown, 0xb1), // T1
+    (ButtonType::ProfileCycle, 0xb2),
+    (ButtonType::Quicklaunch, 0xb3),
+    (ButtonType::Timer, 0xb4),
+    (ButtonType::TimerStop, 0xb5),
+    (ButtonType::OpenDriver, 0xb6),
+    (ButtonType::AppEmail, 0xb7),
+    (ButtonType::AppBrowser, 0xb8),
+    (ButtonType::AppCalculator, 0xb9),
+    (ButtonType::AppMyComputer, 0xba),
+    (ButtonType::TalkEasyshift, 0xbb),
+    (ButtonType::TalkEasyshiftLock, 0xbc),
+    (ButtonType::TalkBothEasyshift, 0xbd),
+    (ButtonType::Shortcut, 0xbf),
+    (ButtonType::Easyshift, 0xdd),   // T2
+    (ButtonType::EasyshiftLock, 0xde),
+];
+
+/// Functions of `KEYBOARD_TYPES` the factory defaults use
+const KEYBOARD_CONFIRMED: [ButtonType; 4] = [
+    ButtonType::Disabled,
+    ButtonType::ProfileUp,
+    ButtonType::ProfileDown,
+    ButtonType::Easyshift,
+];
+
+/// Factory defaults whose function isn't known, which decode as `ButtonType::Unknown`
+///
+/// 0x82 and 0xa3 are HID usages, but the Fn layer stores them next to Roccat functions, so they
+/// aren't taken as keys.
+#[rustfmt::skip]
+const KEYBOARD_UNKNOWN: [u8; 7] = [
+    0x82, // Fn+Pause
+    0xa3, // Fn+F11
+    0xa7, // Fn+F12
+    0xc0, // Easyshift+T3
+    0xc1, // Fn+F4
+    0xc2, // Fn+F9
+    0xc3, // Fn+F10
+];
+
+/// Functions of mouse buttons, which no device confirms yet
+#[rustfmt::skip]
+const MOUSE_TYPES: [(ButtonType, u8); 44] = [
+    (ButtonType::Click, 0x01),
+    (ButtonType::Menu, 0x02),
+    (ButtonType::UniversalScrolling, 0x03),
+    (ButtonType::DoubleClick, 0x04),
+    (ButtonType::Shortcut, 0x05),
+    (ButtonType::Disabled, 0x06),
+    (ButtonType::BrowserForward, 0x07),
+    (ButtonType::BrowserBackward, 0x08),
+    (ButtonType::TiltLeft, 0x09),
+    (ButtonType::TiltRight, 0x0a),
+    (ButtonType::ScrollUp, 0x0d),
+    (ButtonType::ScrollDown, 0x0e),
+    (ButtonType::Quicklaunch, 0x0f),
+    (ButtonType::ProfileCycle, 0x10),
+    (ButtonType::ProfileUp, 0x11),
+    (ButtonType::ProfileDown, 0x12),
+    (ButtonType::CpiCycle, 0x14),
+    (ButtonType::CpiUp, 0x15),
+    (ButtonType::CpiDown, 0x16),
+    (ButtonType::SensitivityCycle, 0x17),
+    (ButtonType::SensitivityUp, 0x18),
+    (ButtonType::SensitivityDown, 0x19),
+    (ButtonType::WindowsKey, 0x1a),
+    (ButtonType::OpenDriver, 0x1b),
+    (ButtonType::Multimedia(MultimediaKey::OpenPlayer), 0x20),
+    (ButtonType::Multimedia(MultimediaKey::NextTrack), 0x21),
+    (ButtonType::Multimedia(MultimediaKey::PreviousTrack), 0x22),
+    (ButtonType::Multimedia(MultimediaKey::PlayPause), 0x23),
+    (ButtonType::Multimedia(MultimediaKey::Stop), 0x24),
+    (ButtonType::Multimedia(MultimediaKey::Mute), 0x25),
+    (ButtonType::Multimedia(MultimediaKey::VolumeUp), 0x26),
+    (ButtonType::Multimedia(MultimediaKey::VolumeDown), 0x27),
+    (ButtonType::Macro, 0x30),
+    (ButtonType::Timer, 0x31),
+    (ButtonType::TimerStop, 0x32),
+    (ButtonType::Easyshift, 0x41),
+    (ButtonType::EasyshiftLock, 0x42),
+    (ButtonType::TalkEasyshift, 0x60),
+    (ButtonType::TalkEasyshiftLock, 0x61),
+    (ButtonType::TalkBothEasyshift, 0x62),
+    (ButtonType::AppEmail, 0x70),
+    (ButtonType::AppBrowser, 0x71),
+    (ButtonType::AppCalculator, 0x72),
+    (ButtonType::AppMyComputer, 0x73),
+];
+
+impl MultimediaKey {
+    pub const ALL: [MultimediaKey; 8] = [
+        MultimediaKey::OpenPlayer,
+        MultimediaKey::PreviousTrack,
+        MultimediaKey::NextTrack,
+        MultimediaKey::PlayPause,
+        MultimediaKey::Stop,
+        MultimediaKey::Mute,
+        MultimediaKey::VolumeUp,
+        MultimediaKey::VolumeDown,
+    ];
+}
+
+impl ButtonType {
+    /// Looks up the function a device family stores as `code`
+    pub fn from_code(family: ButtonFamily, code: u8) -> Result<Self, Error> {
+        let invalid = Error::InvalidValue {
+            type_name: "ButtonType",
+            value: code,
+        };
+        match family {
+            ButtonFamily::Keyboard => match code {
+                code if is_key_usage(code) => Ok(ButtonType::Key(code)),
+                code if (KEYBOARD_MULTIMEDIA..KEYBOARD_MACRO_SLOT).contains(&code) => {
+                    Ok(ButtonType::Multimedia(
+                        MultimediaKey::ALL[(code - KEYBOARD_MULTIMEDIA) as usize],
+                    ))
+                }
+                code if (KEYBOARD_MACRO_SLOT..KEYBOARD_MACRO_SLOT + MACRO_SLOT_COUNT)
+                    .contains(&code) =>
+                {
+                    Ok(ButtonType::MacroSlot(code - KEYBOARD_MACRO_SLOT + 1))
+                }
+                code if KEYBOARD_UNKNOWN.contains(&code) => Ok(ButtonType::Unknown(code)),
+                code => lookup(&KEYBOARD_TYPES, code).ok_or(invalid),
+            },
+            ButtonFamily::Mouse => lookup(&MOUSE_TYPES, code).ok_or(invalid),
+        }
+    }
+
+    /// Gets the code a device family stores the function as, failing for functions it lacks
+    pub fn code(self, family: ButtonFamily) -> Result<u8, Error> {
+        let unsupported = Error::UnsupportedButtonType {
+            button_type: self,
+            family,
+        };
+        match (family, self) {
+            (ButtonFamily::Keyboard, ButtonType::Key(usage)) if is_key_usage(usage) => Ok(usage),
+            (ButtonFamily::Keyboard, ButtonType::Multimedia(key)) => {
+                Ok(KEYBOARD_MULTIMEDIA + key as u8)
+            }
+            (ButtonFamily::Keyboard, ButtonType::MacroSlot(slot)) => {
+                Error::ensure_range("Macro slot", slot, 1..=MACRO_SLOT_COUNT as i64)?;
+                Ok(KEYBOARD_MACRO_SLOT + slot - 1)
+            }
+            (ButtonFamily::Keyboard, ButtonType::Unknown(code))
+                if KEYBOARD_UNKNOWN.contains(&code) =>
+            {
+                Ok(code)
+            }
+            (_, ButtonType::Key(_))
+            | (_, ButtonType::MacroSlot(_))
+            | (_, ButtonType::Unknown(_)) => Err(unsupported),
+            (ButtonFamily::Keyboard, button_type) => {
+                code(&KEYBOARD_TYPES, button_type).ok_or(unsupported)
+            }
+            (ButtonFamily::Mouse, button_type) => {
+                code(&MOUSE_TYPES, button_type).ok_or(unsupported)
+            }
+        }
+    }
+
+    /// Whether a device of the family is known to store the function under its code
+    ///
+    /// Only the factory defaults of the Ryos MK FX confirm codes so far. Drivers read back
+    /// functions that aren't confirmed after writing them, see `Error::UnconfirmedButtonCode`.
+    pub fn is_confirmed(self, family: ButtonFamily) -> bool {
+        match (family, self) {
+            (ButtonFamily::Keyboard, ButtonType::Key(_))
+            | (ButtonFamily::Keyboard, ButtonType::Multimedia(_))
+            | (ButtonFamily::Keyboard, ButtonType::MacroSlot(_))
+            | (ButtonFamily::Keyboard, ButtonType::Unknown(_)) => true,
+            (ButtonFamily::Keyboard, button_type) => KEYBOARD_CONFIRMED.contains(&button_type),
+            (ButtonFamily::Mouse, _) => false,
+        }
+    }
+}
+
+/// Usages of the HID keyboard page that keyboards store as plain keys
+///
+/// The HID Usage Tables define keys at 0x04–0xa4 and modifiers at 0xe0–0xe7 on the keyboard page,
+/// except for the usages the Ryos MK FX uses for functions, see `KEYBOARD_UNKNOWN`.
+pub fn is_key_usage(value: u8) -> bool {
+    matches!(value, 0x04..=0xa4 | 0xe0..=0xe7) && !KEYBOARD_UNKNOWN.contains(&value)
+}
+
+fn lookup(table: &[(ButtonType, u8)], code: u8) -> Option<ButtonType> {
+    table
+        .iter()
+        .find(|&&(_, candidate)| candidate == code)
+        .map(|&(button_type, _)| button_type)
+}
+
+fn code(table: &[(ButtonType, u8)], button_type: ButtonType) -> Option<u8> {
+    table
+        .iter()
+        .find(|&&(candidate, _)| candidate == button_type)
+        .map(|&(_, code)| code)
+}
+
+#[derive(ReportField, Copy, Clone, Debug, PartialEq, Eq)]
 #[repr(C, packed)]
 pub struct ButtonConfig {
     pub type_: u8,
-    pub modifier: u8, // enum ButtonModifier
+    pub modifier: u8, // ButtonModifiers
     pub key: u8,
 }
 
@@ -23,4 +357,71 @@ impl ButtonConfig {
             key,
         }
     }
+
+    /// Function without a key, failing for shortcuts and functions the family lacks
+    pub fn from_type(family: ButtonFamily, button_type: ButtonType) -> Result<Self, Error> {
+        if button_type == ButtonType::Shortcut {
+            return Err(Error::UnsupportedButtonType {
+                button_type,
+                family,
+            });
+        }
+        Ok(Self::new(button_type.code(family)?, 0, 0))
+    }
+
+    /// Sends a HID keyboard usage with modifiers held
+    pub fn shortcut(
+        family: ButtonFamily,
+        modifiers: ButtonModifiers,
+        usage: u8,
+    ) -> Result<Self, Error> {
+        Ok(Self::new(
+            ButtonType::Shortcut.code(family)?,
+            modifiers.bits(),
+            usage,
+        ))
+    }
+
+    /// Decodes the function, failing for codes the family doesn't know
+    pub fn button_type(&self, family: ButtonFamily) -> Result<ButtonType, Error> {
+        ButtonType::from_code(family, self.type_)
+    }
+
+    /// Whether the family is known to store the function, `false` for codes it doesn't know
+    pub fn is_confirmed(&self, family: ButtonFamily) -> bool {
+        matches!(self.button_type(family), Ok(button_type) if button_type.is_confirmed(family))
+    }
+
+    /// Decodes the modifiers, failing for unknown bits
+    pub fn modifiers(&self) -> Result<ButtonModifiers, Error> {
+        ButtonModifiers::from_bits(self.modifier).ok_or(Error::InvalidValue {
+            type_name: "ButtonModifiers",
+            value: self.modifier,
+        })
+    }
+}
+
+/// Reads back buttons with codes that aren't confirmed, see `ButtonType::is_confirmed`
+///
+/// `read` is only called if `written` holds such a button. Fails if the device stored one of
+/// them differently, e.g. because its firmware doesn't know the code.
+pub(crate) fn check_stored<S: AsRef<[ButtonConfig]>>(
+    family: ButtonFamily,
+    written: &[ButtonConfig],
+    read: impl FnOnce() -> Result<S, Error>,
+) -> Result<(), Error> {
+    if written.iter().all(|config| config.is_confirmed(family)) {
+        return Ok(());
+    }
+    let stored = read()?;
+    for (written, stored) in written.iter().zip(stored.as_ref()) {
+        if written != stored && !written.is_confirmed(family) {
+            return Err(Error::UnconfirmedButtonCode {
+                family,
+                written: written.type_,
+                stored: stored.type_,
+            });
+        }
+    }
+    Ok(())
 }
@@ -0,0 +1,108 @@
+use crate::{descriptor::ReportDescriptor, Error};
+use log::debug;
+use std::{fs, path::PathBuf};
+
+const USAGE_PAGE_GENERIC_DESKTOP: u16 = 0x01;
+const USAGE_PAGE_VENDOR_MIN: u16 = 0xff00;
+
+/// A hidraw node together with what udev and sysfs tell about it
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct HidrawNode {
+    pub path: PathBuf,
+    /// `bInterfaceNumber` of the USB interface the node belongs to
+    pub interface_number: Option<u8>,
+    /// Usage pages of the application collections in its report descriptor, empty if unknown
+    pub usage_pages: Vec<u16>,
+}
+
+impl HidrawNode {
+    /// Describes a hidraw device found through udev, `None` if it has no device node
+    pub(crate) fn from_udev(device: &libudev::Device) -> Option<Self> {
+        let path = device.devnode()?.to_path_buf();
+
+        // The USB interface is an ancestor of the hidraw node in sysfs
+        let interface_number = device
+            .syspath()
+            .ancestors()
+            .filter_map(|path| fs::read_to_string(path.join("bInterfaceNumber")).ok())
+            .next()
+            .and_then(|number| u8::from_str_radix(number.trim(), 16).ok());
+
+        let usage_pages = match ReportDescriptor::from_sysfs(&path) {
+            Ok(descriptor) => descriptor
+                .applications
+                .iter()
+                .map(|application| application.usage_page)
+                .collect(),
+            Err(error) => {
+                debug!(
+                    "Can't read report descriptor of {}: {}",
+                    path.display(),
+                    error
+                );
+                Vec::new()
+            }
+        };
+
+        Some(Self {
+            path,
+            interface_number,
+            usage_pages,
+        })
+    }
+}
+
+/// Kind of usage page an interface's application collections use
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum UsagePage {
+    /// Keyboards and mice
+    GenericDesktop,
+    /// Anything in the vendor defined range 0xff00..=0xffff
+    Vendor,
+}
+
+impl UsagePage {
+    pub fn matches(self, usage_page: u16) -> bool {
+        match self {
+            UsagePage::GenericDesktop => usage_page == USAGE_PAGE_GENERIC_DESKTOP,
+            UsagePage::Vendor => usage_page >= USAGE_PAGE_VENDOR_MIN,
+        }
+    }
+}
+
+/// How one of a device's interfaces is recognized among its hidraw nodes
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct InterfaceSpec {
+    pub name: &'static str,
+    pub interface_number: u8,
+    pub usage_page: UsagePage,
+}
+
+impl InterfaceSpec {
+    /// The interface number has to match, the usage page only if the descriptor was readable
+    pub fn matches(&self, node: &HidrawNode) -> bool {
+        node.interface_number == Some(self.interface_number)
+            && (node.usage_pages.is_empty()
+                || node
+                    .usage_pages
+                    .iter()
+                    .any(|&usage_page| self.usage_page.matches(usage_page)))
+    }
+}
+
+/// Picks the node for each of `specs`, returning their paths in the same order
+pub fn classify(nodes: &[HidrawNode], specs: &[InterfaceSpec]) -> Result<Vec<PathBuf>, Error> {
+    specs
+        .iter()
+        .map(|spec| {
+            nodes
+                .iter()
+                .find(|node| spec.matches(node))
+                .map(|node| node.path.clone())
+                .ok_or(Error::InterfaceNotFound {
+                    name: spec.name,
+                    interface_number: spec.interface_number,
+                })
+        })
+        .collect()
+}
@@ -1,10 +1,39 @@
 pub mod button;
+pub mod interface;
 pub mod ryosmkfx;
+mod traits;
 pub mod tyon;
 
+pub use self::traits::*;
+
 use self::{ryosmkfx::RyosMkFx, tyon::Tyon};
-use failure::Error;
-use std::convert::TryInto;
+use crate::Error;
+use std::{convert::TryInto, fmt};
+
+/// Identifies an attached USB device
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct DeviceId {
+    /// Position on the USB bus like `1-4.2`, stable as long as the device stays in the same port
+    pub bus_path: String,
+    /// USB serial number, if the device reports one
+    pub serial: Option<String>,
+    pub vendor_id: u16,
+    pub product_id: u16,
+}
+
+impl fmt::Display for DeviceId {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            fmt,
+            "{} ({:04x}:{:04x}",
+            self.bus_path, self.vendor_id, self.product_id
+        )?;
+        if let Some(ref serial) = self.serial {
+            write!(fmt, ", serial {}", serial)?;
+        }
+        write!(fmt, ")")
+    }
+}
 
 pub enum Device {
     RyosMkFx(RyosMkFx),
@@ -12,25 +41,69 @@ pub enum Device {
 }
 
 impl Device {
-    pub fn get_common_name(&self) -> &str {
+    /// The model as a `RoccatDevice`, which `Device` forwards everything to
+    pub fn as_roccat_device(&self) -> &dyn RoccatDevice {
         match *self {
-            Device::RyosMkFx(_) => RyosMkFx::get_common_name(),
-            Device::Tyon(_) => Tyon::get_common_name(),
+            Device::RyosMkFx(ref device) => device,
+            Device::Tyon(ref device) => device,
         }
     }
 
-    pub fn get_profile(&self) -> Result<u8, Error> {
+    pub fn set_id(&mut self, id: DeviceId) {
         match *self {
-            Device::RyosMkFx(ref device) => device.get_profile(),
-            Device::Tyon(ref device) => device.get_profile(),
+            Device::RyosMkFx(ref mut device) => device.set_id(id),
+            Device::Tyon(ref mut device) => device.set_id(id),
         }
     }
 
-    pub fn set_profile(&self, profile: u8) -> Result<(), Error> {
-        match *self {
-            Device::RyosMkFx(ref device) => device.set_profile(profile),
-            Device::Tyon(ref device) => device.set_profile(profile),
-        }
+    pub fn get_common_name(&self) -> &str {
+        self.name()
+    }
+}
+
+impl RoccatDevice for Device {
+    fn name(&self) -> &str {
+        self.as_roccat_device().name()
+    }
+
+    fn id(&self) -> Option<&DeviceId> {
+        self.as_roccat_device().id()
+    }
+
+    fn firmware_info(&self) -> Result<Option<FirmwareInfo>, Error> {
+        self.as_roccat_device().firmware_info()
+    }
+
+    fn profile_count(&self) -> u8 {
+        self.as_roccat_device().profile_count()
+    }
+
+    fn get_profile(&self) -> Result<u8, Error> {
+        self.as_roccat_device().get_profile()
+    }
+
+    fn set_profile(&self, profile: u8) -> Result<(), Error> {
+        self.as_roccat_device().set_profile(profile)
+    }
+
+    fn as_per_key_lighting(&self) -> Option<&dyn PerKeyLighting> {
+        self.as_roccat_device().as_per_key_lighting()
+    }
+
+    fn as_key_remapping(&self) -> Option<&dyn KeyRemapping> {
+        self.as_roccat_device().as_key_remapping()
+    }
+
+    fn as_dpi_control(&self) -> Option<&dyn DpiControl> {
+        self.as_roccat_device().as_dpi_control()
+    }
+
+    fn as_event_source(&self) -> Option<&dyn EventSource> {
+        self.as_roccat_device().as_event_source()
+    }
+
+    fn as_key_labels(&self) -> Option<&dyn KeyLabels> {
+        self.as_roccat_device().as_key_labels()
     }
 }
 
@@ -56,7 +129,8 @@ impl TryInto<tyon::Interface> for Interface {
     fn try_into(self) -> Result<tyon::Interface, Error> {
         match self {
             Interface::Primary => Ok(tyon::Interface::Primary),
-            Interface::Events => Ok(tyon::Interface::Events),
+            // Tyon events aren't read, so the interface isn't opened
+            Interface::Events => Err(Error::MissingInterface { index: 1 }),
         }
     }
 }
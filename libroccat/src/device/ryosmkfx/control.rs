@@ -1,9 +1,15 @@
-use failure::{bail, Error};
-use hidraw_derive::{HidrawRead, HidrawWrite};
-use std::fs::File;
+use crate::{
+    report::ReportField,
+    transport::{default_write_check_timeout, poll_until, CancelToken, Transport},
+    Error,
+};
+use hidraw_derive::{HidrawRead, HidrawWrite, ReportField};
+use std::time::Duration;
 
+#[derive(ReportField, Copy, Clone, Debug, PartialEq)]
 #[repr(u8)]
 pub enum ControlRequest {
+    Macro = 0x10, // First macro slot, followed by the others, unconfirmed, see MacroReport
     KeysPrimary = 0xa0,
     KeysFunction = 0xa1,
     KeysMacro = 0xa2,
@@ -18,6 +24,7 @@ pub enum ControlRequest {
     Request12 = 0xf0, // idk, from erazor_de's code
 }
 
+#[derive(ReportField, Copy, Clone, Debug, PartialEq)]
 #[repr(u8)]
 pub enum ControlStatus {
     Critical0 = 0x00,
@@ -27,41 +34,43 @@ pub enum ControlStatus {
     Critical1 = 0x04, // used by Ryos MK
 }
 
-#[derive(HidrawRead, HidrawWrite)]
+#[derive(ReportField, HidrawRead, HidrawWrite)]
 #[repr(C, packed)]
+#[hidraw_report(id = 0x04, len = 3, size = "none")]
 pub struct Control {
-    #[hidraw_constant = "0x04"]
-    _report_id: u8,
     pub value: u8,
     pub request: u8,
 }
 
 impl Control {
     pub fn new(value: u8, request: u8) -> Self {
-        Self {
-            value,
-            request,
-            ..unsafe { ::std::mem::uninitialized() }
-        }
+        Self { value, request }
     }
 
-    pub fn check_write(interface: &File) -> Result<(), Error> {
-        unsafe {
-            loop {
-                use std::{thread::sleep, time::Duration};
-
-                sleep(Duration::from_millis(50));
+    /// Waits for the device to finish processing a request, using the default timeout
+    pub fn check_write<T: Transport + ?Sized>(interface: &T) -> Result<(), Error> {
+        Self::check_write_with(interface, default_write_check_timeout(), None)
+    }
 
-                let control = Self::read(interface)?;
-                match ::std::mem::transmute(control.value) {
-                    ControlStatus::Ok => return Ok(()),
-                    ControlStatus::Busy => (),
-                    ControlStatus::Critical0 | ControlStatus::Critical1 => {
-                        bail!("Got critical status")
-                    }
-                    ControlStatus::Invalid => bail!("Got unknown status"),
+    /// Waits for the device to finish processing a request
+    ///
+    /// Fails with `Error::DeviceBusy` if the device is still busy after `timeout` and with
+    /// `Error::Cancelled` if `cancel` gets cancelled.
+    pub fn check_write_with<T: Transport + ?Sized>(
+        interface: &T,
+        timeout: Option<Duration>,
+        cancel: Option<&CancelToken>,
+    ) -> Result<(), Error> {
+        poll_until(timeout, cancel, || {
+            let control = Self::read(interface)?;
+            match ControlStatus::decode(&[control.value])? {
+                ControlStatus::Ok => Ok(true),
+                ControlStatus::Busy => Ok(false),
+                status @ ControlStatus::Critical0 | status @ ControlStatus::Critical1 => {
+                    Err(Error::CriticalStatus(status))
                 }
+                ControlStatus::Invalid => Err(Error::Rejected),
             }
-        }
+        })
     }
 }
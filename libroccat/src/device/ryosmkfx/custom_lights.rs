@@ -1,9 +1,9 @@
 use super::{hardware_color::*, sdk::*};
 use bitfield::*;
-use hidraw_derive::{HidrawRead, HidrawWrite};
+use hidraw_derive::{HidrawRead, HidrawWrite, ReportField};
 use std::fmt;
 
-#[derive(Copy, Clone)]
+#[derive(ReportField, Copy, Clone)]
 #[repr(C, packed)]
 pub struct LightLayer {
     states: [u8; 15],
@@ -178,9 +178,9 @@ impl LightLayer {
         let mut means_red = [0u8; 7];
         let mut means_green = [0u8; 7];
         let mut means_blue = [0u8; 7];
-        let mut cluster_red = [u8::max_value(); 120];
-        let mut cluster_green = [u8::max_value(); 120];
-        let mut cluster_blue = [u8::max_value(); 120];
+        let mut cluster_red = [u8::MAX; 120];
+        let mut cluster_green = [u8::MAX; 120];
+        let mut cluster_blue = [u8::MAX; 120];
 
         for i in 0..120 {
             values_red[i] = data.keys[i].red;
@@ -275,8 +275,8 @@ fn update_means(values: [u8; 120], means: &mut [u8; 7], cluster: [u8; 120], data
     }
 
     for i in 0..7 {
-        if counts[i] > 0 {
-            means[i] = (mean_sums[i] / counts[i]) as u8;
+        if let Some(mean) = mean_sums[i].checked_div(counts[i]) {
+            means[i] = mean as u8;
         }
     }
 }
@@ -295,7 +295,7 @@ fn set_cluster(
             continue;
         }
 
-        let mut smallest_error = isize::max_value();
+        let mut smallest_error = isize::MAX;
         for (j, mean) in means.iter().enumerate().take(7) {
             let error = (values[i] as isize - *mean as isize).abs();
             if error < smallest_error {
@@ -381,13 +381,10 @@ impl LightLayerData {
     }
 }
 
-#[derive(HidrawRead, HidrawWrite, Copy, Clone)]
+#[derive(ReportField, HidrawRead, HidrawWrite, Copy, Clone)]
 #[repr(C, packed)]
+#[hidraw_report(id = 0x18, len = 232)]
 pub struct CustomLights {
-    #[hidraw_constant = "0x18"]
-    _report_id: u8,
-    #[hidraw_constant = "::std::mem::size_of::<Self>() as u8"]
-    _size: u8,
     pub light_layer: LightLayer,
     #[hidraw_bytesum]
     _bytesum: u16,
@@ -397,7 +394,7 @@ impl CustomLights {
     pub fn new(light_layer: LightLayer) -> Self {
         Self {
             light_layer,
-            ..unsafe { ::std::mem::uninitialized() }
+            _bytesum: Default::default(),
         }
     }
 }
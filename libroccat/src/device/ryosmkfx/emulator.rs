@@ -0,0 +1,739 @@
+use super::{
+    effect_index_to_sdk_index, ControlRequest, ControlStatus, EventKeyAction, EventType,
+    LightControlWriteCheck, RyosMkFx, DEFAULT_KEYS_EASYZONE, DEFAULT_KEYS_FUNCTION,
+    DEFAULT_KEYS_MACRO, DEFAULT_KEYS_PRIMARY, DEFAULT_KEYS_THUMBSTER,
+};
+use crate::{
+    device::button::{ButtonConfig, MACRO_SLOT_COUNT},
+    transport::Transport,
+    Error,
+};
+use bitfield::NibbleField;
+#[cfg(feature = "async")]
+use std::task::{Context, Poll, Waker};
+use std::{
+    collections::{HashMap, VecDeque},
+    io,
+    sync::{Arc, Condvar, Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+const PROFILE_COUNT: u8 = 5;
+
+const REPORT_ID_CONTROL: u8 = 0x04;
+const REPORT_ID_PROFILE: u8 = 0x05;
+const REPORT_ID_KEYS_PRIMARY: u8 = 0x06;
+const REPORT_ID_KEYS_FUNCTION: u8 = 0x07;
+const REPORT_ID_KEYS_MACRO: u8 = 0x08;
+const REPORT_ID_KEYS_THUMBSTER: u8 = 0x09;
+const REPORT_ID_KEYS_EXTRA: u8 = 0x0a;
+const REPORT_ID_KEYS_EASYZONE: u8 = 0x0b;
+const REPORT_ID_LIGHTS: u8 = 0x0d;
+const REPORT_ID_MACRO: u8 = 0x0e;
+const REPORT_ID_DEVICE_INFO: u8 = 0x0f;
+const REPORT_ID_LIGHT_CONTROL: u8 = 0x13;
+const REPORT_ID_CUSTOM_LIGHTS: u8 = 0x18;
+const REPORT_ID_EVENT: u8 = 0x03;
+
+/// Reports that are stored once per profile and selected with a `Control` request
+///
+/// (report ID, length, control request, offset of the profile index)
+#[rustfmt::skip]
+const PROFILE_REPORTS: [(u8, usize, u8, usize); 7] = [
+    (REPORT_ID_KEYS_PRIMARY,   125, ControlRequest::KeysPrimary as u8,   2),
+    (REPORT_ID_KEYS_FUNCTION,   95, ControlRequest::KeysFunction as u8,  2),
+    (REPORT_ID_KEYS_MACRO,      35, ControlRequest::KeysMacro as u8,     2),
+    (REPORT_ID_KEYS_THUMBSTER,  23, ControlRequest::KeysThumbster as u8, 2),
+    (REPORT_ID_KEYS_EXTRA,       8, ControlRequest::KeysExtra as u8,     2),
+    (REPORT_ID_KEYS_EASYZONE,  294, ControlRequest::KeysEasyzone as u8,  3),
+    (REPORT_ID_LIGHTS,          32, ControlRequest::Light as u8,         2),
+];
+
+const MACRO_LEN: usize = 2002;
+const CUSTOM_LIGHTS_LEN: usize = 232;
+const DEVICE_INFO_LEN: usize = 7;
+const EVENT_LEN: usize = 5;
+
+/// A software Ryos MK FX
+///
+/// The emulator answers the same feature reports the keyboard does and keeps its settings in
+/// memory, so `RyosMkFx` can be driven without hardware. Status responses of the `Control` and
+/// `LightControl` reports can be scripted to exercise the busy and error paths, and events can
+/// be injected into the Events interface.
+#[derive(Clone)]
+pub struct Emulator {
+    shared: Arc<Shared>,
+}
+
+struct Shared {
+    state: Mutex<State>,
+    event_available: Condvar,
+}
+
+struct State {
+    profile: u8,
+    control_request: u8,
+    control_status: ControlStatus,
+    control_busy_reads: usize,
+    queued_control_statuses: VecDeque<ControlStatus>,
+    selected_profile: u8,
+    profile_reports: HashMap<(u8, u8), Vec<u8>>,
+    selected_macro: u8,
+    /// Macro reports by profile and slot, empty slots aren't stored
+    macros: HashMap<(u8, u8), Vec<u8>>,
+    light_control_state: u8,
+    light_control_status: LightControlWriteCheck,
+    light_control_busy_reads: usize,
+    queued_light_control_statuses: VecDeque<LightControlWriteCheck>,
+    custom_lights: Vec<u8>,
+    device_info: Vec<u8>,
+    busy_reads: usize,
+    /// Button codes stored as disabled, like by a firmware that doesn't know them
+    unknown_button_codes: Vec<u8>,
+    written_reports: Vec<Vec<u8>>,
+    events: VecDeque<[u8; 5]>,
+    /// Tasks waiting for an event in `poll_read_report`
+    #[cfg(feature = "async")]
+    event_wakers: Vec<Waker>,
+    plugged_in: bool,
+    /// Counts plug-ins, so interfaces from before an unplug stay disconnected
+    generation: usize,
+}
+
+impl Default for Emulator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Emulator {
+    /// Creates an emulator with all profiles enabled, the first one active and default settings
+    pub fn new() -> Self {
+        let mut profile_reports = HashMap::new();
+        for profile in 0..PROFILE_COUNT {
+            profile_reports.insert((REPORT_ID_KEYS_PRIMARY, profile), {
+                let mut payload = vec![profile];
+                payload.extend_from_slice(&DEFAULT_KEYS_PRIMARY);
+                build_report(REPORT_ID_KEYS_PRIMARY, 1, &payload)
+            });
+            profile_reports.insert(
+                (REPORT_ID_KEYS_FUNCTION, profile),
+                build_keys_report(REPORT_ID_KEYS_FUNCTION, 1, profile, &DEFAULT_KEYS_FUNCTION),
+            );
+            profile_reports.insert(
+                (REPORT_ID_KEYS_MACRO, profile),
+                build_keys_report(REPORT_ID_KEYS_MACRO, 1, profile, &DEFAULT_KEYS_MACRO),
+            );
+            profile_reports.insert(
+                (REPORT_ID_KEYS_THUMBSTER, profile),
+                build_keys_report(
+                    REPORT_ID_KEYS_THUMBSTER,
+                    1,
+                    profile,
+                    &DEFAULT_KEYS_THUMBSTER,
+                ),
+            );
+            profile_reports.insert(
+                (REPORT_ID_KEYS_EXTRA, profile),
+                build_report(REPORT_ID_KEYS_EXTRA, 1, &[profile, 0xff, 0xf1, 0x00]),
+            );
+            profile_reports.insert(
+                (REPORT_ID_KEYS_EASYZONE, profile),
+                build_keys_report(REPORT_ID_KEYS_EASYZONE, 2, profile, &DEFAULT_KEYS_EASYZONE),
+            );
+            #[rustfmt::skip]
+            profile_reports.insert(
+                (REPORT_ID_LIGHTS, profile),
+                build_report(REPORT_ID_LIGHTS, 1, &[
+                    profile, 0x04, 0x01, 0x0a, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x01, 0x1e,
+                    0xff, 0x00, 0xff, 0x00, 0xff, 0x00,
+                    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                ]),
+            );
+        }
+
+        let mut profile = 0u8;
+        profile.set_nibble(1, PROFILE_COUNT);
+
+        Self {
+            shared: Arc::new(Shared {
+                state: Mutex::new(State {
+                    profile,
+                    control_request: 0x00,
+                    control_status: ControlStatus::Ok,
+                    control_busy_reads: 0,
+                    queued_control_statuses: VecDeque::new(),
+                    selected_profile: 0,
+                    profile_reports,
+                    selected_macro: 0,
+                    macros: HashMap::new(),
+                    light_control_state: 0x00,
+                    light_control_status: LightControlWriteCheck::Ok,
+                    light_control_busy_reads: 0,
+                    queued_light_control_statuses: VecDeque::new(),
+                    custom_lights: build_report(
+                        REPORT_ID_CUSTOM_LIGHTS,
+                        1,
+                        &[0u8; CUSTOM_LIGHTS_LEN - 4],
+                    ),
+                    device_info: vec![REPORT_ID_DEVICE_INFO, 0x07, 0x6f, 0x11, 0x1a, 0x00, 0x00],
+                    busy_reads: 0,
+                    unknown_button_codes: Vec::new(),
+                    written_reports: Vec::new(),
+                    events: VecDeque::new(),
+                    #[cfg(feature = "async")]
+                    event_wakers: Vec::new(),
+                    plugged_in: true,
+                    generation: 0,
+                }),
+                event_available: Condvar::new(),
+            }),
+        }
+    }
+
+    /// Gets the interfaces of the emulated device, in interface order
+    pub fn interfaces(&self) -> Vec<EmulatedInterface> {
+        let generation = self.shared.state.lock().unwrap().generation;
+        vec![
+            EmulatedInterface {
+                shared: Arc::clone(&self.shared),
+                events: false,
+                generation,
+            },
+            EmulatedInterface {
+                shared: Arc::clone(&self.shared),
+                events: true,
+                generation,
+            },
+        ]
+    }
+
+    /// Opens the emulated device
+    pub fn open(&self) -> Result<RyosMkFx<EmulatedInterface>, Error> {
+        RyosMkFx::from_transports(self.interfaces())
+    }
+
+    /// Gets the current profile, starting at 1
+    pub fn profile(&self) -> u8 {
+        self.shared.state.lock().unwrap().profile.get_nibble(0) + 1
+    }
+
+    /// Switches profiles the way the profile key does, including the event it sends
+    pub fn switch_profile(&self, profile: u8) -> Result<(), Error> {
+        Error::ensure_range("Profile", profile, 1..=i64::from(PROFILE_COUNT))?;
+        self.shared
+            .state
+            .lock()
+            .unwrap()
+            .profile
+            .set_nibble(0, profile - 1);
+        self.inject_event([
+            REPORT_ID_EVENT,
+            0x00,
+            EventType::ProfileStart as u8,
+            profile,
+            0x00,
+        ]);
+        Ok(())
+    }
+
+    /// Sets how many times the device reports `Busy` after each write before it's done
+    pub fn set_busy_reads(&self, busy_reads: usize) {
+        self.shared.state.lock().unwrap().busy_reads = busy_reads;
+    }
+
+    /// Makes the keys reports store functions with these codes as disabled
+    ///
+    /// Emulates a firmware that doesn't know the codes, but still accepts the reports.
+    pub fn set_unknown_button_codes(&self, codes: &[u8]) {
+        self.shared.state.lock().unwrap().unknown_button_codes = codes.to_vec();
+    }
+
+    /// Queues a status to be returned by the next read of the `Control` report
+    ///
+    /// Queued statuses take precedence over the status of the last write.
+    pub fn queue_control_status(&self, status: ControlStatus) {
+        let mut state = self.shared.state.lock().unwrap();
+        state.queued_control_statuses.push_back(status);
+    }
+
+    /// Queues a status to be returned by the next read of the `LightControl` report
+    pub fn queue_light_control_status(&self, status: LightControlWriteCheck) {
+        let mut state = self.shared.state.lock().unwrap();
+        state.queued_light_control_statuses.push_back(status);
+    }
+
+    /// Gets whether the custom lights are shown instead of the stored ones
+    pub fn custom_lights_active(&self) -> bool {
+        self.shared.state.lock().unwrap().light_control_state == 0x01
+    }
+
+    /// Gets the custom lights report as last written
+    pub fn custom_lights(&self) -> Vec<u8> {
+        self.shared.state.lock().unwrap().custom_lights.clone()
+    }
+
+    /// Gets a per-profile report as stored for `profile`, starting at 1
+    pub fn profile_report(&self, report_id: u8, profile: u8) -> Option<Vec<u8>> {
+        let state = self.shared.state.lock().unwrap();
+        state
+            .profile_reports
+            .get(&(report_id, profile.wrapping_sub(1)))
+            .cloned()
+    }
+
+    /// Gets the macro report as stored for `profile` and `slot`, both starting at 1
+    pub fn macro_report(&self, profile: u8, slot: u8) -> Option<Vec<u8>> {
+        let state = self.shared.state.lock().unwrap();
+        state
+            .macros
+            .get(&(profile.wrapping_sub(1), slot.wrapping_sub(1)))
+            .cloned()
+    }
+
+    /// Stores a macro report built from `payload` for `profile` and `slot`, both starting at 1
+    ///
+    /// `payload` is everything between the size and the bytesum. Its indices aren't checked, so
+    /// the macro can be left the way the vendor software writes it, or answer for another slot.
+    pub fn store_macro_report(&self, profile: u8, slot: u8, payload: &[u8]) {
+        let mut state = self.shared.state.lock().unwrap();
+        state.macros.insert(
+            (profile.wrapping_sub(1), slot.wrapping_sub(1)),
+            build_report(REPORT_ID_MACRO, 2, payload),
+        );
+    }
+
+    /// Gets every feature report the device accepted so far, oldest first
+    pub fn written_reports(&self) -> Vec<Vec<u8>> {
+        self.shared.state.lock().unwrap().written_reports.clone()
+    }
+
+    /// Disconnects all interfaces handed out so far, like pulling the cable
+    pub fn unplug(&self) {
+        let mut state = self.shared.state.lock().unwrap();
+        state.plugged_in = false;
+        state.generation += 1;
+        state.wake_event_readers();
+        self.shared.event_available.notify_all();
+    }
+
+    /// Plugs the device back in, only interfaces fetched from now on can reach it
+    pub fn replug(&self) {
+        self.shared.state.lock().unwrap().plugged_in = true;
+    }
+
+    /// Queues a raw event report on the Events interface
+    pub fn inject_event(&self, event: [u8; 5]) {
+        let mut state = self.shared.state.lock().unwrap();
+        state.events.push_back(event);
+        state.wake_event_readers();
+        self.shared.event_available.notify_all();
+    }
+
+    /// Queues the event sent when a key is pressed or released
+    pub fn inject_key(&self, sdk_index: u8, pressed: bool) -> Result<(), Error> {
+        let effect_index = (0..=u8::MAX)
+            .find(|&effect_index| effect_index_to_sdk_index(effect_index) == sdk_index)
+            .ok_or_else(|| invalid_input(&format!("Key {} doesn't send events", sdk_index)))?;
+        let action = if pressed {
+            EventKeyAction::Press
+        } else {
+            EventKeyAction::Release
+        };
+        self.inject_event([
+            REPORT_ID_EVENT,
+            0x00,
+            EventType::Effect as u8,
+            effect_index,
+            action as u8,
+        ]);
+        Ok(())
+    }
+}
+
+impl State {
+    #[cfg(feature = "async")]
+    fn wake_event_readers(&mut self) {
+        self.event_wakers.drain(..).for_each(Waker::wake);
+    }
+
+    #[cfg(not(feature = "async"))]
+    fn wake_event_readers(&mut self) {}
+
+    fn pop_event(&mut self, buf: &mut [u8]) -> Option<usize> {
+        self.events.pop_front().map(|event| {
+            let len = usize::min(buf.len(), event.len());
+            buf[..len].copy_from_slice(&event[..len]);
+            len
+        })
+    }
+
+    fn finish_write(&mut self, status: ControlStatus) {
+        self.control_status = status;
+        self.control_busy_reads = self.busy_reads;
+    }
+
+    fn get_feature_report(&mut self, report_id: u8) -> Result<Vec<u8>, Error> {
+        Ok(match report_id {
+            REPORT_ID_CONTROL => {
+                let status = if let Some(status) = self.queued_control_statuses.pop_front() {
+                    status
+                } else if self.control_busy_reads > 0 {
+                    self.control_busy_reads -= 1;
+                    ControlStatus::Busy
+                } else {
+                    self.control_status
+                };
+                vec![REPORT_ID_CONTROL, status as u8, self.control_request]
+            }
+            REPORT_ID_PROFILE => vec![REPORT_ID_PROFILE, 0x03, self.profile],
+            REPORT_ID_DEVICE_INFO => self.device_info.clone(),
+            REPORT_ID_LIGHT_CONTROL => {
+                let status = if let Some(status) = self.queued_light_control_statuses.pop_front() {
+                    status
+                } else if self.light_control_busy_reads > 0 {
+                    self.light_control_busy_reads -= 1;
+                    LightControlWriteCheck::Busy
+                } else {
+                    self.light_control_status
+                };
+                vec![
+                    REPORT_ID_LIGHT_CONTROL,
+                    0x08,
+                    self.light_control_state,
+                    0x00,
+                    0x00,
+                    0x00,
+                    status as u8,
+                    0x00,
+                ]
+            }
+            REPORT_ID_CUSTOM_LIGHTS => self.custom_lights.clone(),
+            REPORT_ID_MACRO => {
+                let key = (self.selected_profile, self.selected_macro);
+                self.macros.get(&key).cloned().unwrap_or_else(|| {
+                    // Named nothing, played once, no steps
+                    let mut payload = vec![0u8; MACRO_LEN - 5];
+                    payload[..3].copy_from_slice(&[key.0, key.1, 0x01]);
+                    build_report(REPORT_ID_MACRO, 2, &payload)
+                })
+            }
+            report_id => self
+                .profile_reports
+                .get(&(report_id, self.selected_profile))
+                .cloned()
+                .ok_or_else(|| invalid_input(&format!("Unknown report {:#04x}", report_id)))?,
+        })
+    }
+
+    /// Disables the unknown button codes of a keys report whose buttons start at `start`
+    fn forget_unknown_button_codes(&self, report: &[u8], start: usize) -> Vec<u8> {
+        let mut report = report.to_vec();
+        let buttons = matches!(
+            report[0],
+            REPORT_ID_KEYS_FUNCTION
+                | REPORT_ID_KEYS_MACRO
+                | REPORT_ID_KEYS_THUMBSTER
+                | REPORT_ID_KEYS_EASYZONE
+        );
+        if !buttons || self.unknown_button_codes.is_empty() {
+            return report;
+        }
+        let end = report.len() - 2;
+        for button in report[start..end].chunks_mut(3) {
+            if self.unknown_button_codes.contains(&button[0]) {
+                button.copy_from_slice(&[0x00, 0x00, 0x00]);
+            }
+        }
+        let sum = bytesum(&report[..end]);
+        report[end..].copy_from_slice(&sum.to_le_bytes());
+        report
+    }
+
+    fn set_feature_report(&mut self, report: &[u8]) -> Result<(), Error> {
+        match report[0] {
+            REPORT_ID_CONTROL => {
+                ensure_valid(report.len() == 3, "Control report has the wrong length")?;
+                let (profile, request) = (report[1], report[2]);
+                self.control_request = request;
+                let macro_requests =
+                    ControlRequest::Macro as u8..ControlRequest::Macro as u8 + MACRO_SLOT_COUNT;
+                let known = PROFILE_REPORTS
+                    .iter()
+                    .any(|&(_, _, control_request, _)| control_request == request)
+                    || macro_requests.contains(&request);
+                if known && profile < PROFILE_COUNT {
+                    self.selected_profile = profile;
+                    if macro_requests.contains(&request) {
+                        self.selected_macro = request - macro_requests.start;
+                    }
+                    self.finish_write(ControlStatus::Ok);
+                } else {
+                    self.finish_write(ControlStatus::Invalid);
+                }
+            }
+            REPORT_ID_PROFILE => {
+                ensure_valid(report.len() == 3, "Profile report has the wrong length")?;
+                if report[2].get_nibble(0) < PROFILE_COUNT {
+                    self.profile.set_nibble(0, report[2].get_nibble(0));
+                    self.finish_write(ControlStatus::Ok);
+                } else {
+                    self.finish_write(ControlStatus::Invalid);
+                }
+            }
+            REPORT_ID_LIGHT_CONTROL => {
+                ensure_valid(
+                    report.len() == 8,
+                    "LightControl report has the wrong length",
+                )?;
+                self.light_control_state = report[2];
+                self.light_control_status = LightControlWriteCheck::Ok;
+                self.light_control_busy_reads = self.busy_reads;
+            }
+            REPORT_ID_CUSTOM_LIGHTS => {
+                ensure_valid(
+                    report.len() == CUSTOM_LIGHTS_LEN,
+                    "CustomLights report has the wrong length",
+                )?;
+                if bytesum_matches(report) {
+                    self.custom_lights = report.to_vec();
+                    self.light_control_status = LightControlWriteCheck::Ok;
+                } else {
+                    self.light_control_status = LightControlWriteCheck::Invalid;
+                }
+                self.light_control_busy_reads = self.busy_reads;
+            }
+            REPORT_ID_MACRO => {
+                ensure_valid(
+                    report.len() == MACRO_LEN,
+                    "Macro report has the wrong length",
+                )?;
+                let (profile, slot) = (report[3], report[4]);
+                if profile < PROFILE_COUNT && slot < MACRO_SLOT_COUNT && bytesum_matches(report) {
+                    self.macros.insert((profile, slot), report.to_vec());
+                    self.finish_write(ControlStatus::Ok);
+                } else {
+                    self.finish_write(ControlStatus::Invalid);
+                }
+            }
+            REPORT_ID_DEVICE_INFO => return Err(invalid_input("DeviceInfo report is read only")),
+            report_id => {
+                let &(_, len, _, profile_offset) = PROFILE_REPORTS
+                    .iter()
+                    .find(|&&(id, _, _, _)| id == report_id)
+                    .ok_or_else(|| invalid_input(&format!("Unknown report {:#04x}", report_id)))?;
+                ensure_valid(
+                    report.len() == len,
+                    &format!("Report {:#04x} has the wrong length", report_id),
+                )?;
+                let profile = report[profile_offset];
+                if profile < PROFILE_COUNT && bytesum_matches(report) {
+                    let stored = self.forget_unknown_button_codes(report, profile_offset + 1);
+                    self.profile_reports.insert((report_id, profile), stored);
+                    self.finish_write(ControlStatus::Ok);
+                } else {
+                    self.finish_write(ControlStatus::Invalid);
+                }
+            }
+        }
+
+        self.written_reports.push(report.to_vec());
+        Ok(())
+    }
+}
+
+/// One interface of an `Emulator`
+pub struct EmulatedInterface {
+    shared: Arc<Shared>,
+    events: bool,
+    generation: usize,
+}
+
+impl EmulatedInterface {
+    /// Locks the device state, failing if this interface was unplugged
+    fn state(&self) -> Result<MutexGuard<'_, State>, Error> {
+        let state = self.shared.state.lock().unwrap();
+        self.ensure_connected(&state)?;
+        Ok(state)
+    }
+
+    fn ensure_connected(&self, state: &State) -> Result<(), Error> {
+        if state.plugged_in && state.generation == self.generation {
+            Ok(())
+        } else {
+            Err(Error::Disconnected)
+        }
+    }
+
+    /// Pops the next event, waiting until `deadline` or forever
+    fn pop_event(&self, buf: &mut [u8], deadline: Option<Instant>) -> Result<Option<usize>, Error> {
+        ensure_valid(
+            self.events,
+            "Primary interface has no emulated input reports",
+        )?;
+        let mut state = self.state()?;
+        loop {
+            if let Some(len) = state.pop_event(buf) {
+                return Ok(Some(len));
+            }
+            state = match deadline {
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        return Ok(None);
+                    }
+                    let event_available = &self.shared.event_available;
+                    event_available
+                        .wait_timeout(state, deadline - now)
+                        .unwrap()
+                        .0
+                }
+                None => self.shared.event_available.wait(state).unwrap(),
+            };
+            self.ensure_connected(&state)?;
+        }
+    }
+}
+
+impl Transport for EmulatedInterface {
+    fn get_feature_report(&self, buf: &mut [u8]) -> Result<usize, Error> {
+        ensure_valid(!self.events, "Events interface has no feature reports")?;
+        let report = self.state()?.get_feature_report(buf[0])?;
+        let len = usize::min(buf.len(), report.len());
+        buf[..len].copy_from_slice(&report[..len]);
+        Ok(len)
+    }
+
+    fn set_feature_report(&self, buf: &[u8]) -> Result<usize, Error> {
+        ensure_valid(!self.events, "Events interface has no feature reports")?;
+        self.state()?.set_feature_report(buf)?;
+        Ok(buf.len())
+    }
+
+    fn read_report(&self, buf: &mut [u8]) -> Result<usize, Error> {
+        Ok(self.pop_event(buf, None)?.unwrap_or_default())
+    }
+
+    fn read_report_timeout(
+        &self,
+        buf: &mut [u8],
+        timeout: Duration,
+    ) -> Result<Option<usize>, Error> {
+        self.pop_event(buf, Some(Instant::now() + timeout))
+    }
+
+    #[cfg(feature = "async")]
+    fn poll_read_report(
+        &self,
+        context: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<Result<usize, Error>> {
+        let result = ensure_valid(
+            self.events,
+            "Primary interface has no emulated input reports",
+        )
+        .and_then(|()| self.state());
+        let mut state = match result {
+            Ok(state) => state,
+            Err(error) => return Poll::Ready(Err(error)),
+        };
+        match state.pop_event(buf) {
+            Some(len) => Poll::Ready(Ok(len)),
+            None => {
+                let waker = context.waker();
+                if !state
+                    .event_wakers
+                    .iter()
+                    .any(|waiting| waiting.will_wake(waker))
+                {
+                    state.event_wakers.push(waker.clone());
+                }
+                Poll::Pending
+            }
+        }
+    }
+
+    fn report_descriptor(&self) -> Result<Option<Vec<u8>>, Error> {
+        Ok(Some(if self.events {
+            build_descriptor(0xff00, 0x01, &[(REPORT_ID_EVENT, EVENT_LEN)], 0x81)
+        } else {
+            let mut reports = vec![
+                (REPORT_ID_CONTROL, 3),
+                (REPORT_ID_PROFILE, 3),
+                (REPORT_ID_DEVICE_INFO, DEVICE_INFO_LEN),
+                (REPORT_ID_LIGHT_CONTROL, 8),
+                (REPORT_ID_CUSTOM_LIGHTS, CUSTOM_LIGHTS_LEN),
+                (REPORT_ID_MACRO, MACRO_LEN),
+            ];
+            reports.extend(PROFILE_REPORTS.iter().map(|&(id, len, _, _)| (id, len)));
+            // Generic Desktop, Keyboard
+            build_descriptor(0x0001, 0x06, &reports, 0xb1)
+        }))
+    }
+}
+
+/// Builds a descriptor declaring byte-sized reports of one main item kind in one application
+fn build_descriptor(usage_page: u16, usage: u8, reports: &[(u8, usize)], main_item: u8) -> Vec<u8> {
+    let [page_low, page_high] = usage_page.to_le_bytes();
+    // Usage Page (16 bit), Usage, Collection (Application), Report Size (8)
+    let mut descriptor = vec![
+        0x06, page_low, page_high, 0x09, usage, 0xa1, 0x01, 0x75, 0x08,
+    ];
+    for &(id, len) in reports {
+        let [count_low, count_high] = (len as u16 - 1).to_le_bytes();
+        // Report ID, Report Count (16 bit), Usage (1), Input/Feature (Data, Variable, Absolute)
+        descriptor.extend_from_slice(&[0x85, id, 0x96, count_low, count_high, 0x09, 0x01]);
+        descriptor.extend_from_slice(&[main_item, 0x02]);
+    }
+    // End Collection
+    descriptor.push(0xc0);
+    descriptor
+}
+
+fn bytesum(bytes: &[u8]) -> u16 {
+    bytes
+        .iter()
+        .fold(0u16, |sum, byte| sum.wrapping_add(u16::from(*byte)))
+}
+
+fn bytesum_matches(report: &[u8]) -> bool {
+    let (data, sum) = report.split_at(report.len() - 2);
+    bytesum(data) == u16::from_le_bytes([sum[0], sum[1]])
+}
+
+/// Builds a report with its size field and bytesum around `payload`
+fn build_report(report_id: u8, size_len: usize, payload: &[u8]) -> Vec<u8> {
+    let len = 1 + size_len + payload.len() + 2;
+    let mut report = vec![report_id];
+    report.extend_from_slice(&(len as u16).to_le_bytes()[..size_len]);
+    report.extend_from_slice(payload);
+    let sum = bytesum(&report);
+    report.extend_from_slice(&sum.to_le_bytes());
+    report
+}
+
+fn build_keys_report(
+    report_id: u8,
+    size_len: usize,
+    profile: u8,
+    keys: &[ButtonConfig],
+) -> Vec<u8> {
+    let mut payload = vec![profile];
+    for key in keys {
+        payload.extend_from_slice(&[key.type_, key.modifier, key.key]);
+    }
+    build_report(report_id, size_len, &payload)
+}
+
+/// Rejects a transfer the way the kernel does for reports the device doesn't accept
+fn invalid_input(message: &str) -> Error {
+    Error::Io(io::Error::new(io::ErrorKind::InvalidInput, message))
+}
+
+fn ensure_valid(condition: bool, message: &str) -> Result<(), Error> {
+    if condition {
+        Ok(())
+    } else {
+        Err(invalid_input(message))
+    }
+}
@@ -1,52 +1,177 @@
 use super::effect_index_to_sdk_index;
-use std::fmt;
+use crate::{report::ReportField, Error};
+use hidraw_derive::ReportField;
+use std::time::Instant;
 
+/// Events compare equal when they carry the same report, regardless of when they were received
+#[derive(Copy, Clone, Debug)]
 pub struct Event {
-    _report_id: u8,
     pub subtype: EventSubtype,
     pub type_: EventType,
     pub data: u8,
     pub action: EventAction,
+    /// When the event was received, on the monotonic clock
+    pub timestamp: Instant,
+    /// The report as received
+    pub raw: [u8; Event::SIZE],
+}
+
+impl PartialEq for Event {
+    fn eq(&self, other: &Self) -> bool {
+        self.subtype == other.subtype
+            && self.type_ == other.type_
+            && self.data == other.data
+            && self.action == other.action
+            && self.raw == other.raw
+    }
 }
 
 impl Event {
+    pub const REPORT_ID: u8 = 0x03;
+
+    /// Size of an event report in bytes
+    pub const SIZE: usize = 5;
+
+    /// Decodes an event report received just now, rejecting unknown event types and actions
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        Self::from_bytes_at(bytes, Instant::now())
+    }
+
+    /// Decodes an event report received at `timestamp`
+    pub fn from_bytes_at(bytes: &[u8], timestamp: Instant) -> Result<Self, Error> {
+        if bytes.len() != Self::SIZE {
+            return Err(Error::ReportLength {
+                report_id: bytes.first().cloned().unwrap_or_default(),
+                expected: Self::SIZE,
+                actual: bytes.len(),
+            });
+        }
+
+        let type_ = EventType::decode(&bytes[2..])?;
+        Ok(Self {
+            subtype: match type_ {
+                EventType::Rad => EventSubtype::Rad(EventRadSubtype::decode(&bytes[1..])?),
+                _ => EventSubtype::None(bytes[1]),
+            },
+            type_,
+            data: bytes[3],
+            action: match type_ {
+                EventType::Effect => EventAction::Key(EventKeyAction::decode(&bytes[4..])?),
+                EventType::LiveRecording => {
+                    EventAction::LiveRecording(EventLiveRecordingAction::decode(&bytes[4..])?)
+                }
+                _ => EventAction::None(bytes[4]),
+            },
+            timestamp,
+            raw: [bytes[0], bytes[1], bytes[2], bytes[3], bytes[4]],
+        })
+    }
+
+    /// Keeps a report that doesn't decode as an `EventType::Unknown` event received at
+    /// `timestamp`, whose `decode` gives `DecodedEvent::Unknown`
+    pub fn from_raw_at(raw: [u8; Self::SIZE], timestamp: Instant) -> Self {
+        Self {
+            subtype: EventSubtype::None(raw[1]),
+            type_: EventType::Unknown,
+            data: raw[3],
+            action: EventAction::None(raw[4]),
+            timestamp,
+            raw,
+        }
+    }
+
     pub fn sdk_index(&self) -> u8 {
         effect_index_to_sdk_index(self.data)
     }
-}
 
-impl fmt::Debug for Event {
-    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-        fmt.debug_struct("Event")
-            .field("_report_id", &self._report_id)
-            .field(
-                "subtype",
-                match self.type_ {
-                    EventType::Rad => unsafe { &self.subtype.rad },
-                    _ => unsafe { &self.subtype.none },
-                },
-            )
-            .field("type_", &self.type_)
-            .field("data", &self.data)
-            .field(
-                "action",
-                match self.type_ {
-                    EventType::Effect => unsafe { &self.action.key },
-                    EventType::LiveRecording => unsafe { &self.action.live_recording },
-                    _ => unsafe { &self.action.none },
-                },
-            )
-            .finish()
+    /// Interprets the fields the way the event type defines them
+    pub fn decode(&self) -> DecodedEvent {
+        match (self.type_, self.subtype, self.action) {
+            (EventType::Unknown, _, _) => DecodedEvent::Unknown { raw: self.raw },
+            (EventType::Effect, _, EventAction::Key(EventKeyAction::Press)) => {
+                DecodedEvent::KeyPress {
+                    key: self.sdk_index(),
+                }
+            }
+            (EventType::Effect, _, EventAction::Key(EventKeyAction::Release)) => {
+                DecodedEvent::KeyRelease {
+                    key: self.sdk_index(),
+                }
+            }
+            (EventType::Profile, _, _) => DecodedEvent::ProfileChanged { profile: self.data },
+            (EventType::LiveRecording, _, EventAction::LiveRecording(action)) => {
+                DecodedEvent::LiveRecording {
+                    action,
+                    data: self.data,
+                }
+            }
+            (EventType::Rad, EventSubtype::Rad(subtype), _) => DecodedEvent::Rad(subtype),
+            (EventType::Multimedia, _, _) => DecodedEvent::Multimedia(self.data),
+            (EventType::Effect, _, _)
+            | (EventType::LiveRecording, _, _)
+            | (EventType::Rad, _, _) => DecodedEvent::Unknown { raw: self.raw },
+            (type_, _, _) => DecodedEvent::Other {
+                type_,
+                data: self.data,
+            },
+        }
     }
 }
 
-#[derive(Copy, Clone)]
-pub union EventSubtype {
-    pub none: u8,
-    pub rad: EventRadSubtype,
+/// An event as what happened on the device
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum DecodedEvent {
+    /// A key was pressed, by SDK index
+    KeyPress {
+        key: u8,
+    },
+    /// A key was released, by SDK index
+    KeyRelease {
+        key: u8,
+    },
+    /// The profile was switched, as numbered by the device
+    ProfileChanged {
+        profile: u8,
+    },
+    LiveRecording {
+        action: EventLiveRecordingAction,
+        data: u8,
+    },
+    Rad(EventRadSubtype),
+    /// A multimedia key was used, by its multimedia key code
+    Multimedia(u8),
+    /// A known event type without a variant of its own
+    Other {
+        type_: EventType,
+        data: u8,
+    },
+    /// An event type or value the firmware isn't known to send
+    Unknown {
+        raw: [u8; Event::SIZE],
+    },
 }
 
+impl DecodedEvent {
+    /// Decodes any event report of the right length, turning unexpected values into `Unknown`
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        match Event::from_bytes(bytes) {
+            Ok(event) => Ok(event.decode()),
+            Err(Error::InvalidValue { .. }) => Ok(DecodedEvent::Unknown {
+                raw: [bytes[0], bytes[1], bytes[2], bytes[3], bytes[4]],
+            }),
+            Err(error) => Err(error),
+        }
+    }
+}
+
+/// Meaning of the subtype byte, which depends on the event type
 #[derive(Copy, Clone, Debug, PartialEq)]
+pub enum EventSubtype {
+    None(u8),
+    Rad(EventRadSubtype),
+}
+
+#[derive(ReportField, Copy, Clone, Debug, PartialEq, Eq, Hash)]
 #[repr(u8)]
 pub enum EventRadSubtype {
     W = 0x01,
@@ -70,7 +195,34 @@ pub enum EventRadSubtype {
     Total = 0x13,
 }
 
-#[derive(Debug, PartialEq)]
+impl EventRadSubtype {
+    /// Name used in scripts and configuration, like `thumbster_1`
+    pub fn name(self) -> &'static str {
+        match self {
+            EventRadSubtype::W => "w",
+            EventRadSubtype::A => "a",
+            EventRadSubtype::S => "s",
+            EventRadSubtype::D => "d",
+            EventRadSubtype::Thumbster1 => "thumbster_1",
+            EventRadSubtype::Thumbster2 => "thumbster_2",
+            EventRadSubtype::Thumbster3 => "thumbster_3",
+            EventRadSubtype::Easyshift => "easyshift",
+            EventRadSubtype::Multimedia => "multimedia",
+            EventRadSubtype::M1 => "m1",
+            EventRadSubtype::M2 => "m2",
+            EventRadSubtype::M3 => "m3",
+            EventRadSubtype::M4 => "m4",
+            EventRadSubtype::M5 => "m5",
+            EventRadSubtype::MacroShortcut => "macro_shortcut",
+            EventRadSubtype::Talk => "talk",
+            EventRadSubtype::MacroLifeRec => "macro_life_rec",
+            EventRadSubtype::Backlight => "backlight",
+            EventRadSubtype::Total => "total",
+        }
+    }
+}
+
+#[derive(ReportField, Copy, Clone, Debug, PartialEq)]
 #[repr(u8)]
 pub enum EventType {
     Unknown = 0x00,
@@ -93,21 +245,74 @@ pub enum EventType {
     Talk = 0xff,
 }
 
-#[derive(Copy, Clone)]
-pub union EventAction {
-    pub none: u8,
-    pub key: EventKeyAction,
-    pub live_recording: EventLiveRecordingAction,
+impl EventType {
+    /// Every event type, in report value order
+    pub const ALL: [EventType; 18] = [
+        EventType::Unknown,
+        EventType::ProfileStart,
+        EventType::Profile,
+        EventType::Macro,
+        EventType::LiveRecording,
+        EventType::Quicklaunch,
+        EventType::Easyshift,
+        EventType::Multimedia,
+        EventType::Backlight,
+        EventType::TimerStart,
+        EventType::TimerStop,
+        EventType::OpenDriver,
+        EventType::LedMacro,
+        EventType::Rad,
+        EventType::Effect,
+        EventType::Layer,
+        EventType::EasyshiftSelf,
+        EventType::Talk,
+    ];
+
+    /// Name used in scripts and configuration, like `timer_start`
+    pub fn name(self) -> &'static str {
+        match self {
+            EventType::Unknown => "unknown",
+            EventType::ProfileStart => "profile_start",
+            EventType::Profile => "profile",
+            EventType::Macro => "macro",
+            EventType::LiveRecording => "live_recording",
+            EventType::Quicklaunch => "quicklaunch",
+            EventType::Easyshift => "easyshift",
+            EventType::Multimedia => "multimedia",
+            EventType::Backlight => "backlight",
+            EventType::TimerStart => "timer_start",
+            EventType::TimerStop => "timer_stop",
+            EventType::OpenDriver => "open_driver",
+            EventType::LedMacro => "led_macro",
+            EventType::Rad => "rad",
+            EventType::Effect => "effect",
+            EventType::Layer => "layer",
+            EventType::EasyshiftSelf => "easyshift_self",
+            EventType::Talk => "talk",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().cloned().find(|type_| type_.name() == name)
+    }
 }
 
+/// Meaning of the action byte, which depends on the event type
 #[derive(Copy, Clone, Debug, PartialEq)]
+pub enum EventAction {
+    None(u8),
+    Key(EventKeyAction),
+    LiveRecording(EventLiveRecordingAction),
+}
+
+#[derive(ReportField, Copy, Clone, Debug, PartialEq)]
 #[repr(u8)]
 pub enum EventKeyAction {
     Release = 0x00,
     Press = 0x01,
 }
 
-#[derive(Copy, Clone, Debug, PartialEq)]
+#[derive(ReportField, Copy, Clone, Debug, PartialEq)]
 #[repr(u8)]
 pub enum EventLiveRecordingAction {
     Start = 0x01,
@@ -116,3 +321,25 @@ pub enum EventLiveRecordingAction {
     EndAbort = 0x04,
     InvalidKey = 0x05,
 }
+
+impl EventLiveRecordingAction {
+    /// Name used in scripts and configuration, like `end_success`
+    pub fn name(self) -> &'static str {
+        match self {
+            EventLiveRecordingAction::Start => "start",
+            EventLiveRecordingAction::MacroKeySelected => "macro_key_selected",
+            EventLiveRecordingAction::EndSuccess => "end_success",
+            EventLiveRecordingAction::EndAbort => "end_abort",
+            EventLiveRecordingAction::InvalidKey => "invalid_key",
+        }
+    }
+}
+
+impl EventKeyAction {
+    pub fn name(self) -> &'static str {
+        match self {
+            EventKeyAction::Release => "release",
+            EventKeyAction::Press => "press",
+        }
+    }
+}
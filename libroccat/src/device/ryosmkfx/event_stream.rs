@@ -0,0 +1,102 @@
+use super::{Event, EventResult, Interface, Shared};
+use crate::{event_bus::SubscriberStream, transport::Transport};
+use futures::{
+    stream::Stream,
+    task::{Context, Poll},
+};
+use log::warn;
+use std::{
+    pin::Pin,
+    sync::{atomic::Ordering, Arc, Weak},
+    thread,
+};
+
+/// Events and read errors as a `Stream`, see `RyosMkFx::event_stream`
+///
+/// Polling the stream reads every report waiting on the Events interface and publishes it to all
+/// subscribers, then waits for the interface to become readable again. The stream ends once the
+/// device is dropped, or once it's lost without a way to reopen it.
+pub struct EventStream<T: Transport + 'static> {
+    shared: Weak<Shared<T>>,
+    events: SubscriberStream<EventResult>,
+    /// No more reports will be read, only buffered events are left
+    done: bool,
+}
+
+impl<T: Transport + 'static> EventStream<T> {
+    pub(super) fn new(shared: &Arc<Shared<T>>) -> Self {
+        Self {
+            shared: Arc::downgrade(shared),
+            events: shared.events.subscribe().into_stream(),
+            done: false,
+        }
+    }
+}
+
+impl<T: Transport + 'static> Stream for EventStream<T> {
+    type Item = EventResult;
+
+    fn poll_next(mut self: Pin<&mut Self>, context: &mut Context<'_>) -> Poll<Option<EventResult>> {
+        let stream = &mut *self;
+        if !stream.done {
+            stream.done = match stream.shared.upgrade() {
+                Some(shared) => shared.poll_events(context).is_ready(),
+                None => true,
+            };
+        }
+        if stream.done {
+            Poll::Ready(stream.events.get_ref().try_recv())
+        } else {
+            Pin::new(&mut stream.events).poll_next(context)
+        }
+    }
+}
+
+impl<T: Transport + 'static> Shared<T> {
+    /// Reads every report that's waiting, ready once no more will arrive
+    fn poll_events(self: &Arc<Self>, context: &mut Context<'_>) -> Poll<()> {
+        loop {
+            if self.stop.load(Ordering::SeqCst) {
+                return Poll::Ready(());
+            }
+            if self.reopening.load(Ordering::SeqCst) {
+                let mut wakers = self.reopen_wakers.lock().unwrap();
+                if !wakers.iter().any(|waker| waker.will_wake(context.waker())) {
+                    wakers.push(context.waker().clone());
+                }
+                drop(wakers);
+                // The reopen might have finished before the waker was in place
+                if self.reopening.load(Ordering::SeqCst) {
+                    return Poll::Pending;
+                }
+            }
+
+            let mut buf = [0u8; Event::SIZE];
+            let read = match self.interface(Interface::Events) {
+                Ok(interface) => interface.poll_read_report(context, &mut buf),
+                Err(error) => Poll::Ready(Err(error)),
+            };
+            match read {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(Ok(len)) => self.push_report(&buf, len),
+                Poll::Ready(Err(error)) if error.is_permanent() => {
+                    warn!("Event reader lost the device: {}", error);
+                    self.push(Err(error));
+                    if self.reopen.lock().unwrap().is_none() {
+                        return Poll::Ready(());
+                    }
+                    // Reopening sleeps between attempts, which only a thread can do
+                    self.reopening.store(true, Ordering::SeqCst);
+                    let shared = Arc::clone(self);
+                    thread::spawn(move || shared.wait_for_reopen());
+                }
+                Poll::Ready(Err(error)) => {
+                    warn!("Reading an event failed: {}", error);
+                    self.push(Err(error));
+                    context.waker().wake_by_ref();
+                    return Poll::Pending;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,238 @@
+use super::KeyId;
+
+/// Physical arrangement of the keys, which differs around Enter and the left Shift key
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum PhysicalLayout {
+    Ansi,
+    Iso,
+}
+
+/// Where a key is, in key widths from the top left corner of the keyboard
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct KeyRect {
+    pub key: KeyId,
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl KeyRect {
+    pub fn center(&self) -> (f32, f32) {
+        (self.x + self.width / 2.0, self.y + self.height / 2.0)
+    }
+
+    pub fn contains(&self, x: f32, y: f32) -> bool {
+        x >= self.x && x < self.x + self.width && y >= self.y && y < self.y + self.height
+    }
+}
+
+/// Positions and sizes of all keys of a physical layout, for effects that work in space
+///
+/// The M keys are the leftmost column and the Thumbster keys sit below the space bar. ISO Enter
+/// is approximated by its lower, narrower part extended up through both rows.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Geometry {
+    layout: PhysicalLayout,
+    keys: Vec<KeyRect>,
+}
+
+impl Geometry {
+    pub fn new(layout: PhysicalLayout) -> Self {
+        let variant: &[KeyRect] = match layout {
+            PhysicalLayout::Ansi => &ANSI_KEYS,
+            PhysicalLayout::Iso => &ISO_KEYS,
+        };
+        let mut keys: Vec<KeyRect> = COMMON_KEYS.iter().chain(variant).cloned().collect();
+        keys.sort_by_key(|rect| rect.key);
+        Self { layout, keys }
+    }
+
+    pub fn layout(&self) -> PhysicalLayout {
+        self.layout
+    }
+
+    /// All keys of the layout, by `KeyId`
+    pub fn keys(&self) -> &[KeyRect] {
+        &self.keys
+    }
+
+    /// Gets a key's rectangle, `None` if the layout doesn't have it
+    pub fn get(&self, key: KeyId) -> Option<&KeyRect> {
+        self.keys
+            .binary_search_by_key(&key, |rect| rect.key)
+            .ok()
+            .map(|index| &self.keys[index])
+    }
+
+    /// Gets the key covering a point
+    pub fn key_at(&self, x: f32, y: f32) -> Option<KeyId> {
+        self.keys
+            .iter()
+            .find(|rect| rect.contains(x, y))
+            .map(|rect| rect.key)
+    }
+
+    /// Gets the keys whose centers are within `radius` of a point, nearest first
+    pub fn keys_within(&self, x: f32, y: f32, radius: f32) -> Vec<KeyId> {
+        let mut keys: Vec<(f32, KeyId)> = self
+            .keys
+            .iter()
+            .map(|rect| {
+                let (center_x, center_y) = rect.center();
+                ((center_x - x).hypot(center_y - y), rect.key)
+            })
+            .filter(|&(distance, _)| distance <= radius)
+            .collect();
+        keys.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        keys.into_iter().map(|(_, key)| key).collect()
+    }
+
+    /// Width and height of the whole keyboard
+    pub fn size(&self) -> (f32, f32) {
+        self.keys.iter().fold((0.0, 0.0), |(width, height), rect| {
+            (
+                width.max(rect.x + rect.width),
+                height.max(rect.y + rect.height),
+            )
+        })
+    }
+}
+
+const fn rect(key: KeyId, x: f32, y: f32, width: f32, height: f32) -> KeyRect {
+    KeyRect {
+        key,
+        x,
+        y,
+        width,
+        height,
+    }
+}
+
+#[rustfmt::skip]
+const COMMON_KEYS: [KeyRect; 109] = [
+    rect(KeyId::Esc, 1.25, 0.0, 1.0, 1.0),
+    rect(KeyId::F1, 3.25, 0.0, 1.0, 1.0),
+    rect(KeyId::F2, 4.25, 0.0, 1.0, 1.0),
+    rect(KeyId::F3, 5.25, 0.0, 1.0, 1.0),
+    rect(KeyId::F4, 6.25, 0.0, 1.0, 1.0),
+    rect(KeyId::F5, 7.75, 0.0, 1.0, 1.0),
+    rect(KeyId::F6, 8.75, 0.0, 1.0, 1.0),
+    rect(KeyId::F7, 9.75, 0.0, 1.0, 1.0),
+    rect(KeyId::F8, 10.75, 0.0, 1.0, 1.0),
+    rect(KeyId::F9, 12.25, 0.0, 1.0, 1.0),
+    rect(KeyId::F10, 13.25, 0.0, 1.0, 1.0),
+    rect(KeyId::F11, 14.25, 0.0, 1.0, 1.0),
+    rect(KeyId::F12, 15.25, 0.0, 1.0, 1.0),
+    rect(KeyId::PrintScreen, 16.5, 0.0, 1.0, 1.0),
+    rect(KeyId::ScrollLock, 17.5, 0.0, 1.0, 1.0),
+    rect(KeyId::Pause, 18.5, 0.0, 1.0, 1.0),
+    rect(KeyId::M1, 0.0, 1.25, 1.0, 1.0),
+    rect(KeyId::Grave, 1.25, 1.25, 1.0, 1.0),
+    rect(KeyId::Num1, 2.25, 1.25, 1.0, 1.0),
+    rect(KeyId::Num2, 3.25, 1.25, 1.0, 1.0),
+    rect(KeyId::Num3, 4.25, 1.25, 1.0, 1.0),
+    rect(KeyId::Num4, 5.25, 1.25, 1.0, 1.0),
+    rect(KeyId::Num5, 6.25, 1.25, 1.0, 1.0),
+    rect(KeyId::Num6, 7.25, 1.25, 1.0, 1.0),
+    rect(KeyId::Num7, 8.25, 1.25, 1.0, 1.0),
+    rect(KeyId::Num8, 9.25, 1.25, 1.0, 1.0),
+    rect(KeyId::Num9, 10.25, 1.25, 1.0, 1.0),
+    rect(KeyId::Num0, 11.25, 1.25, 1.0, 1.0),
+    rect(KeyId::Minus, 12.25, 1.25, 1.0, 1.0),
+    rect(KeyId::Equal, 13.25, 1.25, 1.0, 1.0),
+    rect(KeyId::Backspace, 14.25, 1.25, 2.0, 1.0),
+    rect(KeyId::Insert, 16.5, 1.25, 1.0, 1.0),
+    rect(KeyId::Home, 17.5, 1.25, 1.0, 1.0),
+    rect(KeyId::PageUp, 18.5, 1.25, 1.0, 1.0),
+    rect(KeyId::NumLock, 19.75, 1.25, 1.0, 1.0),
+    rect(KeyId::KpDivide, 20.75, 1.25, 1.0, 1.0),
+    rect(KeyId::KpMultiply, 21.75, 1.25, 1.0, 1.0),
+    rect(KeyId::KpMinus, 22.75, 1.25, 1.0, 1.0),
+    rect(KeyId::M2, 0.0, 2.25, 1.0, 1.0),
+    rect(KeyId::Tab, 1.25, 2.25, 1.5, 1.0),
+    rect(KeyId::Q, 2.75, 2.25, 1.0, 1.0),
+    rect(KeyId::W, 3.75, 2.25, 1.0, 1.0),
+    rect(KeyId::E, 4.75, 2.25, 1.0, 1.0),
+    rect(KeyId::R, 5.75, 2.25, 1.0, 1.0),
+    rect(KeyId::T, 6.75, 2.25, 1.0, 1.0),
+    rect(KeyId::Y, 7.75, 2.25, 1.0, 1.0),
+    rect(KeyId::U, 8.75, 2.25, 1.0, 1.0),
+    rect(KeyId::I, 9.75, 2.25, 1.0, 1.0),
+    rect(KeyId::O, 10.75, 2.25, 1.0, 1.0),
+    rect(KeyId::P, 11.75, 2.25, 1.0, 1.0),
+    rect(KeyId::LeftBracket, 12.75, 2.25, 1.0, 1.0),
+    rect(KeyId::RightBracket, 13.75, 2.25, 1.0, 1.0),
+    rect(KeyId::Delete, 16.5, 2.25, 1.0, 1.0),
+    rect(KeyId::End, 17.5, 2.25, 1.0, 1.0),
+    rect(KeyId::PageDown, 18.5, 2.25, 1.0, 1.0),
+    rect(KeyId::Kp7, 19.75, 2.25, 1.0, 1.0),
+    rect(KeyId::Kp8, 20.75, 2.25, 1.0, 1.0),
+    rect(KeyId::Kp9, 21.75, 2.25, 1.0, 1.0),
+    rect(KeyId::KpPlus, 22.75, 2.25, 1.0, 2.0),
+    rect(KeyId::M3, 0.0, 3.25, 1.0, 1.0),
+    rect(KeyId::CapsLock, 1.25, 3.25, 1.75, 1.0),
+    rect(KeyId::A, 3.0, 3.25, 1.0, 1.0),
+    rect(KeyId::S, 4.0, 3.25, 1.0, 1.0),
+    rect(KeyId::D, 5.0, 3.25, 1.0, 1.0),
+    rect(KeyId::F, 6.0, 3.25, 1.0, 1.0),
+    rect(KeyId::G, 7.0, 3.25, 1.0, 1.0),
+    rect(KeyId::H, 8.0, 3.25, 1.0, 1.0),
+    rect(KeyId::J, 9.0, 3.25, 1.0, 1.0),
+    rect(KeyId::K, 10.0, 3.25, 1.0, 1.0),
+    rect(KeyId::L, 11.0, 3.25, 1.0, 1.0),
+    rect(KeyId::Semicolon, 12.0, 3.25, 1.0, 1.0),
+    rect(KeyId::Apostrophe, 13.0, 3.25, 1.0, 1.0),
+    rect(KeyId::Kp4, 19.75, 3.25, 1.0, 1.0),
+    rect(KeyId::Kp5, 20.75, 3.25, 1.0, 1.0),
+    rect(KeyId::Kp6, 21.75, 3.25, 1.0, 1.0),
+    rect(KeyId::M4, 0.0, 4.25, 1.0, 1.0),
+    rect(KeyId::Z, 3.5, 4.25, 1.0, 1.0),
+    rect(KeyId::X, 4.5, 4.25, 1.0, 1.0),
+    rect(KeyId::C, 5.5, 4.25, 1.0, 1.0),
+    rect(KeyId::V, 6.5, 4.25, 1.0, 1.0),
+    rect(KeyId::B, 7.5, 4.25, 1.0, 1.0),
+    rect(KeyId::N, 8.5, 4.25, 1.0, 1.0),
+    rect(KeyId::M, 9.5, 4.25, 1.0, 1.0),
+    rect(KeyId::Comma, 10.5, 4.25, 1.0, 1.0),
+    rect(KeyId::Period, 11.5, 4.25, 1.0, 1.0),
+    rect(KeyId::Slash, 12.5, 4.25, 1.0, 1.0),
+    rect(KeyId::RightShift, 13.5, 4.25, 2.75, 1.0),
+    rect(KeyId::Up, 17.5, 4.25, 1.0, 1.0),
+    rect(KeyId::Kp1, 19.75, 4.25, 1.0, 1.0),
+    rect(KeyId::Kp2, 20.75, 4.25, 1.0, 1.0),
+    rect(KeyId::Kp3, 21.75, 4.25, 1.0, 1.0),
+    rect(KeyId::KpEnter, 22.75, 4.25, 1.0, 2.0),
+    rect(KeyId::M5, 0.0, 5.25, 1.0, 1.0),
+    rect(KeyId::LeftCtrl, 1.25, 5.25, 1.25, 1.0),
+    rect(KeyId::LeftSuper, 2.5, 5.25, 1.25, 1.0),
+    rect(KeyId::LeftAlt, 3.75, 5.25, 1.25, 1.0),
+    rect(KeyId::Space, 5.0, 5.25, 6.25, 1.0),
+    rect(KeyId::RightAlt, 11.25, 5.25, 1.25, 1.0),
+    rect(KeyId::Fn, 12.5, 5.25, 1.25, 1.0),
+    rect(KeyId::Menu, 13.75, 5.25, 1.25, 1.0),
+    rect(KeyId::RightCtrl, 15.0, 5.25, 1.25, 1.0),
+    rect(KeyId::Left, 16.5, 5.25, 1.0, 1.0),
+    rect(KeyId::Down, 17.5, 5.25, 1.0, 1.0),
+    rect(KeyId::Right, 18.5, 5.25, 1.0, 1.0),
+    rect(KeyId::Kp0, 19.75, 5.25, 2.0, 1.0),
+    rect(KeyId::KpDecimal, 21.75, 5.25, 1.0, 1.0),
+    rect(KeyId::Thumbster1, 5.75, 6.5, 1.25, 0.5),
+    rect(KeyId::Thumbster2, 7.5, 6.5, 1.25, 0.5),
+    rect(KeyId::Thumbster3, 9.25, 6.5, 1.25, 0.5),
+];
+
+#[rustfmt::skip]
+const ANSI_KEYS: [KeyRect; 3] = [
+    rect(KeyId::Backslash, 14.75, 2.25, 1.5, 1.0),
+    rect(KeyId::Enter, 14.0, 3.25, 2.25, 1.0),
+    rect(KeyId::LeftShift, 1.25, 4.25, 2.25, 1.0),
+];
+
+#[rustfmt::skip]
+const ISO_KEYS: [KeyRect; 4] = [
+    rect(KeyId::Enter, 15.0, 2.25, 1.25, 2.0),
+    rect(KeyId::Backslash, 14.0, 3.25, 1.0, 1.0),
+    rect(KeyId::LeftShift, 1.25, 4.25, 1.25, 1.0),
+    rect(KeyId::NonUsBackslash, 2.5, 4.25, 1.0, 1.0),
+];
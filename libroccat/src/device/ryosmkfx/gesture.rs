@@ -0,0 +1,309 @@
+use super::{DecodedEvent, Event, EventRadSubtype, EventType};
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+/// A key as far as gestures are concerned
+///
+/// Pressing an M or Thumbster key sends both an `Effect` press and a `Rad` event while per-key
+/// lighting is active, so the same key can show up as `Sdk` and `Rad`. Bind whichever is sent.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum GestureKey {
+    /// Key with press and release events, by SDK index
+    Sdk(u8),
+    /// M1–M5 or Thumbster key reported through a `Rad` event, which has no release
+    Rad(EventRadSubtype),
+    /// Macro played by the device, by the data byte of its `Macro` event, which has no release
+    Macro(u8),
+}
+
+impl GestureKey {
+    /// Keys that are only reported once per press can only be tapped
+    pub fn has_release(self) -> bool {
+        match self {
+            GestureKey::Sdk(_) => true,
+            GestureKey::Rad(_) | GestureKey::Macro(_) => false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Gesture {
+    Tap(GestureKey),
+    DoubleTap(GestureKey),
+    LongPress(GestureKey),
+    /// Keys pressed together, in the order they were pressed
+    Chord(Vec<GestureKey>),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GestureEvent {
+    pub gesture: Gesture,
+    /// When the gesture was complete
+    pub timestamp: Instant,
+}
+
+/// Thresholds telling gestures apart
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct GestureConfig {
+    /// Longest hold that still counts as a tap
+    pub tap_max: Duration,
+    /// Longest time between releasing the first and second tap of a double tap
+    ///
+    /// Taps are reported once this passed without a second tap, zero reports them right away and
+    /// disables double taps.
+    pub double_tap_window: Duration,
+    /// Shortest hold that counts as a long press
+    pub long_press: Duration,
+    /// Longest time between the first and the last press of a chord
+    pub chord_window: Duration,
+}
+
+impl Default for GestureConfig {
+    fn default() -> Self {
+        Self {
+            tap_max: Duration::from_millis(250),
+            double_tap_window: Duration::from_millis(300),
+            long_press: Duration::from_millis(500),
+            chord_window: Duration::from_millis(50),
+        }
+    }
+}
+
+struct Held {
+    pressed: Instant,
+    /// Part of a chord or already reported as long press, so its release means nothing
+    consumed: bool,
+}
+
+struct PendingChord {
+    start: Instant,
+    keys: Vec<GestureKey>,
+}
+
+/// Recognizes taps, double taps, long presses and chords in a device's events
+///
+/// Some gestures are only complete once time passed without further events, so besides feeding
+/// it events, `poll` has to be called by `next_deadline`, e.g. by waiting for events with a
+/// timeout until then. Timestamps come from the events, so replaying recorded events works too.
+pub struct GestureRecognizer {
+    config: GestureConfig,
+    held: HashMap<GestureKey, Held>,
+    chord: Option<PendingChord>,
+    /// Release time of taps that may still become double taps
+    taps: HashMap<GestureKey, Instant>,
+}
+
+impl Default for GestureRecognizer {
+    fn default() -> Self {
+        Self::new(GestureConfig::default())
+    }
+}
+
+impl GestureRecognizer {
+    pub fn new(config: GestureConfig) -> Self {
+        Self {
+            config,
+            held: HashMap::new(),
+            chord: None,
+            taps: HashMap::new(),
+        }
+    }
+
+    pub fn config(&self) -> &GestureConfig {
+        &self.config
+    }
+
+    /// Feeds an event, returning the gestures it completed, oldest first
+    pub fn update(&mut self, event: &Event) -> Vec<GestureEvent> {
+        let mut gestures = self.poll(event.timestamp);
+        match event.decode() {
+            DecodedEvent::KeyPress { key } => self.press(GestureKey::Sdk(key), event.timestamp),
+            DecodedEvent::KeyRelease { key } => {
+                self.release(GestureKey::Sdk(key), event.timestamp, &mut gestures)
+            }
+            DecodedEvent::Rad(subtype) if is_extra_key(subtype) => {
+                self.tap(GestureKey::Rad(subtype), event.timestamp, &mut gestures)
+            }
+            DecodedEvent::Other {
+                type_: EventType::Macro,
+                data,
+            } => self.tap(GestureKey::Macro(data), event.timestamp, &mut gestures),
+            _ => (),
+        }
+        gestures
+    }
+
+    /// Completes gestures that only needed time to pass, returning them oldest first
+    pub fn poll(&mut self, now: Instant) -> Vec<GestureEvent> {
+        let mut gestures = Vec::new();
+
+        if let Some(ref chord) = self.chord {
+            if now >= chord.start + self.config.chord_window {
+                self.finish_chord(now, &mut gestures);
+            }
+        }
+
+        let long_press = self.config.long_press;
+        let chord = &self.chord;
+        for (&key, held) in &mut self.held {
+            let in_chord = chord
+                .as_ref()
+                .is_some_and(|chord| chord.keys.contains(&key));
+            if !held.consumed && !in_chord && now >= held.pressed + long_press {
+                held.consumed = true;
+                gestures.push(GestureEvent {
+                    gesture: Gesture::LongPress(key),
+                    timestamp: held.pressed + long_press,
+                });
+            }
+        }
+
+        let window = self.config.double_tap_window;
+        let mut expired = Vec::new();
+        self.taps.retain(|&key, &mut released| {
+            if now >= released + window {
+                expired.push(GestureEvent {
+                    gesture: Gesture::Tap(key),
+                    timestamp: released + window,
+                });
+                false
+            } else {
+                true
+            }
+        });
+        gestures.extend(expired);
+
+        gestures.sort_by_key(|gesture| gesture.timestamp);
+        gestures
+    }
+
+    /// When `poll` needs to be called next, `None` if nothing is pending
+    pub fn next_deadline(&self) -> Option<Instant> {
+        let chord = self
+            .chord
+            .as_ref()
+            .map(|chord| chord.start + self.config.chord_window);
+        let long_presses = self
+            .held
+            .values()
+            .filter(|held| !held.consumed)
+            .map(|held| held.pressed + self.config.long_press);
+        let taps = self
+            .taps
+            .values()
+            .map(|&released| released + self.config.double_tap_window);
+        chord.into_iter().chain(long_presses).chain(taps).min()
+    }
+
+    /// Forgets held keys and pending gestures, e.g. after the device was disconnected
+    pub fn reset(&mut self) {
+        self.held.clear();
+        self.chord = None;
+        self.taps.clear();
+    }
+
+    fn press(&mut self, key: GestureKey, now: Instant) {
+        if self.held.contains_key(&key) {
+            return;
+        }
+        self.held.insert(
+            key,
+            Held {
+                pressed: now,
+                consumed: false,
+            },
+        );
+        match self.chord {
+            Some(ref mut chord) => chord.keys.push(key),
+            None => {
+                self.chord = Some(PendingChord {
+                    start: now,
+                    keys: vec![key],
+                })
+            }
+        }
+    }
+
+    fn release(&mut self, key: GestureKey, now: Instant, gestures: &mut Vec<GestureEvent>) {
+        if self
+            .chord
+            .as_ref()
+            .is_some_and(|chord| chord.keys.contains(&key))
+        {
+            self.finish_chord(now, gestures);
+        }
+
+        let held = match self.held.remove(&key) {
+            Some(held) => held,
+            None => return,
+        };
+        let duration = now.saturating_duration_since(held.pressed);
+        if held.consumed {
+            return;
+        }
+        if duration >= self.config.long_press {
+            gestures.push(GestureEvent {
+                gesture: Gesture::LongPress(key),
+                timestamp: now,
+            });
+        } else if duration <= self.config.tap_max {
+            self.tap(key, now, gestures);
+        }
+    }
+
+    fn tap(&mut self, key: GestureKey, now: Instant, gestures: &mut Vec<GestureEvent>) {
+        if self.config.double_tap_window == Duration::from_secs(0) {
+            gestures.push(GestureEvent {
+                gesture: Gesture::Tap(key),
+                timestamp: now,
+            });
+        } else if self.taps.remove(&key).is_some() {
+            // Expired taps were reported by `poll` before getting here
+            gestures.push(GestureEvent {
+                gesture: Gesture::DoubleTap(key),
+                timestamp: now,
+            });
+        } else {
+            self.taps.insert(key, now);
+        }
+    }
+
+    /// Reports the pending chord if more than one key is in it
+    fn finish_chord(&mut self, now: Instant, gestures: &mut Vec<GestureEvent>) {
+        let chord = match self.chord.take() {
+            Some(chord) => chord,
+            None => return,
+        };
+        if chord.keys.len() < 2 {
+            return;
+        }
+
+        for key in &chord.keys {
+            if let Some(held) = self.held.get_mut(key) {
+                held.consumed = true;
+            }
+        }
+        let timestamp = now.min(chord.start + self.config.chord_window);
+        gestures.push(GestureEvent {
+            gesture: Gesture::Chord(chord.keys),
+            timestamp,
+        });
+    }
+}
+
+/// The keys beyond the main block, which can't be remapped to more functions on the device
+fn is_extra_key(subtype: EventRadSubtype) -> bool {
+    matches!(
+        subtype,
+        EventRadSubtype::M1
+            | EventRadSubtype::M2
+            | EventRadSubtype::M3
+            | EventRadSubtype::M4
+            | EventRadSubtype::M5
+            | EventRadSubtype::Thumbster1
+            | EventRadSubtype::Thumbster2
+            | EventRadSubtype::Thumbster3
+    )
+}
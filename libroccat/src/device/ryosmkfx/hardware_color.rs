@@ -23,7 +23,7 @@ impl HardwareColor {
     }
 }
 
-#[cfg_attr(rustfmt, rustfmt_skip)]
+#[rustfmt::skip]
 const COLOR_TO_HARDWARE: [u16; 256] = [
     /*       0       1       2       3       4       5       6       7       8       9     */
     /*  0 */ 0x0000, 0x0001, 0x0002, 0x0003, 0x0004, 0x0005, 0x0006, 0x0007, 0x0008, 0x0009,
@@ -54,7 +54,7 @@ const COLOR_TO_HARDWARE: [u16; 256] = [
     /* 25 */ 0x0f3b, 0x0e3f, 0x0f3c, 0x0f3d, 0x0f3e, 0x0f3f,
 ];
 
-#[cfg_attr(rustfmt, rustfmt_skip)]
+#[rustfmt::skip]
 const LEVEL_TO_COLOR: [u8; 1009] = [
     /*        0     1     2     3     4     5     6     7     8     9   */
     /*   0 */ 0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09,
@@ -0,0 +1,352 @@
+use super::{sdk_index_to_light_index, SDK_KEY_COUNT};
+use crate::Error;
+use std::{fmt, str::FromStr};
+
+/// A physical key of the Ryos MK FX, named after its US layout legend
+///
+/// Values are SDK indices for the keys that have one. ANSI boards report their backslash key,
+/// which sits above Enter, where ISO boards have their `#` key, so both are `Backslash`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[repr(u8)]
+pub enum KeyId {
+    // Keys with an SDK index, in SDK index order
+    Esc = 0,
+    F1 = 1,
+    F2 = 2,
+    F3 = 3,
+    F4 = 4,
+    F5 = 5,
+    F6 = 6,
+    F7 = 7,
+    F8 = 8,
+    F9 = 9,
+    F10 = 10,
+    F11 = 11,
+    F12 = 12,
+    PrintScreen = 13,
+    ScrollLock = 14,
+    Pause = 15,
+    M1 = 16,
+    Grave = 17,
+    Num1 = 18,
+    Num2 = 19,
+    Num3 = 20,
+    Num4 = 21,
+    Num5 = 22,
+    Num6 = 23,
+    Num7 = 24,
+    Num8 = 25,
+    Num9 = 26,
+    Num0 = 27,
+    Minus = 28,
+    Equal = 29,
+    Backspace = 30,
+    Insert = 31,
+    Home = 32,
+    PageUp = 33,
+    NumLock = 34,
+    KpDivide = 35,
+    KpMultiply = 36,
+    KpMinus = 37,
+    M2 = 38,
+    Tab = 39,
+    Q = 40,
+    W = 41,
+    E = 42,
+    R = 43,
+    T = 44,
+    Y = 45,
+    U = 46,
+    I = 47,
+    O = 48,
+    P = 49,
+    LeftBracket = 50,
+    RightBracket = 51,
+    Enter = 52,
+    Delete = 53,
+    End = 54,
+    PageDown = 55,
+    Kp7 = 56,
+    Kp8 = 57,
+    Kp9 = 58,
+    KpPlus = 59,
+    M3 = 60,
+    CapsLock = 61,
+    A = 62,
+    S = 63,
+    D = 64,
+    F = 65,
+    G = 66,
+    H = 67,
+    J = 68,
+    K = 69,
+    L = 70,
+    Semicolon = 71,
+    Apostrophe = 72,
+    Backslash = 73,
+    Kp4 = 74,
+    Kp5 = 75,
+    Kp6 = 76,
+    M4 = 77,
+    LeftShift = 78,
+    NonUsBackslash = 79,
+    Z = 80,
+    X = 81,
+    C = 82,
+    V = 83,
+    B = 84,
+    N = 85,
+    M = 86,
+    Comma = 87,
+    Period = 88,
+    Slash = 89,
+    RightShift = 90,
+    Up = 91,
+    Kp1 = 92,
+    Kp2 = 93,
+    Kp3 = 94,
+    KpEnter = 95,
+    M5 = 96,
+    LeftCtrl = 97,
+    LeftSuper = 98,
+    LeftAlt = 99,
+    Space = 100,
+    RightAlt = 101,
+    Fn = 102,
+    Menu = 103,
+    RightCtrl = 104,
+    Left = 105,
+    Down = 106,
+    Right = 107,
+    Kp0 = 108,
+    KpDecimal = 109,
+    // Keys without lights, which have no SDK index
+    Thumbster1 = 110,
+    Thumbster2 = 111,
+    Thumbster3 = 112,
+}
+
+impl KeyId {
+    /// Every key, in SDK index order followed by the keys without one
+    pub const ALL: [KeyId; 113] = ALL;
+
+    pub fn name(self) -> &'static str {
+        NAMES[self as usize]
+    }
+
+    /// Looks a key up by name, ignoring case
+    pub fn from_name(name: &str) -> Option<Self> {
+        NAMES
+            .iter()
+            .position(|candidate| candidate.eq_ignore_ascii_case(name))
+            .map(|index| ALL[index])
+    }
+
+    pub fn sdk_index(self) -> Option<u8> {
+        Some(self as u8).filter(|&index| index < SDK_KEY_COUNT)
+    }
+
+    pub fn from_sdk_index(sdk_index: u8) -> Option<Self> {
+        Some(sdk_index)
+            .filter(|&index| index < SDK_KEY_COUNT)
+            .map(|index| ALL[index as usize])
+    }
+
+    /// Index of the key in `LightLayerData`
+    pub fn light_index(self) -> Option<u8> {
+        self.sdk_index().map(sdk_index_to_light_index)
+    }
+
+    pub fn from_light_index(light_index: u8) -> Option<Self> {
+        ALL.iter()
+            .cloned()
+            .find(|key| key.light_index() == Some(light_index))
+    }
+
+    /// HID keyboard usage the key sends by default, `None` for keys handled by the device
+    pub fn hid_usage(self) -> Option<u8> {
+        Some(HID_USAGES[self as usize]).filter(|&usage| usage != 0x00)
+    }
+
+    /// Looks a key up by the HID keyboard usage it sends by default
+    ///
+    /// `Non-US #` (0x32) is `Backslash`, which sends it on ISO boards.
+    pub fn from_hid_usage(usage: u8) -> Option<Self> {
+        match usage {
+            0x00 => None,
+            0x32 => Some(KeyId::Backslash),
+            usage => HID_USAGES
+                .iter()
+                .position(|&candidate| candidate == usage)
+                .map(|index| ALL[index]),
+        }
+    }
+}
+
+impl fmt::Display for KeyId {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_str(self.name())
+    }
+}
+
+impl FromStr for KeyId {
+    type Err = Error;
+
+    fn from_str(name: &str) -> Result<Self, Error> {
+        Self::from_name(name).ok_or_else(|| Error::UnknownKey {
+            name: name.to_string(),
+        })
+    }
+}
+
+const ALL: [KeyId; 113] = [
+    KeyId::Esc,
+    KeyId::F1,
+    KeyId::F2,
+    KeyId::F3,
+    KeyId::F4,
+    KeyId::F5,
+    KeyId::F6,
+    KeyId::F7,
+    KeyId::F8,
+    KeyId::F9,
+    KeyId::F10,
+    KeyId::F11,
+    KeyId::F12,
+    KeyId::PrintScreen,
+    KeyId::ScrollLock,
+    KeyId::Pause,
+    KeyId::M1,
+    KeyId::Grave,
+    KeyId::Num1,
+    KeyId::Num2,
+    KeyId::Num3,
+    KeyId::Num4,
+    KeyId::Num5,
+    KeyId::Num6,
+    KeyId::Num7,
+    KeyId::Num8,
+    KeyId::Num9,
+    KeyId::Num0,
+    KeyId::Minus,
+    KeyId::Equal,
+    KeyId::Backspace,
+    KeyId::Insert,
+    KeyId::Home,
+    KeyId::PageUp,
+    KeyId::NumLock,
+    KeyId::KpDivide,
+    KeyId::KpMultiply,
+    KeyId::KpMinus,
+    KeyId::M2,
+    KeyId::Tab,
+    KeyId::Q,
+    KeyId::W,
+    KeyId::E,
+    KeyId::R,
+    KeyId::T,
+    KeyId::Y,
+    KeyId::U,
+    KeyId::I,
+    KeyId::O,
+    KeyId::P,
+    KeyId::LeftBracket,
+    KeyId::RightBracket,
+    KeyId::Enter,
+    KeyId::Delete,
+    KeyId::End,
+    KeyId::PageDown,
+    KeyId::Kp7,
+    KeyId::Kp8,
+    KeyId::Kp9,
+    KeyId::KpPlus,
+    KeyId::M3,
+    KeyId::CapsLock,
+    KeyId::A,
+    KeyId::S,
+    KeyId::D,
+    KeyId::F,
+    KeyId::G,
+    KeyId::H,
+    KeyId::J,
+    KeyId::K,
+    KeyId::L,
+    KeyId::Semicolon,
+    KeyId::Apostrophe,
+    KeyId::Backslash,
+    KeyId::Kp4,
+    KeyId::Kp5,
+    KeyId::Kp6,
+    KeyId::M4,
+    KeyId::LeftShift,
+    KeyId::NonUsBackslash,
+    KeyId::Z,
+    KeyId::X,
+    KeyId::C,
+    KeyId::V,
+    KeyId::B,
+    KeyId::N,
+    KeyId::M,
+    KeyId::Comma,
+    KeyId::Period,
+    KeyId::Slash,
+    KeyId::RightShift,
+    KeyId::Up,
+    KeyId::Kp1,
+    KeyId::Kp2,
+    KeyId::Kp3,
+    KeyId::KpEnter,
+    KeyId::M5,
+    KeyId::LeftCtrl,
+    KeyId::LeftSuper,
+    KeyId::LeftAlt,
+    KeyId::Space,
+    KeyId::RightAlt,
+    KeyId::Fn,
+    KeyId::Menu,
+    KeyId::RightCtrl,
+    KeyId::Left,
+    KeyId::Down,
+    KeyId::Right,
+    KeyId::Kp0,
+    KeyId::KpDecimal,
+    KeyId::Thumbster1,
+    KeyId::Thumbster2,
+    KeyId::Thumbster3,
+];
+
+#[rustfmt::skip]
+const NAMES: [&str; 113] = [
+    "Esc", "F1", "F2", "F3", "F4", "F5", "F6", "F7",
+    "F8", "F9", "F10", "F11", "F12", "PrintScreen", "ScrollLock", "Pause",
+    "M1", "Grave", "Num1", "Num2", "Num3", "Num4", "Num5", "Num6",
+    "Num7", "Num8", "Num9", "Num0", "Minus", "Equal", "Backspace", "Insert",
+    "Home", "PageUp", "NumLock", "KpDivide", "KpMultiply", "KpMinus", "M2", "Tab",
+    "Q", "W", "E", "R", "T", "Y", "U", "I",
+    "O", "P", "LeftBracket", "RightBracket", "Enter", "Delete", "End", "PageDown",
+    "Kp7", "Kp8", "Kp9", "KpPlus", "M3", "CapsLock", "A", "S",
+    "D", "F", "G", "H", "J", "K", "L", "Semicolon",
+    "Apostrophe", "Backslash", "Kp4", "Kp5", "Kp6", "M4", "LeftShift", "NonUsBackslash",
+    "Z", "X", "C", "V", "B", "N", "M", "Comma",
+    "Period", "Slash", "RightShift", "Up", "Kp1", "Kp2", "Kp3", "KpEnter",
+    "M5", "LeftCtrl", "LeftSuper", "LeftAlt", "Space", "RightAlt", "Fn", "Menu",
+    "RightCtrl", "Left", "Down", "Right", "Kp0", "KpDecimal", "Thumbster1", "Thumbster2",
+    "Thumbster3",
+];
+
+/// Default HID keyboard usage by key, 0x00 for none
+#[rustfmt::skip]
+const HID_USAGES: [u8; 113] = [
+    0x29, 0x3a, 0x3b, 0x3c, 0x3d, 0x3e, 0x3f, 0x40, 0x41, 0x42,
+    0x43, 0x44, 0x45, 0x46, 0x47, 0x48, 0x00, 0x35, 0x1e, 0x1f,
+    0x20, 0x21, 0x22, 0x23, 0x24, 0x25, 0x26, 0x27, 0x2d, 0x2e,
+    0x2a, 0x49, 0x4a, 0x4b, 0x53, 0x54, 0x55, 0x56, 0x00, 0x2b,
+    0x14, 0x1a, 0x08, 0x15, 0x17, 0x1c, 0x18, 0x0c, 0x12, 0x13,
+    0x2f, 0x30, 0x28, 0x4c, 0x4d, 0x4e, 0x5f, 0x60, 0x61, 0x57,
+    0x00, 0x39, 0x04, 0x16, 0x07, 0x09, 0x0a, 0x0b, 0x0d, 0x0e,
+    0x0f, 0x33, 0x34, 0x31, 0x5c, 0x5d, 0x5e, 0x00, 0xe1, 0x64,
+    0x1d, 0x1b, 0x06, 0x19, 0x05, 0x11, 0x10, 0x36, 0x37, 0x38,
+    0xe5, 0x52, 0x59, 0x5a, 0x5b, 0x58, 0x00, 0xe0, 0xe3, 0xe2,
+    0x2c, 0xe6, 0x00, 0x65, 0xe4, 0x50, 0x51, 0x4f, 0x62, 0x63,
+    0x00, 0x00, 0x00,
+];
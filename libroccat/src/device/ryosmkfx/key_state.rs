@@ -0,0 +1,85 @@
+use super::{DecodedEvent, Event};
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+/// How an event changed the set of held keys
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum KeyChange {
+    Pressed {
+        key: u8,
+    },
+    Released {
+        key: u8,
+        /// How long the key was held, `None` if its press was never seen
+        held: Option<Duration>,
+    },
+}
+
+/// Tracks which keys are held, fed with events from the device
+///
+/// Keys are SDK indices. Only `EventType::Effect` events carry key presses and releases, which
+/// the device only sends while per-key lighting is active.
+#[derive(Clone, Debug, Default)]
+pub struct KeyState {
+    /// When each held key was pressed
+    pressed: HashMap<u8, Instant>,
+}
+
+impl KeyState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Applies a press or release, ignoring other events and repeated presses
+    pub fn update(&mut self, event: &Event) -> Option<KeyChange> {
+        match event.decode() {
+            DecodedEvent::KeyPress { key } => {
+                if self.pressed.contains_key(&key) {
+                    return None;
+                }
+                self.pressed.insert(key, event.timestamp);
+                Some(KeyChange::Pressed { key })
+            }
+            DecodedEvent::KeyRelease { key } => {
+                let held = self
+                    .pressed
+                    .remove(&key)
+                    .map(|pressed| event.timestamp.saturating_duration_since(pressed));
+                Some(KeyChange::Released { key, held })
+            }
+            _ => None,
+        }
+    }
+
+    pub fn is_pressed(&self, key: u8) -> bool {
+        self.pressed.contains_key(&key)
+    }
+
+    /// Gets the held keys in the order they were pressed
+    pub fn pressed_keys(&self) -> Vec<u8> {
+        let mut keys: Vec<(u8, Instant)> = self
+            .pressed
+            .iter()
+            .map(|(&key, &pressed)| (key, pressed))
+            .collect();
+        keys.sort_by_key(|&(key, pressed)| (pressed, key));
+        keys.into_iter().map(|(key, _)| key).collect()
+    }
+
+    /// Gets when a held key was pressed
+    pub fn pressed_at(&self, key: u8) -> Option<Instant> {
+        self.pressed.get(&key).cloned()
+    }
+
+    /// Gets how long a key has been held so far, `None` if it isn't held
+    pub fn hold_duration(&self, key: u8) -> Option<Duration> {
+        self.pressed_at(key).map(|pressed| pressed.elapsed())
+    }
+
+    /// Forgets all held keys, e.g. after the device was disconnected
+    pub fn clear(&mut self) {
+        self.pressed.clear();
+    }
+}
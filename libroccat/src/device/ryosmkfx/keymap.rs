@@ -0,0 +1,397 @@
+use super::{
+    KeyId, PhysicalLayout, DEFAULT_KEYS_EASYZONE, DEFAULT_KEYS_FUNCTION, DEFAULT_KEYS_MACRO,
+    DEFAULT_KEYS_PRIMARY, DEFAULT_KEYS_THUMBSTER,
+};
+use crate::{
+    device::button::{
+        is_key_usage, ButtonConfig, ButtonFamily, ButtonModifiers, ButtonType, MultimediaKey,
+    },
+    Error,
+};
+
+/// `KeysPrimary` entry of disabled keys
+const PRIMARY_DISABLED: u8 = 0x00;
+
+/// Default capslock and Fn functions of `KeysExtra`
+const DEFAULT_KEYS_EXTRA: [u8; 3] = [0xff, 0xf1, 0x00];
+
+/// HID usage `KeysPrimary` uses for the Fn key
+const FN_USAGE: u8 = 0xf1;
+/// HID usage the backslash key sends on ISO boards
+const NON_US_HASH_USAGE: u8 = 0x32;
+
+/// Set of functions a key has, switched by holding Fn or Easyshift
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum KeyLayer {
+    Normal,
+    /// Only F1–F12, Print Screen, Scroll Lock and Pause have functions with Fn held
+    Fn,
+    Easyshift,
+}
+
+impl KeyLayer {
+    pub fn name(self) -> &'static str {
+        match self {
+            KeyLayer::Normal => "normal",
+            KeyLayer::Fn => "fn",
+            KeyLayer::Easyshift => "easyshift",
+        }
+    }
+}
+
+/// What a key does when pressed
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum KeyAction {
+    Disabled,
+    /// Sends a HID keyboard usage while holding modifiers
+    Key {
+        usage: u8,
+        modifiers: ButtonModifiers,
+    },
+    Multimedia(MultimediaKey),
+    ProfileUp,
+    ProfileDown,
+    ProfileCycle,
+    /// Plays the macro stored in a slot, see `MACRO_SLOT_COUNT`
+    Macro(u8),
+    /// Switches to the Easyshift layer while held
+    Easyshift,
+    /// Toggles the Easyshift layer
+    EasyshiftLock,
+    /// Any other function the keyboard has a code for
+    Function(ButtonType),
+    /// A function that isn't known, kept as the device stores it
+    Other(ButtonConfig),
+}
+
+impl KeyAction {
+    /// Plain key without modifiers
+    pub fn key(usage: u8) -> Self {
+        KeyAction::Key {
+            usage,
+            modifiers: ButtonModifiers::empty(),
+        }
+    }
+
+    pub fn from_button_config(config: ButtonConfig) -> Self {
+        let plain = config.modifier == 0 && config.key == 0;
+        match config.button_type(ButtonFamily::Keyboard) {
+            // Plain keys are stored without the shortcut type
+            Ok(ButtonType::Shortcut) => match config.modifiers() {
+                Ok(modifiers) if !modifiers.is_empty() || !is_key_usage(config.key) => {
+                    KeyAction::Key {
+                        usage: config.key,
+                        modifiers,
+                    }
+                }
+                _ => KeyAction::Other(config),
+            },
+            Ok(button_type) if plain => Self::from_button_type(button_type),
+            _ => KeyAction::Other(config),
+        }
+    }
+
+    pub fn to_button_config(self) -> Result<ButtonConfig, Error> {
+        let family = ButtonFamily::Keyboard;
+        match self {
+            KeyAction::Key { usage, modifiers } if modifiers.is_empty() && is_key_usage(usage) => {
+                ButtonConfig::from_type(family, ButtonType::Key(usage))
+            }
+            KeyAction::Key { usage, modifiers } => ButtonConfig::shortcut(family, modifiers, usage),
+            KeyAction::Other(config) => Ok(config),
+            KeyAction::Disabled => ButtonConfig::from_type(family, ButtonType::Disabled),
+            KeyAction::Multimedia(key) => {
+                ButtonConfig::from_type(family, ButtonType::Multimedia(key))
+            }
+            KeyAction::ProfileUp => ButtonConfig::from_type(family, ButtonType::ProfileUp),
+            KeyAction::ProfileDown => ButtonConfig::from_type(family, ButtonType::ProfileDown),
+            KeyAction::ProfileCycle => ButtonConfig::from_type(family, ButtonType::ProfileCycle),
+            KeyAction::Macro(slot) => ButtonConfig::from_type(family, ButtonType::MacroSlot(slot)),
+            KeyAction::Easyshift => ButtonConfig::from_type(family, ButtonType::Easyshift),
+            KeyAction::EasyshiftLock => ButtonConfig::from_type(family, ButtonType::EasyshiftLock),
+            KeyAction::Function(button_type) => ButtonConfig::from_type(family, button_type),
+        }
+    }
+
+    /// Decodes a `KeysPrimary` entry
+    pub fn from_primary(usage: u8) -> Self {
+        match usage {
+            PRIMARY_DISABLED => KeyAction::Disabled,
+            usage if is_key_usage(usage) => KeyAction::key(usage),
+            usage => KeyAction::Other(ButtonConfig::new(usage, 0, 0)),
+        }
+    }
+
+    /// Encodes a `KeysPrimary` entry, which can only hold plain keys
+    pub fn to_primary(self) -> Option<u8> {
+        match self {
+            KeyAction::Disabled => Some(PRIMARY_DISABLED),
+            KeyAction::Key { usage, modifiers } if modifiers.is_empty() => Some(usage),
+            KeyAction::Other(ButtonConfig {
+                type_,
+                modifier: 0,
+                key: 0,
+            }) => Some(type_),
+            _ => None,
+        }
+    }
+
+    fn from_button_type(button_type: ButtonType) -> Self {
+        match button_type {
+            ButtonType::Disabled => KeyAction::Disabled,
+            ButtonType::Key(usage) => KeyAction::key(usage),
+            ButtonType::Multimedia(key) => KeyAction::Multimedia(key),
+            ButtonType::ProfileUp => KeyAction::ProfileUp,
+            ButtonType::ProfileDown => KeyAction::ProfileDown,
+            ButtonType::ProfileCycle => KeyAction::ProfileCycle,
+            ButtonType::MacroSlot(slot) => KeyAction::Macro(slot),
+            ButtonType::Easyshift => KeyAction::Easyshift,
+            ButtonType::EasyshiftLock => KeyAction::EasyshiftLock,
+            ButtonType::Unknown(code) => KeyAction::Other(ButtonConfig::new(code, 0, 0)),
+            button_type => KeyAction::Function(button_type),
+        }
+    }
+}
+
+/// Where a key's function is stored
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Slot {
+    Primary(usize),
+    Function(usize),
+    Macro(usize),
+    Thumbster(usize),
+    Easyzone(usize),
+}
+
+impl Slot {
+    fn of(key: KeyId, layer: KeyLayer, physical: PhysicalLayout) -> Option<Slot> {
+        let index = key as usize;
+        let extra = match key {
+            KeyId::M1 | KeyId::Thumbster1 => Some(0),
+            KeyId::M2 | KeyId::Thumbster2 => Some(1),
+            KeyId::M3 | KeyId::Thumbster3 => Some(2),
+            KeyId::M4 => Some(3),
+            KeyId::M5 => Some(4),
+            _ => None,
+        };
+        let is_thumbster = index >= KeyId::Thumbster1 as usize;
+
+        match (layer, extra) {
+            (KeyLayer::Normal, Some(extra)) if is_thumbster => Some(Slot::Thumbster(extra)),
+            (KeyLayer::Easyshift, Some(extra)) if is_thumbster => Some(Slot::Thumbster(3 + extra)),
+            (KeyLayer::Normal, Some(extra)) => Some(Slot::Macro(extra)),
+            (KeyLayer::Easyshift, Some(extra)) => Some(Slot::Macro(5 + extra)),
+            // F1 to Pause follow Escape in SDK order
+            (KeyLayer::Normal, None) if (1..=15).contains(&index) => {
+                Some(Slot::Function(index - 1))
+            }
+            (KeyLayer::Fn, None) if (1..=15).contains(&index) => {
+                Some(Slot::Function(15 + index - 1))
+            }
+            (KeyLayer::Fn, _) => None,
+            (KeyLayer::Normal, None) => {
+                let usage = default_usage(key, physical)?;
+                DEFAULT_KEYS_PRIMARY
+                    .iter()
+                    .position(|&entry| entry == usage)
+                    .map(Slot::Primary)
+            }
+            (KeyLayer::Easyshift, None) => {
+                let usage = default_usage(key, physical)?;
+                DEFAULT_KEYS_EASYZONE
+                    .iter()
+                    .position(|entry| entry.type_ == usage)
+                    .map(Slot::Easyzone)
+            }
+        }
+    }
+}
+
+/// Usage the key sends by default, which the device looks its functions up by
+fn default_usage(key: KeyId, physical: PhysicalLayout) -> Option<u8> {
+    match (key, physical) {
+        (KeyId::Fn, _) => Some(FN_USAGE),
+        (KeyId::Backslash, PhysicalLayout::Iso) => Some(NON_US_HASH_USAGE),
+        (key, _) => key.hid_usage(),
+    }
+}
+
+/// Contents of the `Keys*` reports
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) struct KeyTables {
+    pub primary: [u8; 120],
+    pub function: [ButtonConfig; 15 * 2],
+    pub macro_: [ButtonConfig; 5 * 2],
+    pub thumbster: [ButtonConfig; 3 * 2],
+    pub extra: [u8; 3],
+    pub easyzone: [ButtonConfig; 96],
+}
+
+/// Which of the `Keys*` reports differ from what was read
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct KeymapChanges {
+    pub primary: bool,
+    pub function: bool,
+    pub macro_: bool,
+    pub thumbster: bool,
+    pub extra: bool,
+    pub easyzone: bool,
+}
+
+impl KeymapChanges {
+    pub fn any(&self) -> bool {
+        self.primary
+            || self.function
+            || self.macro_
+            || self.thumbster
+            || self.extra
+            || self.easyzone
+    }
+}
+
+/// Functions of every key of a profile, kept in all six `Keys*` reports
+///
+/// Read one with `RyosMkFx::get_keymap` and write it back with `RyosMkFx::set_keymap`, which
+/// only writes the reports that changed since.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Keymap {
+    profile: u8,
+    /// Decides where the backslash key's functions are, which sends a different usage on ISO
+    /// boards
+    physical: PhysicalLayout,
+    tables: KeyTables,
+    /// What the device has, `None` if unknown
+    saved: Option<KeyTables>,
+}
+
+impl Keymap {
+    /// The default functions, which `RyosMkFx::set_keymap` writes in full
+    pub fn new(profile: u8, physical: PhysicalLayout) -> Self {
+        let tables = KeyTables {
+            primary: DEFAULT_KEYS_PRIMARY,
+            function: DEFAULT_KEYS_FUNCTION,
+            macro_: DEFAULT_KEYS_MACRO,
+            thumbster: DEFAULT_KEYS_THUMBSTER,
+            extra: DEFAULT_KEYS_EXTRA,
+            easyzone: DEFAULT_KEYS_EASYZONE,
+        };
+        Self {
+            profile,
+            physical,
+            tables,
+            saved: None,
+        }
+    }
+
+    /// Keymap as read from the device
+    pub(crate) fn from_tables(profile: u8, physical: PhysicalLayout, tables: KeyTables) -> Self {
+        Self {
+            profile,
+            physical,
+            tables,
+            saved: Some(tables),
+        }
+    }
+
+    pub fn profile(&self) -> u8 {
+        self.profile
+    }
+
+    pub fn physical_layout(&self) -> PhysicalLayout {
+        self.physical
+    }
+
+    /// Gets a key's function, `None` if it has none on this layer
+    pub fn get(&self, key: KeyId, layer: KeyLayer) -> Option<KeyAction> {
+        Slot::of(key, layer, self.physical).map(|slot| self.action(slot))
+    }
+
+    /// Sets a key's function
+    ///
+    /// Keys in the main block only take plain keys without modifiers on the normal layer.
+    pub fn set(&mut self, key: KeyId, layer: KeyLayer, action: KeyAction) -> Result<(), Error> {
+        let unsupported = || Error::UnsupportedKeyAction {
+            key: key.name(),
+            layer: layer.name(),
+        };
+        match Slot::of(key, layer, self.physical).ok_or_else(unsupported)? {
+            Slot::Primary(index) => {
+                self.tables.primary[index] = action.to_primary().ok_or_else(unsupported)?
+            }
+            Slot::Function(index) => self.tables.function[index] = action.to_button_config()?,
+            Slot::Macro(index) => self.tables.macro_[index] = action.to_button_config()?,
+            Slot::Thumbster(index) => self.tables.thumbster[index] = action.to_button_config()?,
+            Slot::Easyzone(index) => self.tables.easyzone[index] = action.to_button_config()?,
+        }
+        Ok(())
+    }
+
+    /// Gives a key its default function back
+    pub fn reset(&mut self, key: KeyId, layer: KeyLayer) -> Result<(), Error> {
+        match Keymap::new(self.profile, self.physical).get(key, layer) {
+            Some(action) => self.set(key, layer, action),
+            None => Err(Error::UnsupportedKeyAction {
+                key: key.name(),
+                layer: layer.name(),
+            }),
+        }
+    }
+
+    /// Capslock and Fn functions of `KeysExtra`, which aren't documented
+    pub fn extra(&self) -> [u8; 3] {
+        self.tables.extra
+    }
+
+    pub fn set_extra(&mut self, extra: [u8; 3]) {
+        self.tables.extra = extra;
+    }
+
+    /// Reports that differ from the device, all of them for keymaps that weren't read from it
+    pub fn changes(&self) -> KeymapChanges {
+        let tables = &self.tables;
+        let saved = match self.saved {
+            Some(ref saved) => saved,
+            None => {
+                return KeymapChanges {
+                    primary: true,
+                    function: true,
+                    macro_: true,
+                    thumbster: true,
+                    extra: true,
+                    easyzone: true,
+                }
+            }
+        };
+        KeymapChanges {
+            primary: tables.primary != saved.primary,
+            function: tables.function != saved.function,
+            macro_: tables.macro_ != saved.macro_,
+            thumbster: tables.thumbster != saved.thumbster,
+            extra: tables.extra != saved.extra,
+            easyzone: tables.easyzone != saved.easyzone,
+        }
+    }
+
+    pub fn is_modified(&self) -> bool {
+        self.changes().any()
+    }
+
+    pub(crate) fn tables(&self) -> &KeyTables {
+        &self.tables
+    }
+
+    /// Records that the device has every report as it is now
+    pub(crate) fn mark_saved(&mut self) {
+        self.saved = Some(self.tables);
+    }
+
+    fn action(&self, slot: Slot) -> KeyAction {
+        match slot {
+            Slot::Primary(index) => KeyAction::from_primary(self.tables.primary[index]),
+            Slot::Function(index) => KeyAction::from_button_config(self.tables.function[index]),
+            Slot::Macro(index) => KeyAction::from_button_config(self.tables.macro_[index]),
+            Slot::Thumbster(index) => KeyAction::from_button_config(self.tables.thumbster[index]),
+            Slot::Easyzone(index) => KeyAction::from_button_config(self.tables.easyzone[index]),
+        }
+    }
+}
@@ -1,6 +1,6 @@
 use crate::device::button::*;
 
-#[cfg_attr(rustfmt, rustfmt_skip)]
+#[rustfmt::skip]
 pub const DEFAULT_KEYS_PRIMARY: [u8; 120] = [
     0xaa, 0x29, 0x3a, 0x3c, 0x3e, 0x40, 0x42, 0x43, 0x44, 0x46,
     0x53, 0x55, 0x35, 0x1e, 0x3b, 0x3d, 0x3f, 0x41, 0x27, 0x2e,
@@ -16,7 +16,7 @@ pub const DEFAULT_KEYS_PRIMARY: [u8; 120] = [
     0xe2, 0x91, 0x2c, 0x88, 0xe6, 0xf1, 0x51, 0x52, 0x4f, 0xe7,
 ];
 
-#[cfg_attr(rustfmt, rustfmt_skip)]
+#[rustfmt::skip]
 pub const DEFAULT_KEYS_FUNCTION: [ButtonConfig; 15 * 2] = [
     ButtonConfig { type_: 0x3a, modifier: 0x00, key: 0x00, },
     ButtonConfig { type_: 0x3b, modifier: 0x00, key: 0x00, },
@@ -50,7 +50,7 @@ pub const DEFAULT_KEYS_FUNCTION: [ButtonConfig; 15 * 2] = [
     ButtonConfig { type_: 0x82, modifier: 0x00, key: 0x00, },
 ];
 
-#[cfg_attr(rustfmt, rustfmt_skip)]
+#[rustfmt::skip]
 pub const DEFAULT_KEYS_MACRO: [ButtonConfig; 5 * 2] = [
     ButtonConfig { type_: 0xD3, modifier: 0x00, key: 0x00, },
     ButtonConfig { type_: 0xD4, modifier: 0x00, key: 0x00, },
@@ -64,7 +64,7 @@ pub const DEFAULT_KEYS_MACRO: [ButtonConfig; 5 * 2] = [
     ButtonConfig { type_: 0xDC, modifier: 0x00, key: 0x00, },
 ];
 
-#[cfg_attr(rustfmt, rustfmt_skip)]
+#[rustfmt::skip]
 pub const DEFAULT_KEYS_THUMBSTER: [ButtonConfig; 3 * 2] = [
     ButtonConfig { type_: 0xb1, modifier: 0x00, key: 0x00, },
     ButtonConfig { type_: 0xdd, modifier: 0x00, key: 0x00, },
@@ -74,7 +74,7 @@ pub const DEFAULT_KEYS_THUMBSTER: [ButtonConfig; 3 * 2] = [
     ButtonConfig { type_: 0xc0, modifier: 0x00, key: 0x00, },
 ];
 
-#[cfg_attr(rustfmt, rustfmt_skip)]
+#[rustfmt::skip]
 pub const DEFAULT_KEYS_EASYZONE: [ButtonConfig; 96] = [
     ButtonConfig { type_: 0x35, modifier: 0x00, key: 0x00, },
     ButtonConfig { type_: 0x1e, modifier: 0x00, key: 0x00, },
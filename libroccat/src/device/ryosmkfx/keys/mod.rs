@@ -2,16 +2,13 @@ mod defaults;
 
 pub use self::defaults::*;
 use crate::device::button::*;
-use hidraw_derive::{HidrawRead, HidrawWrite};
+use hidraw_derive::{HidrawRead, HidrawWrite, ReportField};
 use std::{self, fmt};
 
-#[derive(HidrawRead, HidrawWrite, Copy, Clone)]
+#[derive(ReportField, HidrawRead, HidrawWrite, Copy, Clone)]
 #[repr(C, packed)]
+#[hidraw_report(id = 0x06, len = 125)]
 pub struct KeysPrimary {
-    #[hidraw_constant = "0x06"]
-    _report_id: u8,
-    #[hidraw_constant = "::std::mem::size_of::<Self>() as u8"]
-    _size: u8,
     pub profile_index: u8,
     pub keys: [u8; 120], // Just key mappings
     #[hidraw_bytesum]
@@ -23,7 +20,7 @@ impl KeysPrimary {
         Self {
             profile_index,
             keys,
-            ..unsafe { std::mem::uninitialized() }
+            _bytesum: Default::default(),
         }
     }
 }
@@ -33,7 +30,7 @@ impl Default for KeysPrimary {
         Self {
             profile_index: Default::default(),
             keys: DEFAULT_KEYS_PRIMARY,
-            ..unsafe { std::mem::uninitialized() }
+            _bytesum: Default::default(),
         }
     }
 }
@@ -42,8 +39,6 @@ impl fmt::Debug for KeysPrimary {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
         let bytesum = self._bytesum;
         fmt.debug_struct("KeysPrimary")
-            .field("_report_id", &self._report_id)
-            .field("_size", &self._size)
             .field("profile_index", &self.profile_index)
             .field("keys", &&self.keys[..])
             .field("_bytesum", &bytesum)
@@ -51,13 +46,10 @@ impl fmt::Debug for KeysPrimary {
     }
 }
 
-#[derive(HidrawRead, HidrawWrite)]
+#[derive(ReportField, HidrawRead, HidrawWrite)]
 #[repr(C, packed)]
+#[hidraw_report(id = 0x07, len = 95)]
 pub struct KeysFunction {
-    #[hidraw_constant = "0x07"]
-    _report_id: u8,
-    #[hidraw_constant = "::std::mem::size_of::<Self>() as u8"]
-    _size: u8,
     pub profile_index: u8,
     pub keys: [ButtonConfig; 15 * 2], // Regular and with Fn pressed
     #[hidraw_bytesum]
@@ -69,7 +61,7 @@ impl KeysFunction {
         Self {
             profile_index,
             keys,
-            ..unsafe { std::mem::uninitialized() }
+            _bytesum: Default::default(),
         }
     }
 }
@@ -79,7 +71,7 @@ impl Default for KeysFunction {
         Self {
             profile_index: Default::default(),
             keys: DEFAULT_KEYS_FUNCTION,
-            ..unsafe { std::mem::uninitialized() }
+            _bytesum: Default::default(),
         }
     }
 }
@@ -88,8 +80,6 @@ impl fmt::Debug for KeysFunction {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
         let bytesum = self._bytesum;
         fmt.debug_struct("KeysFunction")
-            .field("_report_id", &self._report_id)
-            .field("_size", &self._size)
             .field("profile_index", &self.profile_index)
             .field("keys", &&self.keys[..])
             .field("_bytesum", &bytesum)
@@ -97,13 +87,10 @@ impl fmt::Debug for KeysFunction {
     }
 }
 
-#[derive(HidrawRead, HidrawWrite)]
+#[derive(ReportField, HidrawRead, HidrawWrite)]
 #[repr(C, packed)]
+#[hidraw_report(id = 0x08, len = 35)]
 pub struct KeysMacro {
-    #[hidraw_constant = "0x08"]
-    _report_id: u8,
-    #[hidraw_constant = "::std::mem::size_of::<Self>() as u8"]
-    _size: u8,
     pub profile_index: u8,
     pub keys: [ButtonConfig; 5 * 2], // Regular and with EasyShift pressed
     #[hidraw_bytesum]
@@ -115,7 +102,7 @@ impl KeysMacro {
         Self {
             profile_index,
             keys,
-            ..unsafe { std::mem::uninitialized() }
+            _bytesum: Default::default(),
         }
     }
 }
@@ -125,7 +112,7 @@ impl Default for KeysMacro {
         Self {
             profile_index: Default::default(),
             keys: DEFAULT_KEYS_MACRO,
-            ..unsafe { std::mem::uninitialized() }
+            _bytesum: Default::default(),
         }
     }
 }
@@ -134,8 +121,6 @@ impl fmt::Debug for KeysMacro {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
         let bytesum = self._bytesum;
         fmt.debug_struct("KeysMacro")
-            .field("_report_id", &self._report_id)
-            .field("_size", &self._size)
             .field("profile_index", &self.profile_index)
             .field("keys", &&self.keys[..])
             .field("_bytesum", &bytesum)
@@ -143,13 +128,10 @@ impl fmt::Debug for KeysMacro {
     }
 }
 
-#[derive(HidrawRead, HidrawWrite)]
+#[derive(ReportField, HidrawRead, HidrawWrite)]
 #[repr(C, packed)]
+#[hidraw_report(id = 0x09, len = 23)]
 pub struct KeysThumbster {
-    #[hidraw_constant = "0x09"]
-    _report_id: u8,
-    #[hidraw_constant = "::std::mem::size_of::<Self>() as u8"]
-    _size: u8,
     pub profile_index: u8,
     pub keys: [ButtonConfig; 3 * 2], // Regular and with EasyShift pressed
     #[hidraw_bytesum]
@@ -161,7 +143,7 @@ impl KeysThumbster {
         Self {
             profile_index,
             keys,
-            ..unsafe { std::mem::uninitialized() }
+            _bytesum: Default::default(),
         }
     }
 }
@@ -171,7 +153,7 @@ impl Default for KeysThumbster {
         Self {
             profile_index: Default::default(),
             keys: DEFAULT_KEYS_THUMBSTER,
-            ..unsafe { std::mem::uninitialized() }
+            _bytesum: Default::default(),
         }
     }
 }
@@ -180,8 +162,6 @@ impl fmt::Debug for KeysThumbster {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
         let bytesum = self._bytesum;
         fmt.debug_struct("KeysThumbster")
-            .field("_report_id", &self._report_id)
-            .field("_size", &self._size)
             .field("profile_index", &self.profile_index)
             .field("keys", &&self.keys[..])
             .field("_bytesum", &bytesum)
@@ -189,13 +169,10 @@ impl fmt::Debug for KeysThumbster {
     }
 }
 
-#[derive(HidrawRead, HidrawWrite, Copy, Clone, Debug)]
+#[derive(ReportField, HidrawRead, HidrawWrite, Copy, Clone, Debug)]
 #[repr(C, packed)]
+#[hidraw_report(id = 0x0a, len = 8)]
 pub struct KeysExtra {
-    #[hidraw_constant = "0x0a"]
-    _report_id: u8,
-    #[hidraw_constant = "::std::mem::size_of::<Self>() as u8"]
-    _size: u8,
     pub profile_index: u8,
     pub capslock: u8,
     pub fn_: u8,
@@ -211,7 +188,7 @@ impl KeysExtra {
             capslock,
             fn_,
             unused,
-            ..unsafe { std::mem::uninitialized() }
+            _bytesum: Default::default(),
         }
     }
 }
@@ -223,18 +200,15 @@ impl Default for KeysExtra {
             capslock: 0xff,
             fn_: 0xf1,
             unused: 0x00,
-            ..unsafe { std::mem::uninitialized() }
+            _bytesum: Default::default(),
         }
     }
 }
 
-#[derive(HidrawRead, HidrawWrite)]
+#[derive(ReportField, HidrawRead, HidrawWrite)]
 #[repr(C, packed)]
+#[hidraw_report(id = 0x0b, len = 294, size = "u16")]
 pub struct KeysEasyzone {
-    #[hidraw_constant = "0x0b"]
-    _report_id: u8,
-    #[hidraw_constant = "::std::mem::size_of::<Self>() as u16"]
-    _size: u16,
     pub profile_index: u8,
     pub keys: [ButtonConfig; 96], // Just key mappings
     #[hidraw_bytesum]
@@ -246,7 +220,7 @@ impl KeysEasyzone {
         Self {
             profile_index,
             keys,
-            ..unsafe { std::mem::uninitialized() }
+            _bytesum: Default::default(),
         }
     }
 }
@@ -256,18 +230,15 @@ impl Default for KeysEasyzone {
         Self {
             profile_index: Default::default(),
             keys: DEFAULT_KEYS_EASYZONE,
-            ..unsafe { std::mem::uninitialized() }
+            _bytesum: Default::default(),
         }
     }
 }
 
 impl fmt::Debug for KeysEasyzone {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-        let size = self._size;
         let bytesum = self._bytesum;
         fmt.debug_struct("KeysEasyzone")
-            .field("_report_id", &self._report_id)
-            .field("_size", &size)
             .field("profile_index", &self.profile_index)
             .field("keys", &&self.keys[..])
             .field("_bytesum", &bytesum)
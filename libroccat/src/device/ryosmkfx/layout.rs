@@ -0,0 +1,159 @@
+use super::{Geometry, KeyId, PhysicalLayout};
+use crate::Error;
+use std::{fmt, str::FromStr};
+
+/// A national keyboard layout, which decides the label printed on each key
+///
+/// Keys without a character keep their English labels, except for AltGr. Keypad keys are
+/// labelled with a `Num` prefix, so they don't clash with the main block.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum KeyboardLayout {
+    /// US ANSI
+    #[default]
+    Us,
+    /// UK ISO
+    Uk,
+    /// German ISO, QWERTZ
+    De,
+    /// French ISO, AZERTY
+    Fr,
+    /// Swedish and Finnish ISO
+    Nordic,
+}
+
+impl KeyboardLayout {
+    pub const ALL: [KeyboardLayout; 5] = [
+        KeyboardLayout::Us,
+        KeyboardLayout::Uk,
+        KeyboardLayout::De,
+        KeyboardLayout::Fr,
+        KeyboardLayout::Nordic,
+    ];
+
+    /// Name used in scripts and configuration, like `de`
+    pub fn name(self) -> &'static str {
+        match self {
+            KeyboardLayout::Us => "us",
+            KeyboardLayout::Uk => "uk",
+            KeyboardLayout::De => "de",
+            KeyboardLayout::Fr => "fr",
+            KeyboardLayout::Nordic => "nordic",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .cloned()
+            .find(|layout| layout.name().eq_ignore_ascii_case(name))
+    }
+
+    pub fn physical(self) -> PhysicalLayout {
+        match self {
+            KeyboardLayout::Us => PhysicalLayout::Ansi,
+            _ => PhysicalLayout::Iso,
+        }
+    }
+
+    pub fn geometry(self) -> Geometry {
+        Geometry::new(self.physical())
+    }
+
+    /// Gets the label printed on a key, `None` if boards with this layout don't have it
+    pub fn label(self, key: KeyId) -> Option<&'static str> {
+        if self.physical() == PhysicalLayout::Ansi && key == KeyId::NonUsBackslash {
+            return None;
+        }
+        let overrides: &[(KeyId, &str)] = match self {
+            KeyboardLayout::Us => &[],
+            KeyboardLayout::Uk => &UK_LABELS,
+            KeyboardLayout::De => &DE_LABELS,
+            KeyboardLayout::Fr => &FR_LABELS,
+            KeyboardLayout::Nordic => &NORDIC_LABELS,
+        };
+        let label = overrides
+            .iter()
+            .find(|&&(candidate, _)| candidate == key)
+            .map_or(US_LABELS[key as usize], |&(_, label)| label);
+        Some(label)
+    }
+
+    /// Looks a key up by its label, ignoring case, or else by its `KeyId` name
+    ///
+    /// Labels are unique within a layout, which is why the modifiers are `Left Shift` and such.
+    pub fn key(self, label: &str) -> Option<KeyId> {
+        let label_lowercase = label.to_lowercase();
+        KeyId::ALL
+            .iter()
+            .cloned()
+            .find(|&key| {
+                self.label(key)
+                    .is_some_and(|candidate| candidate.to_lowercase() == label_lowercase)
+            })
+            .or_else(|| KeyId::from_name(label))
+    }
+}
+
+impl fmt::Display for KeyboardLayout {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_str(self.name())
+    }
+}
+
+impl FromStr for KeyboardLayout {
+    type Err = Error;
+
+    fn from_str(name: &str) -> Result<Self, Error> {
+        Self::from_name(name).ok_or_else(|| Error::UnknownLayout {
+            name: name.to_string(),
+        })
+    }
+}
+
+/// Labels of the US layout by `KeyId`, which the other layouts only override
+#[rustfmt::skip]
+const US_LABELS: [&str; 113] = [
+    "Esc", "F1", "F2", "F3", "F4", "F5", "F6", "F7", "F8", "F9", "F10", "F11", "F12", "Print",
+    "Scroll Lock", "Pause", "M1", "`", "1", "2", "3", "4", "5", "6", "7", "8", "9", "0", "-", "=",
+    "Backspace", "Insert", "Home", "Page Up", "Num Lock", "Num /", "Num *", "Num -", "M2", "Tab",
+    "Q", "W", "E", "R", "T", "Y", "U", "I", "O", "P", "[", "]", "Enter", "Delete", "End",
+    "Page Down", "Num 7", "Num 8", "Num 9", "Num +", "M3", "Caps Lock", "A", "S", "D", "F", "G",
+    "H", "J", "K", "L", ";", "'", "\\", "Num 4", "Num 5", "Num 6", "M4", "Left Shift", "\\", "Z",
+    "X", "C", "V", "B", "N", "M", ",", ".", "/", "Right Shift", "Up", "Num 1", "Num 2", "Num 3",
+    "Num Enter", "M5", "Left Ctrl", "Win", "Alt", "Space", "Right Alt", "Fn", "Menu", "Right Ctrl",
+    "Left", "Down", "Right", "Num 0", "Num .", "Thumbster 1", "Thumbster 2", "Thumbster 3",
+];
+
+const ALT_GR: (KeyId, &str) = (KeyId::RightAlt, "AltGr");
+
+#[rustfmt::skip]
+const UK_LABELS: [(KeyId, &str); 3] = [
+    (KeyId::Backslash, "#"), (KeyId::NonUsBackslash, "\\"), ALT_GR,
+];
+
+#[rustfmt::skip]
+const DE_LABELS: [(KeyId, &str); 13] = [
+    (KeyId::Grave, "^"), (KeyId::Minus, "ß"), (KeyId::Equal, "´"),
+    (KeyId::Y, "Z"), (KeyId::LeftBracket, "Ü"), (KeyId::RightBracket, "+"),
+    (KeyId::Semicolon, "Ö"), (KeyId::Apostrophe, "Ä"), (KeyId::Backslash, "#"),
+    (KeyId::NonUsBackslash, "<"), (KeyId::Z, "Y"), (KeyId::Slash, "-"), ALT_GR,
+];
+
+#[rustfmt::skip]
+const FR_LABELS: [(KeyId, &str); 27] = [
+    (KeyId::Grave, "²"), (KeyId::Num1, "&"), (KeyId::Num2, "é"), (KeyId::Num3, "\""),
+    (KeyId::Num4, "'"), (KeyId::Num5, "("), (KeyId::Num6, "-"), (KeyId::Num7, "è"),
+    (KeyId::Num8, "_"), (KeyId::Num9, "ç"), (KeyId::Num0, "à"), (KeyId::Minus, ")"),
+    (KeyId::Q, "A"), (KeyId::W, "Z"), (KeyId::LeftBracket, "^"), (KeyId::RightBracket, "$"),
+    (KeyId::A, "Q"), (KeyId::Semicolon, "M"), (KeyId::Apostrophe, "ù"), (KeyId::Backslash, "*"),
+    (KeyId::NonUsBackslash, "<"), (KeyId::Z, "W"), (KeyId::M, ","), (KeyId::Comma, ";"),
+    (KeyId::Period, ":"), (KeyId::Slash, "!"), ALT_GR,
+];
+
+#[rustfmt::skip]
+const NORDIC_LABELS: [(KeyId, &str); 11] = [
+    (KeyId::Grave, "§"), (KeyId::Minus, "+"), (KeyId::Equal, "´"),
+    (KeyId::LeftBracket, "Å"), (KeyId::RightBracket, "¨"), (KeyId::Semicolon, "Ö"),
+    (KeyId::Apostrophe, "Ä"), (KeyId::Backslash, "'"), (KeyId::NonUsBackslash, "<"),
+    (KeyId::Slash, "-"), ALT_GR,
+];
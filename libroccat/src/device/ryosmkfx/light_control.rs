@@ -1,15 +1,18 @@
-use failure::{bail, Error};
-use hidraw_derive::{HidrawRead, HidrawWrite};
-use std::fs::File;
+use crate::{
+    transport::{default_write_check_timeout, poll_until, CancelToken, Transport},
+    Error,
+};
+use hidraw_derive::{HidrawRead, HidrawWrite, ReportField};
+use std::time::Duration;
 
-#[derive(Clone, Debug)]
+#[derive(ReportField, Copy, Clone, Debug)]
 #[repr(u8)]
 pub enum LightControlState {
     Stored = 0x00,
     Custom = 0x01,
 }
 
-#[derive(Clone, Debug)]
+#[derive(ReportField, Copy, Clone, Debug)]
 #[repr(u8)]
 pub enum LightControlWriteCheck {
     Ok = 0x01,
@@ -17,13 +20,10 @@ pub enum LightControlWriteCheck {
     Busy = 0x03,
 }
 
-#[derive(HidrawRead, HidrawWrite, Debug)]
+#[derive(ReportField, HidrawRead, HidrawWrite, Debug)]
 #[repr(C, packed)]
+#[hidraw_report(id = 0x13, len = 8)]
 pub struct LightControl {
-    #[hidraw_constant = "0x13"]
-    _report_id: u8,
-    #[hidraw_constant = "::std::mem::size_of::<Self> as u8"]
-    _size: u8,
     pub state: LightControlState,
     pub unknown0: [u8; 3],
     pub write_check: LightControlWriteCheck,
@@ -34,24 +34,33 @@ impl LightControl {
     pub fn new(state: LightControlState) -> Self {
         Self {
             state,
-            ..unsafe { ::std::mem::uninitialized() }
+            unknown0: Default::default(),
+            write_check: LightControlWriteCheck::Ok,
+            unknown1: Default::default(),
         }
     }
 
-    pub fn check_write(file: &File) -> Result<(), Error> {
-        unsafe {
-            loop {
-                use std::{thread::sleep, time::Duration};
-
-                sleep(Duration::from_millis(50));
+    /// Waits for the device to accept custom lights, using the default timeout
+    pub fn check_write<T: Transport + ?Sized>(interface: &T) -> Result<(), Error> {
+        Self::check_write_with(interface, default_write_check_timeout(), None)
+    }
 
-                let control = Self::read(file)?;
-                match control.write_check {
-                    LightControlWriteCheck::Ok => return Ok(()),
-                    LightControlWriteCheck::Busy => (),
-                    err => bail!("Write check returned {:?}", err),
-                }
+    /// Waits for the device to accept custom lights
+    ///
+    /// Fails with `Error::DeviceBusy` if the device is still busy after `timeout` and with
+    /// `Error::Cancelled` if `cancel` gets cancelled.
+    pub fn check_write_with<T: Transport + ?Sized>(
+        interface: &T,
+        timeout: Option<Duration>,
+        cancel: Option<&CancelToken>,
+    ) -> Result<(), Error> {
+        poll_until(timeout, cancel, || {
+            let control = Self::read(interface)?;
+            match control.write_check {
+                LightControlWriteCheck::Ok => Ok(true),
+                LightControlWriteCheck::Busy => Ok(false),
+                LightControlWriteCheck::Invalid => Err(Error::Rejected),
             }
-        }
+        })
     }
 }
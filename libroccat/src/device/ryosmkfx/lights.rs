@@ -1,13 +1,13 @@
-use hidraw_derive::{HidrawRead, HidrawWrite};
+use hidraw_derive::{HidrawRead, HidrawWrite, ReportField};
 
-#[derive(Copy, Clone, Debug)]
+#[derive(ReportField, Copy, Clone, Debug)]
 #[repr(u8)]
 pub enum LightMode {
     Plain = 0x00,
     Layer = 0x01,
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(ReportField, Copy, Clone, Debug)]
 #[repr(u8)]
 pub enum LightEffect {
     Off = 0x00,
@@ -23,14 +23,14 @@ pub enum LightEffect {
     Fade = 0x10,
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(ReportField, Copy, Clone, Debug)]
 #[repr(u8)]
 pub enum LightLedFeedback {
     Off = 0x00,
     MacroExecution = 0x01,
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(ReportField, Copy, Clone, Debug)]
 #[repr(u8)]
 pub enum LightDimnessType {
     Off = 0x00,
@@ -39,13 +39,10 @@ pub enum LightDimnessType {
     FallAsleep = 0x03,
 }
 
-#[derive(HidrawRead, HidrawWrite, Debug, Copy, Clone)]
+#[derive(ReportField, HidrawRead, HidrawWrite, Debug, Copy, Clone)]
 #[repr(C, packed)]
+#[hidraw_report(id = 0x0d, len = 32)]
 pub struct Lights {
-    #[hidraw_constant = "0x0d"]
-    _report_id: u8,
-    #[hidraw_constant = "::std::mem::size_of::<Self>() as u8"]
-    _size: u8,
     pub profile: u8,
     pub brightness: u8, // 0-5
     pub dimness: u8,    // 0-5
@@ -86,7 +83,7 @@ impl Default for Lights {
             green: 0xff,
             blue: 0xff,
             unused: Default::default(),
-            ..unsafe { ::std::mem::uninitialized() }
+            _bytesum: Default::default(),
         }
     }
 }
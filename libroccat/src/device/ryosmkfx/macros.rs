@@ -0,0 +1,363 @@
+use super::KeyId;
+use crate::Error;
+use hidraw_derive::{HidrawRead, HidrawWrite, ReportField};
+use std::{fmt, str::FromStr, time::Duration};
+
+/// Most steps a macro can have
+pub const MACRO_MAX_STEPS: usize = 480;
+
+/// Longest macro name in bytes, leaving room for the terminating NUL
+pub const MACRO_MAX_NAME_LEN: usize = NAME_LEN - 1;
+
+const NAME_LEN: usize = 24;
+
+const ACTION_DOWN: u8 = 0x01;
+const ACTION_UP: u8 = 0x02;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum MacroAction {
+    Down,
+    Up,
+}
+
+impl MacroAction {
+    pub fn name(self) -> &'static str {
+        match self {
+            MacroAction::Down => "down",
+            MacroAction::Up => "up",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "down" => Some(MacroAction::Down),
+            "up" => Some(MacroAction::Up),
+            _ => None,
+        }
+    }
+}
+
+/// Pressing or releasing a key, then waiting before the next step
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct MacroStep {
+    /// HID keyboard usage
+    pub key: u8,
+    pub action: MacroAction,
+    /// Up to 65535ms, rounded down to milliseconds
+    pub delay: Duration,
+}
+
+impl MacroStep {
+    pub fn new(key: u8, action: MacroAction, delay: Duration) -> Self {
+        Self { key, action, delay }
+    }
+}
+
+/// A macro the keyboard plays by itself, see `RyosMkFx::set_macro`
+///
+/// Macros can be written as text, one step per line:
+///
+/// ```text
+/// name Select all and copy
+/// loop 1
+/// down LeftCtrl
+/// down A 20
+/// up A
+/// down C 20
+/// up C
+/// up LeftCtrl
+/// ```
+///
+/// Steps are `down` or `up` followed by a key name or a `0x` hexadecimal HID usage and an
+/// optional delay in milliseconds. `set` names the macro set the vendor software files the macro
+/// under. `name`, `set` and `loop` default to no name and playing once. Empty lines and lines
+/// starting with `#` are ignored.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Macro {
+    pub name: String,
+    /// Macro set the vendor software shows the macro in
+    pub set_name: String,
+    /// How often the steps are played in a row
+    pub loop_count: u8,
+    pub steps: Vec<MacroStep>,
+    /// Report bytes of unknown use, kept as read so writing a macro back doesn't change them
+    ///
+    /// They aren't part of the text format.
+    pub unused: [u8; NAME_LEN],
+}
+
+impl Default for Macro {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            set_name: String::new(),
+            loop_count: 1,
+            steps: Vec::new(),
+            unused: [0; NAME_LEN],
+        }
+    }
+}
+
+impl Macro {
+    pub fn new(name: &str, loop_count: u8, steps: Vec<MacroStep>) -> Self {
+        Self {
+            name: name.to_string(),
+            loop_count,
+            steps,
+            ..Self::default()
+        }
+    }
+
+    /// Checks that the macro fits into the keyboard's macro storage
+    pub fn validate(&self) -> Result<(), Error> {
+        Error::ensure_range(
+            "Macro name length",
+            self.name.len() as i64,
+            0..=MACRO_MAX_NAME_LEN as i64,
+        )?;
+        Error::ensure_range(
+            "Macro set name length",
+            self.set_name.len() as i64,
+            0..=MACRO_MAX_NAME_LEN as i64,
+        )?;
+        Error::ensure_range("Macro loop count", self.loop_count, 1..=0xff)?;
+        Error::ensure_range(
+            "Macro step count",
+            self.steps.len() as i64,
+            0..=MACRO_MAX_STEPS as i64,
+        )?;
+        for step in &self.steps {
+            Error::ensure_range(
+                "Macro step delay",
+                step.delay.as_millis().min(i64::MAX as u128) as i64,
+                0..=0xffff,
+            )?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn to_report(
+        &self,
+        profile_index: u8,
+        slot_index: u8,
+    ) -> Result<MacroReport, Error> {
+        self.validate()?;
+        let mut report = MacroReport {
+            profile_index,
+            slot_index,
+            loop_count: self.loop_count,
+            unused: self.unused,
+            set_name: [0; NAME_LEN],
+            name: [0; NAME_LEN],
+            count: self.steps.len() as u16,
+            keystrokes: [Keystroke::default(); MACRO_MAX_STEPS],
+            _bytesum: Default::default(),
+        };
+        report.set_name[..self.set_name.len()].copy_from_slice(self.set_name.as_bytes());
+        report.name[..self.name.len()].copy_from_slice(self.name.as_bytes());
+        for (keystroke, step) in report.keystrokes.iter_mut().zip(&self.steps) {
+            *keystroke = Keystroke {
+                key: step.key,
+                action: match step.action {
+                    MacroAction::Down => ACTION_DOWN,
+                    MacroAction::Up => ACTION_UP,
+                },
+                delay: step.delay.as_millis() as u16,
+            };
+        }
+        Ok(report)
+    }
+
+    /// Reads a macro from the report the device sent for the selected profile and slot
+    pub(crate) fn from_report(
+        report: &MacroReport,
+        profile_index: u8,
+        slot_index: u8,
+    ) -> Result<Self, Error> {
+        for &(field, expected, actual) in &[
+            ("profile index", profile_index, report.profile_index),
+            ("macro slot index", slot_index, report.slot_index),
+        ] {
+            if actual != expected {
+                return Err(Error::SelectionMismatch {
+                    field,
+                    expected,
+                    actual,
+                });
+            }
+        }
+        let count = report.count as usize;
+        Error::ensure_range("Macro step count", count as i64, 0..=MACRO_MAX_STEPS as i64)?;
+        let keystrokes = report.keystrokes;
+        let steps = keystrokes[..count]
+            .iter()
+            .map(|keystroke| {
+                let action = match keystroke.action {
+                    ACTION_DOWN => MacroAction::Down,
+                    ACTION_UP => MacroAction::Up,
+                    value => {
+                        return Err(Error::InvalidValue {
+                            type_name: "MacroAction",
+                            value,
+                        })
+                    }
+                };
+                let delay = keystroke.delay;
+                Ok(MacroStep::new(
+                    keystroke.key,
+                    action,
+                    Duration::from_millis(u64::from(delay)),
+                ))
+            })
+            .collect::<Result<_, Error>>()?;
+        Ok(Self {
+            name: read_name(&report.name),
+            set_name: read_name(&report.set_name),
+            loop_count: report.loop_count,
+            steps,
+            unused: report.unused,
+        })
+    }
+}
+
+/// Reads a NUL terminated name
+fn read_name(name: &[u8; NAME_LEN]) -> String {
+    let len = name.iter().position(|&byte| byte == 0).unwrap_or(NAME_LEN);
+    String::from_utf8_lossy(&name[..len]).into_owned()
+}
+
+impl fmt::Display for Macro {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        if !self.name.is_empty() {
+            writeln!(fmt, "name {}", self.name)?;
+        }
+        if !self.set_name.is_empty() {
+            writeln!(fmt, "set {}", self.set_name)?;
+        }
+        writeln!(fmt, "loop {}", self.loop_count)?;
+        for step in &self.steps {
+            write!(fmt, "{} ", step.action.name())?;
+            match KeyId::from_hid_usage(step.key).filter(|key| key.hid_usage() == Some(step.key)) {
+                Some(key) => write!(fmt, "{}", key)?,
+                None => write!(fmt, "{:#04x}", step.key)?,
+            }
+            match step.delay.as_millis() {
+                0 => writeln!(fmt)?,
+                delay => writeln!(fmt, " {}", delay)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Macro {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<Self, Error> {
+        let mut macro_ = Macro::default();
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = |message: String| Error::InvalidMacro {
+                line: index + 1,
+                message,
+            };
+
+            let (command, argument) = match line.split_once(char::is_whitespace) {
+                Some((command, argument)) => (command, argument.trim()),
+                None => (line, ""),
+            };
+            match command {
+                "name" => macro_.name = argument.to_string(),
+                "set" => macro_.set_name = argument.to_string(),
+                "loop" => {
+                    macro_.loop_count = argument
+                        .parse()
+                        .map_err(|_| invalid(format!("Invalid loop count {}", argument)))?
+                }
+                command => {
+                    let action = MacroAction::from_name(command)
+                        .ok_or_else(|| invalid(format!("Unknown step {}", command)))?;
+                    let mut words = argument.split_whitespace();
+                    let key = words
+                        .next()
+                        .ok_or_else(|| invalid("Missing key".to_string()))?;
+                    let key =
+                        parse_key(key).ok_or_else(|| invalid(format!("Unknown key {}", key)))?;
+                    let delay = match words.next() {
+                        Some(delay) => delay
+                            .parse()
+                            .map_err(|_| invalid(format!("Invalid delay {}", delay)))?,
+                        None => 0,
+                    };
+                    if let Some(extra) = words.next() {
+                        return Err(invalid(format!("Unexpected {}", extra)));
+                    }
+                    macro_
+                        .steps
+                        .push(MacroStep::new(key, action, Duration::from_millis(delay)));
+                }
+            }
+        }
+        macro_.validate()?;
+        Ok(macro_)
+    }
+}
+
+/// Parses a key name or `0x` hexadecimal HID usage
+fn parse_key(key: &str) -> Option<u8> {
+    match key.strip_prefix("0x") {
+        Some(hex) => u8::from_str_radix(hex, 16).ok(),
+        None => KeyId::from_name(key).and_then(KeyId::hid_usage),
+    }
+}
+
+#[derive(ReportField, Copy, Clone, Debug, Default)]
+#[repr(C, packed)]
+pub struct Keystroke {
+    pub key: u8,
+    pub action: u8,
+    pub delay: u16, // ms
+}
+
+/// One macro slot of a profile
+///
+/// The layout follows the Ryos macro report of roccat-tools (erazor_de's code), where `unused`
+/// has no known meaning and `set_name` is only shown by the vendor software. The slot is selected
+/// with a `Control` request of `ControlRequest::Macro` plus the slot index, which no source
+/// confirms, so the profile and slot indices the device sends back are checked on every read.
+#[derive(ReportField, HidrawRead, HidrawWrite)]
+#[repr(C, packed)]
+#[hidraw_report(id = 0x0e, len = 2002, size = "u16")]
+pub struct MacroReport {
+    pub profile_index: u8,
+    pub slot_index: u8,
+    pub loop_count: u8,
+    pub unused: [u8; NAME_LEN],
+    pub set_name: [u8; NAME_LEN],
+    pub name: [u8; NAME_LEN],
+    pub count: u16,
+    pub keystrokes: [Keystroke; MACRO_MAX_STEPS],
+    #[hidraw_bytesum]
+    _bytesum: u16,
+}
+
+impl fmt::Debug for MacroReport {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let count = self.count;
+        let bytesum = self._bytesum;
+        fmt.debug_struct("MacroReport")
+            .field("profile_index", &self.profile_index)
+            .field("slot_index", &self.slot_index)
+            .field("loop_count", &self.loop_count)
+            .field("unused", &&self.unused[..])
+            .field("set_name", &&self.set_name[..])
+            .field("name", &&self.name[..])
+            .field("count", &count)
+            .field("keystrokes", &&self.keystrokes[..])
+            .field("_bytesum", &bytesum)
+            .finish()
+    }
+}
@@ -1,245 +1,794 @@
 mod control;
 mod custom_lights;
+#[cfg(feature = "emulator")]
+mod emulator;
 mod event;
+#[cfg(feature = "async")]
+mod event_stream;
+mod geometry;
+mod gesture;
 mod hardware_color;
+mod key_id;
+mod key_state;
+mod keymap;
 mod keys;
+mod layout;
 mod light_control;
 mod lights;
+mod macros;
 mod sdk;
 
+use crate::{
+    descriptor::{self, ReportKind},
+    device::{
+        button::{self, ButtonFamily, MACRO_SLOT_COUNT},
+        interface::{self, HidrawNode, InterfaceSpec, UsagePage},
+        DeviceId, EventSource, FirmwareInfo, KeyLabels, KeyRemapping, PerKeyLighting, Rgb,
+        RoccatDevice,
+    },
+    event_bus::{EventBus, Overflow, Subscriber},
+    transport::{default_write_check_timeout, CancelToken, HidrawTransport, Transport},
+    udev, Error,
+};
 use bitfield::NibbleField;
-use failure::{ensure, Error};
-use hidraw_derive::{HidrawRead, HidrawWrite};
+use hidraw_derive::{HidrawRead, HidrawWrite, ReportField};
+use log::{debug, info, warn};
+#[cfg(feature = "async")]
+use std::{mem, task::Waker};
 use std::{
-    fs::File,
-    io::prelude::*,
+    ops::RangeInclusive,
     path::PathBuf,
-    sync::{Arc, Mutex},
-    thread,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, RwLock,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
 };
 
+#[cfg(feature = "emulator")]
+pub use self::emulator::*;
+#[cfg(feature = "async")]
+pub use self::event_stream::EventStream;
 pub use self::{
-    control::*, custom_lights::*, event::*, hardware_color::*, keys::*, light_control::*,
-    lights::*, sdk::*,
+    control::*, custom_lights::*, event::*, geometry::*, gesture::*, hardware_color::*, key_id::*,
+    key_state::*, keymap::*, keys::*, layout::*, light_control::*, lights::*, macros::*, sdk::*,
 };
 
-pub struct RyosMkFx {
-    interfaces: Arc<Mutex<Vec<File>>>,
-    event_queue: Arc<Mutex<Vec<Event>>>,
+/// Number of profiles, which are numbered starting at 1
+pub const PROFILE_COUNT: u8 = 5;
+
+const PROFILES: RangeInclusive<i64> = 1..=PROFILE_COUNT as i64;
+
+/// Feature reports on the primary interface, checked against the descriptor when opening
+const FEATURE_REPORTS: [(u8, usize); 13] = [
+    (Control::REPORT_ID, Control::LEN),
+    (Profile::REPORT_ID, Profile::LEN),
+    (KeysPrimary::REPORT_ID, KeysPrimary::LEN),
+    (KeysFunction::REPORT_ID, KeysFunction::LEN),
+    (KeysMacro::REPORT_ID, KeysMacro::LEN),
+    (KeysThumbster::REPORT_ID, KeysThumbster::LEN),
+    (KeysExtra::REPORT_ID, KeysExtra::LEN),
+    (KeysEasyzone::REPORT_ID, KeysEasyzone::LEN),
+    (Lights::REPORT_ID, Lights::LEN),
+    (MacroReport::REPORT_ID, MacroReport::LEN),
+    (DeviceInfo::REPORT_ID, DeviceInfo::LEN),
+    (LightControl::REPORT_ID, LightControl::LEN),
+    (CustomLights::REPORT_ID, CustomLights::LEN),
+];
+
+/// How long the event reader waits for a report before checking whether it should stop
+const EVENT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How often the event reader tries to reopen a disconnected device
+const REOPEN_INTERVAL: Duration = Duration::from_secs(1);
+
+/// An event or a read error, shared between all subscribers
+pub type EventResult = Result<Event, Arc<Error>>;
+
+/// Opens the device's interfaces again, in interface order
+pub type Reopen<T> = Box<dyn FnMut() -> Result<Vec<T>, Error> + Send>;
+
+/// The event reader thread stops once the device is dropped, or once events are read through
+/// `event_stream` instead
+///
+/// Events and read errors are published to every subscriber, see `subscribe`. The device keeps a
+/// subscription of its own for `get_event` and `next_event`. Dropping the device waits for the
+/// reader to stop, which takes up to one `read_report_timeout` poll interval.
+pub struct RyosMkFx<T: Transport + 'static = HidrawTransport> {
+    id: Option<DeviceId>,
+    shared: Arc<Shared<T>>,
+    reader: Option<JoinHandle<()>>,
+    events: Subscriber<EventResult>,
+    write_check_timeout: Option<Duration>,
+    cancel: CancelToken,
+    layout: RwLock<KeyboardLayout>,
+}
+
+/// State shared with the event reader thread
+struct Shared<T> {
+    interfaces: RwLock<Vec<Arc<T>>>,
+    events: EventBus<EventResult>,
+    stop: AtomicBool,
+    reopen: Mutex<Option<Reopen<T>>>,
+    /// Set once `EventStream`s read the events instead of the reader thread
+    #[cfg(feature = "async")]
+    streamed: AtomicBool,
+    /// Set while `wait_for_reopen` runs
+    #[cfg(feature = "async")]
+    reopening: AtomicBool,
+    /// Streams to wake once `wait_for_reopen` is done
+    #[cfg(feature = "async")]
+    reopen_wakers: Mutex<Vec<Waker>>,
+}
+
+impl<T: Transport> Shared<T> {
+    fn interface(&self, interface: Interface) -> Result<Arc<T>, Error> {
+        let index = interface as usize;
+        self.interfaces
+            .read()
+            .unwrap()
+            .get(index)
+            .cloned()
+            .ok_or(Error::MissingInterface { index })
+    }
+
+    fn push(&self, event: Result<Event, Error>) {
+        self.events.publish(event.map_err(Arc::new));
+    }
+
+    /// Publishes a report read from the Events interface
+    fn push_report(&self, buf: &[u8; Event::SIZE], len: usize) {
+        if len == buf.len() {
+            let received = Instant::now();
+            let event = Event::from_bytes_at(buf, received).unwrap_or_else(|error| {
+                debug!("Passing on unknown event {:02x?}: {}", buf, error);
+                Event::from_raw_at(*buf, received)
+            });
+            self.push(Ok(event));
+        }
+    }
+
+    /// Whether the reader thread should stop
+    fn reader_done(&self) -> bool {
+        #[cfg(feature = "async")]
+        {
+            if self.streamed.load(Ordering::SeqCst) {
+                return true;
+            }
+        }
+        self.stop.load(Ordering::SeqCst)
+    }
+
+    fn read_events(&self) {
+        while !self.reader_done() {
+            let mut buf = [0u8; Event::SIZE];
+            let read = self
+                .interface(Interface::Events)
+                .and_then(|interface| interface.read_report_timeout(&mut buf, EVENT_POLL_INTERVAL));
+            match read {
+                Ok(Some(len)) => self.push_report(&buf, len),
+                Ok(None) => (),
+                Err(error) if error.is_permanent() => {
+                    warn!("Event reader lost the device: {}", error);
+                    self.push(Err(error));
+                    if !self.wait_for_reopen() {
+                        return;
+                    }
+                }
+                Err(error) => {
+                    warn!("Reading an event failed: {}", error);
+                    self.push(Err(error));
+                    thread::sleep(EVENT_POLL_INTERVAL);
+                }
+            }
+        }
+    }
+
+    /// Keeps trying to reopen the device, `false` if there's no way to or the device was dropped
+    fn wait_for_reopen(&self) -> bool {
+        #[cfg(feature = "async")]
+        self.reopening.store(true, Ordering::SeqCst);
+        let reopened = self.try_reopen();
+        #[cfg(feature = "async")]
+        {
+            self.reopening.store(false, Ordering::SeqCst);
+            let wakers = mem::take(&mut *self.reopen_wakers.lock().unwrap());
+            wakers.into_iter().for_each(Waker::wake);
+        }
+        reopened
+    }
+
+    fn try_reopen(&self) -> bool {
+        while !self.stop.load(Ordering::SeqCst) {
+            let reopened = match *self.reopen.lock().unwrap() {
+                Some(ref mut reopen) => reopen(),
+                None => return false,
+            };
+            let reopened = reopened.and_then(|interfaces| {
+                let interfaces: Vec<_> = interfaces.into_iter().map(Arc::new).collect();
+                verify_reports(&interfaces)?;
+                Ok(interfaces)
+            });
+            match reopened {
+                Ok(interfaces) => {
+                    *self.interfaces.write().unwrap() = interfaces;
+                    info!("Reopened the device after it was disconnected");
+                    return true;
+                }
+                Err(error @ Error::ReportLayout { .. }) => {
+                    warn!("Device came back with different firmware: {}", error)
+                }
+                Err(error) => debug!("Device isn't back yet: {}", error),
+            }
+
+            let start = Instant::now();
+            while start.elapsed() < REOPEN_INTERVAL && !self.stop.load(Ordering::SeqCst) {
+                thread::sleep(EVENT_POLL_INTERVAL);
+            }
+        }
+        false
+    }
+}
+
+/// Checks that interfaces given in interface order declare the reports this crate uses
+fn verify_reports<T: Transport>(interfaces: &[Arc<T>]) -> Result<(), Error> {
+    let interface = |interface: Interface| {
+        let index = interface as usize;
+        interfaces
+            .get(index)
+            .ok_or(Error::MissingInterface { index })
+    };
+    descriptor::verify_reports(
+        interface(Interface::Primary)?,
+        ReportKind::Feature,
+        &FEATURE_REPORTS,
+    )?;
+    descriptor::verify_reports(
+        interface(Interface::Events)?,
+        ReportKind::Input,
+        &[(Event::REPORT_ID, Event::SIZE)],
+    )
 }
 
 impl RyosMkFx {
+    /// Opens the device from its hidraw nodes, in any order
+    pub fn from_nodes(nodes: &[HidrawNode]) -> Result<Self, Error> {
+        Self::new(interface::classify(nodes, &INTERFACES)?)
+    }
+
+    /// Opens the device from hidraw paths given in interface order
     pub fn new(paths: Vec<PathBuf>) -> Result<Self, Error> {
         let mut interfaces = Vec::new();
         for path in paths {
-            interfaces.push(File::open(path)?);
+            interfaces.push(HidrawTransport::open(path)?);
         }
 
-        let device = Self {
-            interfaces: Arc::new(Mutex::new(interfaces)),
-            event_queue: Arc::new(Mutex::new(Vec::new())),
-        };
+        Self::from_transports(interfaces)
+    }
 
-        let interfaces = Arc::clone(&device.interfaces);
-        let event_queue = Arc::clone(&device.event_queue);
-        thread::spawn(move || {
-            let mut file;
-            {
-                let interfaces_guard = interfaces.lock().unwrap();
-                file = (*interfaces_guard)[Interface::Events as usize]
-                    .try_clone()
-                    .unwrap();
-            }
+    /// Reopens the device through udev once it's plugged back in after being disconnected
+    ///
+    /// Only works for devices opened through udev, which know their `DeviceId`.
+    pub fn set_auto_reopen(&self, enabled: bool) -> Result<(), Error> {
+        if !enabled {
+            self.set_reopen(None);
+            return Ok(());
+        }
 
-            loop {
-                let mut buf = [0u8; ::std::mem::size_of::<Event>()];
-                file.read_exact(&mut buf).unwrap();
+        let id = self.id.clone().ok_or(Error::DeviceNotFound)?;
+        self.set_reopen(Some(Box::new(move || {
+            let paths = interface::classify(&udev::find(&id)?.nodes, &INTERFACES)?;
+            paths.into_iter().map(HidrawTransport::open).collect()
+        })));
+        Ok(())
+    }
+}
 
-                let mut event_queue_guard = event_queue.lock().unwrap();
-                (*event_queue_guard).insert(0, unsafe { ::std::mem::transmute::<_, Event>(buf) });
-            }
+impl<T: Transport + 'static> RyosMkFx<T> {
+    /// Creates a device from already opened interfaces, in interface order
+    pub fn from_transports(interfaces: Vec<T>) -> Result<Self, Error> {
+        let interfaces: Vec<_> = interfaces.into_iter().map(Arc::new).collect();
+        verify_reports(&interfaces)?;
+
+        let shared = Arc::new(Shared {
+            interfaces: RwLock::new(interfaces),
+            events: EventBus::new(),
+            stop: AtomicBool::new(false),
+            reopen: Mutex::new(None),
+            #[cfg(feature = "async")]
+            streamed: AtomicBool::new(false),
+            #[cfg(feature = "async")]
+            reopening: AtomicBool::new(false),
+            #[cfg(feature = "async")]
+            reopen_wakers: Mutex::new(Vec::new()),
         });
+        let mut device = Self {
+            id: None,
+            events: shared.events.subscribe(),
+            shared,
+            reader: None,
+            write_check_timeout: default_write_check_timeout(),
+            cancel: CancelToken::new(),
+            layout: RwLock::new(KeyboardLayout::default()),
+        };
+
+        let shared = Arc::clone(&device.shared);
+        device.reader = Some(thread::spawn(move || shared.read_events()));
 
         Ok(device)
     }
 
-    pub fn get_interface(&self, interface: Interface) -> Result<File, Error> {
-        let guard = self.interfaces.lock().unwrap();
-        Ok((*guard)[interface as usize].try_clone()?)
+    /// Gets the USB device this was opened from, `None` if it was built from transports directly
+    pub fn id(&self) -> Option<&DeviceId> {
+        self.id.as_ref()
+    }
+
+    pub fn set_id(&mut self, id: DeviceId) {
+        self.id = Some(id);
+    }
+
+    pub fn get_interface(&self, interface: Interface) -> Result<Arc<T>, Error> {
+        self.shared.interface(interface)
+    }
+
+    /// Sets how to open the device again after it was disconnected, `None` to not try
+    ///
+    /// While the device is gone, requests fail with `Error::Disconnected`. Reopened interfaces
+    /// are checked against the report layouts like when opening the device.
+    pub fn set_reopen(&self, reopen: Option<Reopen<T>>) {
+        *self.shared.reopen.lock().unwrap() = reopen;
+    }
+
+    /// Sets how long to wait for the device to process a request, `None` waits forever
+    ///
+    /// Defaults to the library-wide default at the time the device was opened.
+    pub fn set_write_check_timeout(&mut self, timeout: Option<Duration>) {
+        self.write_check_timeout = timeout;
+    }
+
+    /// Gets the layout printed on the keycaps, which the device itself doesn't know
+    pub fn layout(&self) -> KeyboardLayout {
+        *self.layout.read().unwrap()
+    }
+
+    /// Sets the layout printed on the keycaps, `KeyboardLayout::Us` unless set
+    pub fn set_layout(&self, layout: KeyboardLayout) {
+        *self.layout.write().unwrap() = layout;
+    }
+
+    /// Gets a token that cancels this device's pending write checks from another thread
+    pub fn cancel_token(&self) -> CancelToken {
+        self.cancel.clone()
+    }
+
+    fn check_write(&self) -> Result<(), Error> {
+        Control::check_write_with(
+            &self.get_interface(Interface::Primary)?,
+            self.write_check_timeout,
+            Some(&self.cancel),
+        )
     }
 
     pub fn get_common_name<'a>() -> &'a str {
         "Ryos MK FX"
     }
 
+    /// Takes the oldest queued event, skipping read errors
     pub fn get_event(&self) -> Option<Event> {
-        let mut guard = self.event_queue.lock().unwrap();
-        (*guard).pop()
+        while let Some(event) = self.events.try_recv() {
+            if let Ok(event) = event {
+                return Some(event);
+            }
+        }
+        None
+    }
+
+    /// Takes the oldest queued event or error the event reader ran into
+    ///
+    /// `Error::Disconnected` means the device was unplugged. Events continue afterwards only if
+    /// the device could be reopened, see `set_reopen`.
+    pub fn next_event(&self) -> Option<EventResult> {
+        self.events.try_recv()
+    }
+
+    /// Waits up to `timeout` for the next event or error, see `next_event`
+    pub fn next_event_timeout(&self, timeout: Duration) -> Option<EventResult> {
+        self.events.recv_timeout(timeout)
+    }
+
+    /// Subscribes to all events and read errors from now on, independently of other subscribers
+    pub fn subscribe(&self) -> Subscriber<EventResult> {
+        self.shared.events.subscribe()
+    }
+
+    /// Subscribes with a buffer of `capacity` events and the given overflow policy
+    pub fn subscribe_with(&self, capacity: usize, overflow: Overflow) -> Subscriber<EventResult> {
+        self.shared.events.subscribe_with(capacity, overflow)
+    }
+
+    /// Subscribes to all events and read errors from now on as a `Stream`
+    ///
+    /// Streams read the Events interface whenever it becomes readable, so the event reader
+    /// thread stops once the first one is created. From then on, events only arrive while a
+    /// stream is polled, for every subscriber including `next_event`.
+    #[cfg(feature = "async")]
+    pub fn event_stream(&self) -> EventStream<T> {
+        self.shared.streamed.store(true, Ordering::SeqCst);
+        EventStream::new(&self.shared)
     }
 
     /// Gets the current profile
     pub fn get_profile(&self) -> Result<u8, Error> {
-        unsafe {
-            Ok(Profile::read(&self.get_interface(Interface::Primary)?)?
-                .index
-                .get_nibble(0)
-                + 1)
-        }
+        Ok(Profile::read(&self.get_interface(Interface::Primary)?)?
+            .index
+            .get_nibble(0)
+            + 1)
     }
 
     /// Sets the current profile
     pub fn set_profile(&self, index: u8) -> Result<(), Error> {
-        unsafe {
-            ensure!(
-                index >= 1 && index <= 5,
-                "Profile {} is out of range",
-                index
-            );
-            let mut profile = Profile::read(&self.get_interface(Interface::Primary)?)?.index;
-            profile.set_nibble(0, index - 1);
-            Profile::new(profile).write(&self.get_interface(Interface::Primary)?)
-        }
+        Error::ensure_range("Profile", index, PROFILES)?;
+        let mut profile = Profile::read(&self.get_interface(Interface::Primary)?)?.index;
+        profile.set_nibble(0, index - 1);
+        Profile::new(profile).write(&self.get_interface(Interface::Primary)?)
     }
 
     pub fn get_info(&self) -> Result<DeviceInfo, Error> {
-        unsafe { DeviceInfo::read(&self.get_interface(Interface::Primary)?) }
+        DeviceInfo::read(&self.get_interface(Interface::Primary)?)
     }
 
     pub fn get_lights(&self, profile: u8) -> Result<Lights, Error> {
-        unsafe {
-            Control::new(profile - 1, ControlRequest::Light as u8)
-                .write(&self.get_interface(Interface::Primary)?)?;
-            Control::check_write(&self.get_interface(Interface::Primary)?)?;
+        Error::ensure_range("Profile", profile, PROFILES)?;
+        Control::new(profile - 1, ControlRequest::Light as u8)
+            .write(&self.get_interface(Interface::Primary)?)?;
+        self.check_write()?;
 
-            Lights::read(&self.get_interface(Interface::Primary)?)
-        }
+        Lights::read(&self.get_interface(Interface::Primary)?)
     }
 
     pub fn set_lights(&self, lights: &Lights) -> Result<(), Error> {
-        unsafe {
-            let mut lights = lights.clone();
-            lights.profile -= 1;
-            lights.write(&self.get_interface(Interface::Primary)?)
-        }
+        Error::ensure_range("Profile", lights.profile, PROFILES)?;
+        let mut lights = *lights;
+        lights.profile -= 1;
+        lights.write(&self.get_interface(Interface::Primary)?)
     }
 
     pub fn set_custom_lights_active(&self, active: bool) -> Result<(), Error> {
-        unsafe {
-            let state = if active {
-                LightControlState::Custom
-            } else {
-                LightControlState::Stored
-            };
-            LightControl::new(state).write(&self.get_interface(Interface::Primary)?)
-        }
+        let state = if active {
+            LightControlState::Custom
+        } else {
+            LightControlState::Stored
+        };
+        LightControl::new(state).write(&self.get_interface(Interface::Primary)?)
     }
 
     pub fn get_custom_lights_active(&self) -> Result<bool, Error> {
-        unsafe {
-            Ok(
-                match LightControl::read(&self.get_interface(Interface::Primary)?)?.state {
-                    LightControlState::Custom => true,
-                    LightControlState::Stored => false,
-                },
-            )
-        }
+        Ok(
+            match LightControl::read(&self.get_interface(Interface::Primary)?)?.state {
+                LightControlState::Custom => true,
+                LightControlState::Stored => false,
+            },
+        )
     }
 
     pub fn get_custom_lights(&self) -> Result<CustomLights, Error> {
-        unsafe { CustomLights::read(&self.get_interface(Interface::Primary)?) }
+        CustomLights::read(&self.get_interface(Interface::Primary)?)
     }
 
     pub fn set_custom_lights(&self, custom_lights: &CustomLights) -> Result<(), Error> {
-        unsafe {
-            custom_lights
-                .clone()
-                .write(&self.get_interface(Interface::Primary)?)?;
-            LightControl::check_write(&self.get_interface(Interface::Primary)?)
+        custom_lights.write(&self.get_interface(Interface::Primary)?)?;
+        LightControl::check_write_with(
+            &self.get_interface(Interface::Primary)?,
+            self.write_check_timeout,
+            Some(&self.cancel),
+        )
+    }
+
+    /// Reads the functions of every key of a profile
+    pub fn get_keymap(&self, profile: u8) -> Result<Keymap, Error> {
+        Error::ensure_range("Profile", profile, PROFILES)?;
+        let extra = self.get_keys_extra(profile)?;
+        let tables = KeyTables {
+            primary: self.get_keys_primary(profile)?.keys,
+            function: self.get_keys_function(profile)?.keys,
+            macro_: self.get_keys_macro(profile)?.keys,
+            thumbster: self.get_keys_thumbster(profile)?.keys,
+            extra: [extra.capslock, extra.fn_, extra.unused],
+            easyzone: self.get_keys_easyzone(profile)?.keys,
+        };
+        Ok(Keymap::from_tables(
+            profile,
+            self.layout().physical(),
+            tables,
+        ))
+    }
+
+    /// Writes the reports of a keymap that changed since it was read or last written
+    ///
+    /// Returns how many reports were written. Reports holding functions whose codes aren't
+    /// confirmed for keyboards are read back, see `ButtonType::is_confirmed`.
+    pub fn set_keymap(&self, keymap: &mut Keymap) -> Result<usize, Error> {
+        let profile = keymap.profile();
+        Error::ensure_range("Profile", profile, PROFILES)?;
+        let index = profile - 1;
+        let changes = keymap.changes();
+        let tables = keymap.tables();
+        let interface = self.get_interface(Interface::Primary)?;
+        let family = ButtonFamily::Keyboard;
+        let mut written = 0;
+
+        if changes.primary {
+            KeysPrimary::new(index, tables.primary).write(&interface)?;
+            self.check_write()?;
+            written += 1;
+        }
+        if changes.function {
+            KeysFunction::new(index, tables.function).write(&interface)?;
+            self.check_write()?;
+            button::check_stored(family, &tables.function, || {
+                Ok(self.get_keys_function(profile)?.keys)
+            })?;
+            written += 1;
+        }
+        if changes.macro_ {
+            KeysMacro::new(index, tables.macro_).write(&interface)?;
+            self.check_write()?;
+            button::check_stored(family, &tables.macro_, || {
+                Ok(self.get_keys_macro(profile)?.keys)
+            })?;
+            written += 1;
+        }
+        if changes.thumbster {
+            KeysThumbster::new(index, tables.thumbster).write(&interface)?;
+            self.check_write()?;
+            button::check_stored(family, &tables.thumbster, || {
+                Ok(self.get_keys_thumbster(profile)?.keys)
+            })?;
+            written += 1;
         }
+        if changes.extra {
+            let [capslock, fn_, unused] = tables.extra;
+            KeysExtra::new(index, capslock, fn_, unused).write(&interface)?;
+            self.check_write()?;
+            written += 1;
+        }
+        if changes.easyzone {
+            KeysEasyzone::new(index, tables.easyzone).write(&interface)?;
+            self.check_write()?;
+            button::check_stored(family, &tables.easyzone, || {
+                Ok(self.get_keys_easyzone(profile)?.keys)
+            })?;
+            written += 1;
+        }
+
+        keymap.mark_saved();
+        Ok(written)
+    }
+
+    /// Reads the macro stored in a slot of a profile
+    ///
+    /// Fails with `Error::SelectionMismatch` if the device answers for another profile or slot,
+    /// see `MacroReport`.
+    pub fn get_macro(&self, profile: u8, slot: u8) -> Result<Macro, Error> {
+        Error::ensure_range("Profile", profile, PROFILES)?;
+        Error::ensure_range("Macro slot", slot, 1..=MACRO_SLOT_COUNT as i64)?;
+        Control::new(profile - 1, ControlRequest::Macro as u8 + slot - 1)
+            .write(&self.get_interface(Interface::Primary)?)?;
+        self.check_write()?;
+
+        Macro::from_report(
+            &MacroReport::read(&self.get_interface(Interface::Primary)?)?,
+            profile - 1,
+            slot - 1,
+        )
+    }
+
+    /// Stores a macro in a slot of a profile, which keys play with `KeyAction::Macro`
+    pub fn set_macro(&self, profile: u8, slot: u8, macro_: &Macro) -> Result<(), Error> {
+        Error::ensure_range("Profile", profile, PROFILES)?;
+        Error::ensure_range("Macro slot", slot, 1..=MACRO_SLOT_COUNT as i64)?;
+        macro_
+            .to_report(profile - 1, slot - 1)?
+            .write(&self.get_interface(Interface::Primary)?)?;
+        self.check_write()
     }
 
     pub fn get_keys_primary(&self, profile: u8) -> Result<KeysPrimary, Error> {
-        unsafe {
-            Control::new(profile - 1, ControlRequest::KeysPrimary as u8)
-                .write(&self.get_interface(Interface::Primary)?)?;
-            Control::check_write(&self.get_interface(Interface::Primary)?)?;
+        Error::ensure_range("Profile", profile, PROFILES)?;
+        Control::new(profile - 1, ControlRequest::KeysPrimary as u8)
+            .write(&self.get_interface(Interface::Primary)?)?;
+        self.check_write()?;
 
-            KeysPrimary::read(&self.get_interface(Interface::Primary)?)
-        }
+        KeysPrimary::read(&self.get_interface(Interface::Primary)?)
     }
 
     pub fn set_keys_primary(&self, keys: KeysPrimary) -> Result<(), Error> {
-        unsafe { keys.write(&self.get_interface(Interface::Primary)?) }
+        keys.write(&self.get_interface(Interface::Primary)?)
     }
 
     pub fn get_keys_function(&self, profile: u8) -> Result<KeysFunction, Error> {
-        unsafe {
-            Control::new(profile - 1, ControlRequest::KeysFunction as u8)
-                .write(&self.get_interface(Interface::Primary)?)?;
-            Control::check_write(&self.get_interface(Interface::Primary)?)?;
+        Error::ensure_range("Profile", profile, PROFILES)?;
+        Control::new(profile - 1, ControlRequest::KeysFunction as u8)
+            .write(&self.get_interface(Interface::Primary)?)?;
+        self.check_write()?;
 
-            KeysFunction::read(&self.get_interface(Interface::Primary)?)
-        }
+        KeysFunction::read(&self.get_interface(Interface::Primary)?)
     }
 
     pub fn set_keys_function(&self, keys: KeysFunction) -> Result<(), Error> {
-        unsafe { keys.write(&self.get_interface(Interface::Primary)?) }
+        keys.write(&self.get_interface(Interface::Primary)?)
     }
 
     pub fn get_keys_macro(&self, profile: u8) -> Result<KeysMacro, Error> {
-        unsafe {
-            Control::new(profile - 1, ControlRequest::KeysMacro as u8)
-                .write(&self.get_interface(Interface::Primary)?)?;
-            Control::check_write(&self.get_interface(Interface::Primary)?)?;
+        Error::ensure_range("Profile", profile, PROFILES)?;
+        Control::new(profile - 1, ControlRequest::KeysMacro as u8)
+            .write(&self.get_interface(Interface::Primary)?)?;
+        self.check_write()?;
 
-            KeysMacro::read(&self.get_interface(Interface::Primary)?)
-        }
+        KeysMacro::read(&self.get_interface(Interface::Primary)?)
     }
 
     pub fn set_keys_macro(&self, keys: KeysMacro) -> Result<(), Error> {
-        unsafe { keys.write(&self.get_interface(Interface::Primary)?) }
+        keys.write(&self.get_interface(Interface::Primary)?)
     }
 
     pub fn get_keys_thumbster(&self, profile: u8) -> Result<KeysThumbster, Error> {
-        unsafe {
-            Control::new(profile - 1, ControlRequest::KeysThumbster as u8)
-                .write(&self.get_interface(Interface::Primary)?)?;
-            Control::check_write(&self.get_interface(Interface::Primary)?)?;
+        Error::ensure_range("Profile", profile, PROFILES)?;
+        Control::new(profile - 1, ControlRequest::KeysThumbster as u8)
+            .write(&self.get_interface(Interface::Primary)?)?;
+        self.check_write()?;
 
-            KeysThumbster::read(&self.get_interface(Interface::Primary)?)
-        }
+        KeysThumbster::read(&self.get_interface(Interface::Primary)?)
     }
 
     pub fn set_keys_thumbster(&self, keys: KeysThumbster) -> Result<(), Error> {
-        unsafe { keys.write(&self.get_interface(Interface::Primary)?) }
+        keys.write(&self.get_interface(Interface::Primary)?)
     }
 
     pub fn get_keys_extra(&self, profile: u8) -> Result<KeysExtra, Error> {
-        unsafe {
-            Control::new(profile - 1, ControlRequest::KeysExtra as u8)
-                .write(&self.get_interface(Interface::Primary)?)?;
-            Control::check_write(&self.get_interface(Interface::Primary)?)?;
+        Error::ensure_range("Profile", profile, PROFILES)?;
+        Control::new(profile - 1, ControlRequest::KeysExtra as u8)
+            .write(&self.get_interface(Interface::Primary)?)?;
+        self.check_write()?;
 
-            KeysExtra::read(&self.get_interface(Interface::Primary)?)
-        }
+        KeysExtra::read(&self.get_interface(Interface::Primary)?)
     }
 
     pub fn set_keys_extra(&self, keys: KeysExtra) -> Result<(), Error> {
-        unsafe { keys.write(&self.get_interface(Interface::Primary)?) }
+        keys.write(&self.get_interface(Interface::Primary)?)
     }
 
     pub fn get_keys_easyzone(&self, profile: u8) -> Result<KeysEasyzone, Error> {
-        unsafe {
-            Control::new(profile - 1, ControlRequest::KeysEasyzone as u8)
-                .write(&self.get_interface(Interface::Primary)?)?;
-            Control::check_write(&self.get_interface(Interface::Primary)?)?;
+        Error::ensure_range("Profile", profile, PROFILES)?;
+        Control::new(profile - 1, ControlRequest::KeysEasyzone as u8)
+            .write(&self.get_interface(Interface::Primary)?)?;
+        self.check_write()?;
 
-            KeysEasyzone::read(&self.get_interface(Interface::Primary)?)
-        }
+        KeysEasyzone::read(&self.get_interface(Interface::Primary)?)
     }
 
     pub fn set_keys_easyzone(&self, keys: KeysEasyzone) -> Result<(), Error> {
-        unsafe { keys.write(&self.get_interface(Interface::Primary)?) }
+        keys.write(&self.get_interface(Interface::Primary)?)
+    }
+}
+
+impl<T: Transport + 'static> Drop for RyosMkFx<T> {
+    fn drop(&mut self) {
+        self.shared.stop.store(true, Ordering::SeqCst);
+        if let Some(reader) = self.reader.take() {
+            let _ = reader.join();
+        }
+    }
+}
+
+impl<T: Transport + 'static> RoccatDevice for RyosMkFx<T> {
+    fn name(&self) -> &str {
+        Self::get_common_name()
+    }
+
+    fn id(&self) -> Option<&DeviceId> {
+        self.id()
+    }
+
+    fn firmware_info(&self) -> Result<Option<FirmwareInfo>, Error> {
+        let info = self.get_info()?;
+        Ok(Some(FirmwareInfo {
+            firmware_version: info.firmware_version,
+            dfu_version: Some(info.dfu_version),
+            led_firmware_version: Some(info.led_firmware_version),
+        }))
+    }
+
+    fn profile_count(&self) -> u8 {
+        PROFILE_COUNT
+    }
+
+    fn get_profile(&self) -> Result<u8, Error> {
+        self.get_profile()
+    }
+
+    fn set_profile(&self, profile: u8) -> Result<(), Error> {
+        self.set_profile(profile)
+    }
+
+    fn as_per_key_lighting(&self) -> Option<&dyn PerKeyLighting> {
+        Some(self)
+    }
+
+    fn as_key_remapping(&self) -> Option<&dyn KeyRemapping> {
+        Some(self)
+    }
+
+    fn as_event_source(&self) -> Option<&dyn EventSource> {
+        Some(self)
+    }
+
+    fn as_key_labels(&self) -> Option<&dyn KeyLabels> {
+        Some(self)
+    }
+}
+
+/// Keys are addressed by SDK index
+impl<T: Transport + 'static> PerKeyLighting for RyosMkFx<T> {
+    fn key_count(&self) -> u8 {
+        SDK_KEY_COUNT
+    }
+
+    fn set_per_key_lighting_active(&self, active: bool) -> Result<(), Error> {
+        self.set_custom_lights_active(active)
+    }
+
+    fn set_key_colors(&self, colors: &[Option<Rgb>]) -> Result<(), Error> {
+        let mut data = LightLayerData::default();
+        for (sdk_index, color) in (0..SDK_KEY_COUNT).zip(colors) {
+            if let Some(color) = *color {
+                data.set_key_state(sdk_index, true);
+                data.set_key_red(sdk_index, color.red);
+                data.set_key_green(sdk_index, color.green);
+                data.set_key_blue(sdk_index, color.blue);
+            }
+        }
+        self.set_custom_lights(&CustomLights::new(LightLayer::from_data(&data)))
+    }
+}
+
+/// Keys are addressed like in `KeysPrimary`
+impl<T: Transport + 'static> KeyRemapping for RyosMkFx<T> {
+    fn key_count(&self) -> usize {
+        KeysPrimary::default().keys.len()
+    }
+
+    fn get_key_usages(&self, profile: u8) -> Result<Vec<u8>, Error> {
+        Ok(self.get_keys_primary(profile)?.keys.to_vec())
+    }
+
+    fn set_key_usages(&self, profile: u8, usages: &[u8]) -> Result<(), Error> {
+        let mut keys = self.get_keys_primary(profile)?;
+        let len = keys.keys.len();
+        Error::ensure_range("Key count", usages.len() as i64, len as i64..=len as i64)?;
+        keys.keys.copy_from_slice(usages);
+        self.set_keys_primary(keys)
+    }
+}
+
+impl<T: Transport + 'static> KeyLabels for RyosMkFx<T> {
+    fn layout(&self) -> KeyboardLayout {
+        self.layout()
+    }
+
+    fn set_layout(&self, layout: KeyboardLayout) {
+        self.set_layout(layout)
+    }
+}
+
+impl<T: Transport + 'static> EventSource for RyosMkFx<T> {
+    fn get_event(&self) -> Option<Event> {
+        self.get_event()
+    }
+
+    fn subscribe(&self) -> Subscriber<EventResult> {
+        self.subscribe()
     }
 }
 
@@ -248,13 +797,24 @@ pub enum Interface {
     Events = 1,
 }
 
-#[derive(HidrawRead, HidrawWrite, Debug)]
+/// How to recognize each `Interface`, in the same order
+pub const INTERFACES: [InterfaceSpec; 2] = [
+    InterfaceSpec {
+        name: "primary",
+        interface_number: 0,
+        usage_page: UsagePage::GenericDesktop,
+    },
+    InterfaceSpec {
+        name: "events",
+        interface_number: 1,
+        usage_page: UsagePage::Vendor,
+    },
+];
+
+#[derive(ReportField, HidrawRead, HidrawWrite, Debug)]
 #[repr(C, packed)]
+#[hidraw_report(id = 0x05, len = 3)]
 pub struct Profile {
-    #[hidraw_constant = "0x05"]
-    _report_id: u8,
-    #[hidraw_constant = "::std::mem::size_of::<Self>() as u8"]
-    _size: u8,
     // first nibble: number of profiles enabled
     // second nibble: index of current profile
     pub index: u8,
@@ -262,20 +822,14 @@ pub struct Profile {
 
 impl Profile {
     fn new(index: u8) -> Self {
-        Profile {
-            index: index,
-            ..unsafe { ::std::mem::uninitialized() }
-        }
+        Profile { index }
     }
 }
 
-#[derive(HidrawRead, Debug)]
+#[derive(ReportField, HidrawRead, Debug)]
 #[repr(C, packed)]
+#[hidraw_report(id = 0x0f, len = 7)]
 pub struct DeviceInfo {
-    #[hidraw_constant = "0x0f"]
-    _report_id: u8,
-    #[hidraw_constant = "::std::mem::size_of::<Self>() as u8"]
-    _size: u8,
     pub firmware_version: u8,
     pub dfu_version: u8,
     pub led_firmware_version: u8,
@@ -1,4 +1,7 @@
-#[cfg_attr(rustfmt, rustfmt_skip)]
+/// Number of keys with an SDK index, which go from 0 to `SDK_KEY_COUNT - 1`
+pub const SDK_KEY_COUNT: u8 = 110;
+
+#[rustfmt::skip]
 const SDK_INDEX_TO_LIGHT_INDEX: [u8; 256] = [
     /*       0     1     2     3     4     5     6     7     8     9   */
     /*  0 */ 0x01, 0x02, 0x0e, 0x03, 0x0f, 0x04, 0x10, 0x05, 0x11, 0x06,
@@ -33,7 +36,7 @@ pub fn sdk_index_to_light_index(sdk_index: u8) -> u8 {
     SDK_INDEX_TO_LIGHT_INDEX[sdk_index as usize]
 }
 
-#[cfg_attr(rustfmt, rustfmt_skip)]
+#[rustfmt::skip]
 const EFFECT_INDEX_TO_SDK_INDEX: [u8; 256] = [
     /*       0     1     2     3     4     5     6     7     8     9   */
     /*  0 */ 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
@@ -0,0 +1,115 @@
+use crate::{
+    device::{
+        ryosmkfx::{Event, EventResult, KeyboardLayout},
+        DeviceId,
+    },
+    event_bus::Subscriber,
+    Error,
+};
+use std::ops::RangeInclusive;
+
+/// Firmware versions a device reports about itself
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct FirmwareInfo {
+    pub firmware_version: u8,
+    pub dfu_version: Option<u8>,
+    pub led_firmware_version: Option<u8>,
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Rgb {
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+}
+
+/// What every supported device can do
+///
+/// Optional features are discovered through the `as_*` accessors, which return `None` unless a
+/// device implements the capability trait.
+pub trait RoccatDevice: Send {
+    /// Marketing name of the model
+    fn name(&self) -> &str;
+
+    /// The USB device this was opened from, if it was opened through udev
+    fn id(&self) -> Option<&DeviceId>;
+
+    /// Gets the firmware versions, `None` if the device can't report them
+    fn firmware_info(&self) -> Result<Option<FirmwareInfo>, Error>;
+
+    /// Number of profiles, which are numbered starting at 1
+    fn profile_count(&self) -> u8;
+
+    fn get_profile(&self) -> Result<u8, Error>;
+
+    fn set_profile(&self, profile: u8) -> Result<(), Error>;
+
+    fn as_per_key_lighting(&self) -> Option<&dyn PerKeyLighting> {
+        None
+    }
+
+    fn as_key_remapping(&self) -> Option<&dyn KeyRemapping> {
+        None
+    }
+
+    fn as_dpi_control(&self) -> Option<&dyn DpiControl> {
+        None
+    }
+
+    fn as_event_source(&self) -> Option<&dyn EventSource> {
+        None
+    }
+
+    fn as_key_labels(&self) -> Option<&dyn KeyLabels> {
+        None
+    }
+}
+
+/// Lights each key individually, overriding the profile's light effect while active
+pub trait PerKeyLighting {
+    /// Number of keys, addressed by index `0..key_count()`
+    fn key_count(&self) -> u8;
+
+    /// Switches between per-key colors and the profile's stored lighting
+    fn set_per_key_lighting_active(&self, active: bool) -> Result<(), Error>;
+
+    /// Sets the color of each key by index, `None` and missing keys are unlit
+    fn set_key_colors(&self, colors: &[Option<Rgb>]) -> Result<(), Error>;
+}
+
+/// Changes which HID usage each key sends
+pub trait KeyRemapping {
+    /// Number of keys, addressed by index `0..key_count()`
+    fn key_count(&self) -> usize;
+
+    /// Gets the HID usage of each key in a profile
+    fn get_key_usages(&self, profile: u8) -> Result<Vec<u8>, Error>;
+
+    /// Sets the HID usage of each key in a profile, `usages` has to cover all keys
+    fn set_key_usages(&self, profile: u8, usages: &[u8]) -> Result<(), Error>;
+}
+
+/// Changes a mouse's sensitivity
+pub trait DpiControl {
+    fn dpi_range(&self) -> RangeInclusive<u16>;
+
+    fn get_dpi(&self) -> Result<u16, Error>;
+
+    fn set_dpi(&self, dpi: u16) -> Result<(), Error>;
+}
+
+/// Reports key presses and other events from the device
+pub trait EventSource {
+    /// Takes the oldest event that hasn't been taken yet
+    fn get_event(&self) -> Option<Event>;
+
+    /// Receives every event from now on, independently of `get_event` and other subscribers
+    fn subscribe(&self) -> Subscriber<EventResult>;
+}
+
+/// Labels keys after the layout printed on the keycaps, which the device itself doesn't know
+pub trait KeyLabels {
+    fn layout(&self) -> KeyboardLayout;
+
+    fn set_layout(&self, layout: KeyboardLayout);
+}
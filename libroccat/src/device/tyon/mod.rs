@@ -1,42 +1,136 @@
-use failure::{ensure, Error};
-use hidraw_derive::{HidrawRead, HidrawWrite};
-use std::{fs::File, path::PathBuf};
+use crate::{
+    descriptor::{self, ReportKind},
+    device::{
+        button::{self, ButtonConfig, ButtonFamily},
+        interface::{self, HidrawNode, InterfaceSpec, UsagePage},
+        ryosmkfx::Control,
+        DeviceId, FirmwareInfo, RoccatDevice,
+    },
+    transport::{HidrawTransport, Transport},
+    Error,
+};
+use hidraw_derive::{HidrawRead, HidrawWrite, ReportField};
+use std::{ops::RangeInclusive, path::PathBuf};
 
-pub struct Tyon {
-    interfaces: Vec<File>,
+/// Number of profiles, which are numbered starting at 1
+pub const PROFILE_COUNT: u8 = 5;
+
+/// Number of button functions per profile, from erazor_de's code
+pub const BUTTON_COUNT: usize = 32;
+
+/// `Control` request selecting the `ProfileButtons` of a profile, from erazor_de's code
+const CONTROL_REQUEST_PROFILE_BUTTONS: u8 = 0x90;
+
+const PROFILES: RangeInclusive<i64> = 1..=PROFILE_COUNT as i64;
+
+pub struct Tyon<T: Transport = HidrawTransport> {
+    id: Option<DeviceId>,
+    interfaces: Vec<T>,
 }
 
 impl Tyon {
+    /// Opens the device from its hidraw nodes, in any order
+    pub fn from_nodes(nodes: &[HidrawNode]) -> Result<Self, Error> {
+        Self::new(interface::classify(nodes, &INTERFACES)?)
+    }
+
+    /// Opens the device from hidraw paths given in interface order
     pub fn new(paths: Vec<PathBuf>) -> Result<Self, Error> {
         let mut interfaces = Vec::new();
         for path in paths {
-            interfaces.push(File::open(path)?);
+            interfaces.push(HidrawTransport::open(path)?);
         }
 
-        Ok(Self {
-            interfaces: interfaces,
-        })
+        Self::from_transports(interfaces)
+    }
+}
+
+impl<T: Transport> Tyon<T> {
+    /// Creates a device from already opened interfaces, in interface order
+    ///
+    /// Fails if an interface's report descriptor doesn't match the reports this crate uses.
+    pub fn from_transports(interfaces: Vec<T>) -> Result<Self, Error> {
+        let device = Self {
+            id: None,
+            interfaces,
+        };
+        descriptor::verify_reports(
+            device.get_interface(Interface::Primary)?,
+            ReportKind::Feature,
+            &[(Profile::REPORT_ID, Profile::LEN)],
+        )?;
+        Ok(device)
+    }
+
+    /// Gets the USB device this was opened from, `None` if it was built from transports directly
+    pub fn id(&self) -> Option<&DeviceId> {
+        self.id.as_ref()
+    }
+
+    pub fn set_id(&mut self, id: DeviceId) {
+        self.id = Some(id);
     }
 
-    pub fn get_interface(&self, interface: Interface) -> &File {
-        &self.interfaces[interface as usize]
+    pub fn get_interface(&self, interface: Interface) -> Result<&T, Error> {
+        let index = interface as usize;
+        self.interfaces
+            .get(index)
+            .ok_or(Error::MissingInterface { index })
     }
 
     /// Gets the current profile
     pub fn get_profile(&self) -> Result<u8, Error> {
-        unsafe { Ok(Profile::read(self.get_interface(Interface::Primary))?.index + 1) }
+        Ok(Profile::read(self.get_interface(Interface::Primary)?)?.index + 1)
     }
 
     /// Sets the current profile
     pub fn set_profile(&self, index: u8) -> Result<(), Error> {
-        unsafe {
-            ensure!(
-                index >= 1 && index <= 5,
-                "Profile {} is out of range",
-                index
-            );
-            Profile::new(index - 1).write(self.get_interface(Interface::Primary))
+        Error::ensure_range("Profile", index, PROFILES)?;
+        Profile::new(index - 1).write(self.get_interface(Interface::Primary)?)
+    }
+
+    /// Reads the button functions of a profile
+    pub fn get_buttons(&self, profile: u8) -> Result<[ButtonConfig; BUTTON_COUNT], Error> {
+        Error::ensure_range("Profile", profile, PROFILES)?;
+        let interface = self.get_interface(Interface::Primary)?;
+        Control::new(profile - 1, CONTROL_REQUEST_PROFILE_BUTTONS).write(interface)?;
+        Control::check_write(interface)?;
+
+        let buttons = ProfileButtons::read(interface)?;
+        if buttons.profile_index != profile - 1 {
+            return Err(Error::SelectionMismatch {
+                field: "profile index",
+                expected: profile - 1,
+                actual: buttons.profile_index,
+            });
         }
+        Ok(buttons.buttons)
+    }
+
+    /// Writes the button functions of a profile
+    ///
+    /// Fails for codes mice don't know. As no Tyon confirms the mouse codes yet, the buttons
+    /// are read back and compared, see `ButtonType::is_confirmed`.
+    pub fn set_buttons(
+        &self,
+        profile: u8,
+        buttons: &[ButtonConfig; BUTTON_COUNT],
+    ) -> Result<(), Error> {
+        Error::ensure_range("Profile", profile, PROFILES)?;
+        let family = ButtonFamily::Mouse;
+        for config in buttons.iter() {
+            config.button_type(family)?;
+            config.modifiers()?;
+        }
+
+        let interface = self.get_interface(Interface::Primary)?;
+        ProfileButtons {
+            profile_index: profile - 1,
+            buttons: *buttons,
+        }
+        .write(interface)?;
+        Control::check_write(interface)?;
+        button::check_stored(family, buttons, || self.get_buttons(profile))
     }
 
     pub fn get_common_name<'a>() -> &'a str {
@@ -44,26 +138,70 @@ impl Tyon {
     }
 }
 
+impl<T: Transport> RoccatDevice for Tyon<T> {
+    fn name(&self) -> &str {
+        Self::get_common_name()
+    }
+
+    fn id(&self) -> Option<&DeviceId> {
+        self.id()
+    }
+
+    fn firmware_info(&self) -> Result<Option<FirmwareInfo>, Error> {
+        Ok(None)
+    }
+
+    fn profile_count(&self) -> u8 {
+        PROFILE_COUNT
+    }
+
+    fn get_profile(&self) -> Result<u8, Error> {
+        self.get_profile()
+    }
+
+    fn set_profile(&self, profile: u8) -> Result<(), Error> {
+        self.set_profile(profile)
+    }
+}
+
 pub enum Interface {
     Primary = 0,
-    Events = 1,
 }
 
-#[derive(HidrawRead, HidrawWrite, Debug)]
+/// How to recognize each `Interface`, in the same order
+///
+/// Profiles are switched through feature reports on the mouse interface, the first USB
+/// interface, which reports on the generic desktop usage page. The Tyon's other interfaces
+/// aren't used by this driver.
+pub const INTERFACES: [InterfaceSpec; 1] = [InterfaceSpec {
+    name: "mouse",
+    interface_number: 0,
+    usage_page: UsagePage::GenericDesktop,
+}];
+
+#[derive(ReportField, HidrawRead, HidrawWrite, Debug)]
 #[repr(C, packed)]
+#[hidraw_report(id = 0x05, len = 3)]
 pub struct Profile {
-    #[hidraw_constant = "0x05"]
-    _report_id: u8,
-    #[hidraw_constant = "::std::mem::size_of::<Self>() as u8"]
-    _size: u8,
     pub index: u8,
 }
 
 impl Profile {
     fn new(index: u8) -> Self {
-        Profile {
-            index: index,
-            ..unsafe { ::std::mem::uninitialized() }
-        }
+        Profile { index }
     }
 }
+
+/// Button functions of a profile, selected with a `Control` request
+///
+/// The layout follows the Tyon profile buttons of roccat-tools (erazor_de's code). Unlike
+/// `Profile`, the report isn't checked against the report descriptor when opening the device,
+/// since no Tyon descriptor confirms it and switching profiles shouldn't depend on it. The profile
+/// index the device answers with is checked on every read instead.
+#[derive(ReportField, HidrawRead, HidrawWrite)]
+#[repr(C, packed)]
+#[hidraw_report(id = 0x07, len = 99)]
+pub struct ProfileButtons {
+    pub profile_index: u8,
+    pub buttons: [ButtonConfig; BUTTON_COUNT],
+}
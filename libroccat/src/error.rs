@@ -0,0 +1,332 @@
+use crate::{
+    descriptor::ReportKind,
+    device::{
+        button::{ButtonFamily, ButtonType},
+        ryosmkfx::ControlStatus,
+    },
+    transport::ChecksumMismatch,
+};
+use std::{error, fmt, io, ops::RangeInclusive, path::PathBuf, time::Duration};
+
+/// Everything that can go wrong while talking to a device
+#[derive(Debug)]
+pub enum Error {
+    /// No device matched
+    DeviceNotFound,
+    /// A device selector that couldn't be parsed
+    InvalidSelector {
+        selector: String,
+    },
+    /// A line of a binding table that couldn't be parsed
+    InvalidBinding {
+        line: usize,
+        message: String,
+    },
+    /// A line of a device configuration that couldn't be parsed
+    InvalidConfig {
+        line: usize,
+        message: String,
+    },
+    /// A line of a macro that couldn't be parsed
+    InvalidMacro {
+        line: usize,
+        message: String,
+    },
+    /// A key name that doesn't name a key of the device
+    UnknownKey {
+        name: String,
+    },
+    /// A keyboard layout name that isn't known
+    UnknownLayout {
+        name: String,
+    },
+    /// A key that can't be given a function on a layer
+    UnsupportedKeyAction {
+        key: &'static str,
+        layer: &'static str,
+    },
+    /// A button function the device family has no code for
+    UnsupportedButtonType {
+        button_type: ButtonType,
+        family: ButtonFamily,
+    },
+    /// The device didn't store a button function whose code isn't confirmed for the family
+    UnconfirmedButtonCode {
+        family: ButtonFamily,
+        written: u8,
+        stored: u8,
+    },
+    /// A Roccat device whose product isn't supported
+    UnsupportedDevice {
+        product_id: String,
+    },
+    /// The device node can't be opened by the current user
+    PermissionDenied {
+        path: PathBuf,
+    },
+    /// The device has no interface with this index
+    MissingInterface {
+        index: usize,
+    },
+    /// None of the device's hidraw nodes is the expected interface
+    InterfaceNotFound {
+        name: &'static str,
+        interface_number: u8,
+    },
+    /// The device was unplugged
+    Disconnected,
+    /// The device was still busy processing a request when the write check timed out
+    DeviceBusy {
+        elapsed: Duration,
+    },
+    /// A write check was cancelled through a `CancelToken`
+    Cancelled,
+    /// The device reported a critical error while processing a request
+    CriticalStatus(ControlStatus),
+    /// The device rejected a request as invalid
+    Rejected,
+    /// A value passed to the device is outside of the range it accepts
+    OutOfRange {
+        field: &'static str,
+        value: i64,
+        range: RangeInclusive<i64>,
+    },
+    /// A report's bytesum didn't match its contents
+    Checksum(ChecksumMismatch),
+    /// The device sent a value that isn't known for this field
+    InvalidValue {
+        type_name: &'static str,
+        value: u8,
+    },
+    /// The device answered for another selection than the one requested
+    SelectionMismatch {
+        field: &'static str,
+        expected: u8,
+        actual: u8,
+    },
+    /// A report started with a different report ID than expected
+    ReportId {
+        expected: u8,
+        actual: u8,
+    },
+    /// A report had a different length than expected
+    ReportLength {
+        report_id: u8,
+        expected: usize,
+        actual: usize,
+    },
+    /// The device's report descriptor doesn't declare a report the way libroccat expects it
+    ReportLayout {
+        kind: ReportKind,
+        report_id: u8,
+        expected: usize,
+        /// Length the descriptor declares, `None` if it doesn't declare the report at all
+        actual: Option<usize>,
+    },
+    /// A report descriptor couldn't be parsed
+    MalformedDescriptor {
+        position: usize,
+    },
+    Io(io::Error),
+    Udev(libudev::Error),
+}
+
+pub type Result<T> = ::std::result::Result<T, Error>;
+
+impl Error {
+    /// Checks that `value` lies within `range`
+    pub(crate) fn ensure_range<V: Into<i64>>(
+        field: &'static str,
+        value: V,
+        range: RangeInclusive<i64>,
+    ) -> Result<()> {
+        let value = value.into();
+        if range.contains(&value) {
+            Ok(())
+        } else {
+            Err(Error::OutOfRange {
+                field,
+                value,
+                range,
+            })
+        }
+    }
+
+    /// Whether retrying the failed transfer can't possibly succeed
+    pub fn is_permanent(&self) -> bool {
+        matches!(
+            *self,
+            Error::PermissionDenied { .. } | Error::Disconnected | Error::Cancelled
+        )
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            Error::Io(ref error) => Some(error),
+            Error::Udev(ref error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::DeviceNotFound => write!(fmt, "Device not found"),
+            Error::InvalidSelector { ref selector } => write!(
+                fmt,
+                "Invalid device selector {}, expected e.g. ryosmkfx, tyon#2, serial:XXXX or \
+                 /dev/hidraw3",
+                selector
+            ),
+            Error::InvalidBinding { line, ref message } => {
+                write!(fmt, "Invalid binding on line {}: {}", line, message)
+            }
+            Error::InvalidConfig { line, ref message } => {
+                write!(fmt, "Invalid configuration on line {}: {}", line, message)
+            }
+            Error::InvalidMacro { line, ref message } => {
+                write!(fmt, "Invalid macro on line {}: {}", line, message)
+            }
+            Error::UnknownKey { ref name } => write!(fmt, "Unknown key {}", name),
+            Error::UnknownLayout { ref name } => write!(
+                fmt,
+                "Unknown keyboard layout {}, expected us, uk, de, fr or nordic",
+                name
+            ),
+            Error::UnsupportedKeyAction { key, layer } => write!(
+                fmt,
+                "Key {} can't be given this function on the {} layer",
+                key, layer
+            ),
+            Error::UnsupportedButtonType {
+                button_type,
+                family,
+            } => write!(
+                fmt,
+                "Button function {:?} isn't supported on {} buttons",
+                button_type,
+                family.name()
+            ),
+            Error::UnconfirmedButtonCode {
+                family,
+                written,
+                stored,
+            } => write!(
+                fmt,
+                "Device stored unconfirmed {} button code {:#04x} as {:#04x}",
+                family.name(),
+                written,
+                stored
+            ),
+            Error::UnsupportedDevice { ref product_id } => {
+                write!(fmt, "Incompatible Roccat device {}", product_id)
+            }
+            Error::PermissionDenied { ref path } => {
+                write!(fmt, "Permission denied opening {}", path.display())
+            }
+            Error::MissingInterface { index } => {
+                write!(fmt, "Device is missing interface {}", index)
+            }
+            Error::InterfaceNotFound {
+                name,
+                interface_number,
+            } => write!(
+                fmt,
+                "No hidraw node found for the {} interface (USB interface {})",
+                name, interface_number
+            ),
+            Error::Disconnected => write!(fmt, "Device was disconnected"),
+            Error::DeviceBusy { elapsed } => {
+                write!(fmt, "Device still busy after {}ms", elapsed.as_millis())
+            }
+            Error::Cancelled => write!(fmt, "Write check was cancelled"),
+            Error::CriticalStatus(status) => write!(fmt, "Got critical status {:?}", status),
+            Error::Rejected => write!(fmt, "Device rejected the request"),
+            Error::OutOfRange {
+                field,
+                value,
+                ref range,
+            } => write!(
+                fmt,
+                "{} {} is out of range {}..={}",
+                field,
+                value,
+                range.start(),
+                range.end()
+            ),
+            Error::Checksum(ref mismatch) => write!(fmt, "{}", mismatch),
+            Error::InvalidValue { type_name, value } => {
+                write!(fmt, "Unknown {} value {:#04x}", type_name, value)
+            }
+            Error::SelectionMismatch {
+                field,
+                expected,
+                actual,
+            } => write!(
+                fmt,
+                "Device answered with {} {}, expected {}",
+                field, actual, expected
+            ),
+            Error::ReportId { expected, actual } => write!(
+                fmt,
+                "Got report {:#04x}, expected report {:#04x}",
+                actual, expected
+            ),
+            Error::ReportLength {
+                report_id,
+                expected,
+                actual,
+            } => write!(
+                fmt,
+                "Report {:#04x} has length {}, expected {}",
+                report_id, actual, expected
+            ),
+            Error::ReportLayout {
+                kind,
+                report_id,
+                expected,
+                actual: Some(actual),
+            } => write!(
+                fmt,
+                "Unsupported firmware: {:?} report {:#04x} is {} bytes long instead of {}",
+                kind, report_id, actual, expected
+            ),
+            Error::ReportLayout {
+                kind,
+                report_id,
+                actual: None,
+                ..
+            } => write!(
+                fmt,
+                "Unsupported firmware: {:?} report {:#04x} is missing",
+                kind, report_id
+            ),
+            Error::MalformedDescriptor { position } => {
+                write!(fmt, "Malformed report descriptor at byte {}", position)
+            }
+            Error::Io(ref error) => write!(fmt, "{}", error),
+            Error::Udev(ref error) => write!(fmt, "{}", error),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        Error::Io(error)
+    }
+}
+
+impl From<libudev::Error> for Error {
+    fn from(error: libudev::Error) -> Self {
+        Error::Udev(error)
+    }
+}
+
+impl From<ChecksumMismatch> for Error {
+    fn from(mismatch: ChecksumMismatch) -> Self {
+        Error::Checksum(mismatch)
+    }
+}
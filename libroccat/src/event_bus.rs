@@ -0,0 +1,264 @@
+#[cfg(feature = "async")]
+use futures::{
+    stream::Stream,
+    task::{Context, Poll, Waker},
+};
+#[cfg(feature = "async")]
+use std::pin::Pin;
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Condvar, Mutex, Weak},
+    time::{Duration, Instant},
+};
+
+/// Events a subscriber buffers by default before its overflow policy kicks in
+pub const DEFAULT_CAPACITY: usize = 256;
+
+/// What to do when an event arrives for a subscriber whose buffer is full
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Overflow {
+    /// Discard the oldest buffered event to make room, for consumers that care about the present
+    DropOldest,
+    /// Discard the arriving event, for consumers that need to see what happened first
+    DropNewest,
+}
+
+/// Hands every published event to all of its subscribers
+///
+/// Each subscriber has its own bounded buffer, so a slow or abandoned subscriber never holds up
+/// the others or grows without bounds. Subscribers are closed once the bus is dropped.
+pub struct EventBus<T> {
+    subscribers: Mutex<Vec<Weak<Queue<T>>>>,
+}
+
+struct Queue<T> {
+    state: Mutex<QueueState<T>>,
+    available: Condvar,
+}
+
+struct QueueState<T> {
+    events: VecDeque<T>,
+    capacity: usize,
+    overflow: Overflow,
+    dropped: u64,
+    closed: bool,
+    /// Task polling the subscriber as a `Stream`
+    #[cfg(feature = "async")]
+    waker: Option<Waker>,
+}
+
+/// Receives every event published on an `EventBus` after subscribing
+pub struct Subscriber<T> {
+    queue: Arc<Queue<T>>,
+}
+
+impl<T> Default for EventBus<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> EventBus<T> {
+    pub fn new() -> Self {
+        Self {
+            subscribers: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Subscribes with a buffer of `DEFAULT_CAPACITY` events that drops the oldest on overflow
+    pub fn subscribe(&self) -> Subscriber<T> {
+        self.subscribe_with(DEFAULT_CAPACITY, Overflow::DropOldest)
+    }
+
+    /// Subscribes with a buffer of `capacity` events, which is at least one
+    pub fn subscribe_with(&self, capacity: usize, overflow: Overflow) -> Subscriber<T> {
+        let capacity = capacity.max(1);
+        let queue = Arc::new(Queue {
+            state: Mutex::new(QueueState {
+                events: VecDeque::with_capacity(capacity),
+                capacity,
+                overflow,
+                dropped: 0,
+                closed: false,
+                #[cfg(feature = "async")]
+                waker: None,
+            }),
+            available: Condvar::new(),
+        });
+        self.subscribers
+            .lock()
+            .unwrap()
+            .push(Arc::downgrade(&queue));
+        Subscriber { queue }
+    }
+
+    /// Number of subscribers that haven't been dropped
+    pub fn subscriber_count(&self) -> usize {
+        let mut subscribers = self.subscribers.lock().unwrap();
+        subscribers.retain(|queue| queue.strong_count() > 0);
+        subscribers.len()
+    }
+}
+
+impl<T: Clone> EventBus<T> {
+    /// Buffers `event` for every subscriber
+    pub fn publish(&self, event: T) {
+        let mut subscribers = self.subscribers.lock().unwrap();
+        subscribers.retain(|queue| match queue.upgrade() {
+            Some(queue) => {
+                queue.push(event.clone());
+                true
+            }
+            None => false,
+        });
+    }
+}
+
+impl<T> Drop for EventBus<T> {
+    fn drop(&mut self) {
+        for queue in self.subscribers.lock().unwrap().iter() {
+            if let Some(queue) = queue.upgrade() {
+                let mut state = queue.state.lock().unwrap();
+                state.closed = true;
+                state.wake();
+                queue.available.notify_all();
+            }
+        }
+    }
+}
+
+impl<T> Queue<T> {
+    fn push(&self, event: T) {
+        let mut state = self.state.lock().unwrap();
+        if state.events.len() >= state.capacity {
+            state.dropped += 1;
+            match state.overflow {
+                Overflow::DropOldest => drop(state.events.pop_front()),
+                Overflow::DropNewest => return,
+            }
+        }
+        state.events.push_back(event);
+        state.wake();
+        self.available.notify_all();
+    }
+}
+
+impl<T> QueueState<T> {
+    #[cfg(feature = "async")]
+    fn wake(&mut self) {
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+
+    #[cfg(not(feature = "async"))]
+    fn wake(&mut self) {}
+}
+
+impl<T> Subscriber<T> {
+    /// Takes the oldest buffered event without blocking
+    pub fn try_recv(&self) -> Option<T> {
+        self.queue.state.lock().unwrap().events.pop_front()
+    }
+
+    /// Waits up to `timeout` for an event, `None` if none arrived or the bus is gone
+    pub fn recv_timeout(&self, timeout: Duration) -> Option<T> {
+        self.recv_until(Some(Instant::now() + timeout))
+    }
+
+    /// Blocks until an event arrives, `None` once the bus is gone and the buffer is drained
+    pub fn recv(&self) -> Option<T> {
+        self.recv_until(None)
+    }
+
+    fn recv_until(&self, deadline: Option<Instant>) -> Option<T> {
+        let mut state = self.queue.state.lock().unwrap();
+        loop {
+            if let Some(event) = state.events.pop_front() {
+                return Some(event);
+            }
+            if state.closed {
+                return None;
+            }
+            state = match deadline {
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        return None;
+                    }
+                    let available = &self.queue.available;
+                    available.wait_timeout(state, deadline - now).unwrap().0
+                }
+                None => self.queue.available.wait(state).unwrap(),
+            };
+        }
+    }
+
+    /// Number of events buffered right now
+    pub fn len(&self) -> usize {
+        self.queue.state.lock().unwrap().events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of events lost to the overflow policy so far
+    pub fn dropped(&self) -> u64 {
+        self.queue.state.lock().unwrap().dropped
+    }
+
+    /// Whether the bus is gone, buffered events can still be received
+    pub fn is_closed(&self) -> bool {
+        self.queue.state.lock().unwrap().closed
+    }
+}
+
+impl<T> Iterator for Subscriber<T> {
+    type Item = T;
+
+    /// Blocks until the next event, ending once the bus is gone
+    fn next(&mut self) -> Option<T> {
+        self.recv()
+    }
+}
+
+/// A `Subscriber` as a `Stream`, which yields every event without blocking a thread and ends
+/// once the bus is gone
+#[cfg(feature = "async")]
+pub struct SubscriberStream<T>(Subscriber<T>);
+
+#[cfg(feature = "async")]
+impl<T> Subscriber<T> {
+    pub fn into_stream(self) -> SubscriberStream<T> {
+        SubscriberStream(self)
+    }
+}
+
+#[cfg(feature = "async")]
+impl<T> SubscriberStream<T> {
+    pub fn get_ref(&self) -> &Subscriber<T> {
+        &self.0
+    }
+
+    pub fn into_inner(self) -> Subscriber<T> {
+        self.0
+    }
+}
+
+#[cfg(feature = "async")]
+impl<T> Stream for SubscriberStream<T> {
+    type Item = T;
+
+    fn poll_next(self: Pin<&mut Self>, context: &mut Context<'_>) -> Poll<Option<T>> {
+        let mut state = self.0.queue.state.lock().unwrap();
+        if let Some(event) = state.events.pop_front() {
+            Poll::Ready(Some(event))
+        } else if state.closed {
+            Poll::Ready(None)
+        } else {
+            state.waker = Some(context.waker().clone());
+            Poll::Pending
+        }
+    }
+}
@@ -0,0 +1,246 @@
+use crate::{
+    device::{Device, DeviceId},
+    udev, Error,
+};
+use log::debug;
+use nix::{
+    errno::Errno,
+    poll::{poll, PollFd, PollFlags},
+};
+use std::{
+    collections::{HashMap, HashSet},
+    io,
+    marker::PhantomData,
+    os::unix::io::AsRawFd,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+/// How often the monitor thread checks whether its `Monitor` was dropped
+const STOP_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A change in the set of attached devices
+pub enum Hotplug<D = Device> {
+    /// A supported device was attached and opened
+    Added(D),
+    /// A previously added device was detached
+    Removed(DeviceId),
+}
+
+/// Notifies about supported devices being attached and detached
+///
+/// Devices that are already attached when monitoring starts are reported as added first. Errors
+/// opening a device are passed on without ending the monitor, unsupported products of known
+/// vendors are skipped.
+pub struct Monitor {
+    events: mpsc::Receiver<Result<Hotplug, Error>>,
+    stop: Arc<AtomicBool>,
+}
+
+impl Monitor {
+    /// Gets the next notification if there is one, without blocking
+    pub fn try_next(&self) -> Option<Result<Hotplug, Error>> {
+        self.events.try_recv().ok()
+    }
+
+    /// Waits up to `timeout` for the next notification
+    pub fn next_timeout(&self, timeout: Duration) -> Option<Result<Hotplug, Error>> {
+        self.events.recv_timeout(timeout).ok()
+    }
+
+    /// The channel notifications arrive on, e.g. to `select` on it with other channels
+    pub fn receiver(&self) -> &mpsc::Receiver<Result<Hotplug, Error>> {
+        &self.events
+    }
+}
+
+impl Iterator for Monitor {
+    type Item = Result<Hotplug, Error>;
+
+    /// Blocks until the next notification
+    fn next(&mut self) -> Option<Self::Item> {
+        self.events.recv().ok()
+    }
+}
+
+impl Drop for Monitor {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Starts watching udev for supported devices
+pub fn monitor() -> Result<Monitor, Error> {
+    let (sender, events) = mpsc::channel();
+    let (ready_sender, ready) = mpsc::channel();
+    let stop = Arc::new(AtomicBool::new(false));
+
+    let thread_stop = Arc::clone(&stop);
+    thread::spawn(move || run(&sender, &ready_sender, &thread_stop));
+
+    ready
+        .recv()
+        .unwrap_or_else(|_| Err(io::Error::other("Hotplug monitor thread died").into()))?;
+    Ok(Monitor { events, stop })
+}
+
+fn listen(context: &libudev::Context) -> Result<libudev::MonitorSocket<'_>, Error> {
+    let mut monitor = libudev::Monitor::new(context)?;
+    monitor.match_subsystem("hidraw")?;
+    monitor.match_subsystem_devtype("usb", "usb_device")?;
+    Ok(monitor.listen()?)
+}
+
+fn run(
+    events: &mpsc::Sender<Result<Hotplug, Error>>,
+    ready: &mpsc::Sender<Result<(), Error>>,
+    stop: &AtomicBool,
+) {
+    let context = match libudev::Context::new() {
+        Ok(context) => context,
+        Err(error) => return drop(ready.send(Err(error.into()))),
+    };
+    // Listen before enumerating, so nothing attached in between is missed
+    let mut socket = match listen(&context) {
+        Ok(socket) => socket,
+        Err(error) => return drop(ready.send(Err(error))),
+    };
+    let _ = ready.send(Ok(()));
+
+    let mut tracker = Tracker::new(|syspath: &Path| open(&context, syspath));
+    let existing = match usb_devices(&context) {
+        Ok(existing) => existing,
+        Err(error) => return drop(events.send(Err(error))),
+    };
+    for syspath in existing {
+        if let Some(notification) = tracker.added(&syspath) {
+            if events.send(notification).is_err() {
+                return;
+            }
+        }
+    }
+
+    while !stop.load(Ordering::SeqCst) {
+        let mut fds = [PollFd::new(socket.as_raw_fd(), PollFlags::POLLIN)];
+        match poll(&mut fds, STOP_POLL_INTERVAL.as_millis() as i32) {
+            Ok(0) | Err(nix::Error::Sys(Errno::EINTR)) => continue,
+            Ok(_) => (),
+            Err(error) => {
+                let _ = events.send(Err(io::Error::other(error.to_string()).into()));
+                return;
+            }
+        }
+
+        while let Some(event) = socket.receive_event() {
+            let notification = match event.event_type() {
+                libudev::EventType::Add if event.subsystem() == "hidraw" => {
+                    // The USB device is the closest ancestor with USB IDs
+                    event
+                        .syspath()
+                        .ancestors()
+                        .find(|path| path.join("idVendor").exists())
+                        .and_then(|syspath| tracker.added(syspath))
+                }
+                libudev::EventType::Remove if event.subsystem() == "usb" => {
+                    tracker.removed(event.syspath()).map(Ok)
+                }
+                _ => None,
+            };
+            if let Some(notification) = notification {
+                if events.send(notification).is_err() {
+                    // The `Monitor` is gone
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// Lists the syspaths of all attached USB devices
+fn usb_devices(context: &libudev::Context) -> Result<Vec<PathBuf>, Error> {
+    let mut enumerator = libudev::Enumerator::new(context)?;
+    enumerator.match_subsystem("usb")?;
+    enumerator.match_property("DEVTYPE", "usb_device")?;
+    Ok(enumerator
+        .scan_devices()?
+        .map(|device| device.syspath().to_path_buf())
+        .collect())
+}
+
+/// Opens the USB device at `syspath`, `None` if it's gone already or from an unknown vendor
+fn open(context: &libudev::Context, syspath: &Path) -> Option<Result<(DeviceId, Device), Error>> {
+    let parent = context.device_from_syspath(syspath).ok()?;
+    udev::probe(context, &parent).map(|candidate| {
+        let candidate = candidate?;
+        let id = candidate.id.clone();
+        candidate.open().map(|device| (id, device))
+    })
+}
+
+/// Keeps track of attached devices, turning udev's changes into notifications
+///
+/// Doesn't talk to udev itself, devices are opened through a function taking the syspath of
+/// their USB device. It returns `None` for devices that should be ignored silently, unsupported
+/// products of known vendors are ignored as well.
+pub struct Tracker<D, F> {
+    open: F,
+    /// Opened devices by the syspath of their USB device
+    present: HashMap<PathBuf, DeviceId>,
+    /// Unsupported USB devices, so their other hidraw nodes aren't looked at again
+    unsupported: HashSet<PathBuf>,
+    device: PhantomData<fn() -> D>,
+}
+
+impl<D, F> Tracker<D, F>
+where
+    F: FnMut(&Path) -> Option<Result<(DeviceId, D), Error>>,
+{
+    pub fn new(open: F) -> Self {
+        Self {
+            open,
+            present: HashMap::new(),
+            unsupported: HashSet::new(),
+            device: PhantomData,
+        }
+    }
+
+    /// The USB device at `syspath` or one of its hidraw nodes was attached
+    ///
+    /// Returns a notification unless the device is open already or can't be opened yet.
+    pub fn added(&mut self, syspath: &Path) -> Option<Result<Hotplug<D>, Error>> {
+        if self.present.contains_key(syspath) || self.unsupported.contains(syspath) {
+            return None;
+        }
+
+        match (self.open)(syspath)? {
+            Ok((id, device)) => {
+                self.present.insert(syspath.to_path_buf(), id);
+                Some(Ok(Hotplug::Added(device)))
+            }
+            Err(Error::InterfaceNotFound { name, .. }) => {
+                // Each hidraw node is announced separately, try again with the next one
+                debug!("{} has no {} interface yet", syspath.display(), name);
+                None
+            }
+            Err(Error::UnsupportedDevice { product_id }) => {
+                debug!("Ignoring unsupported product {}", product_id);
+                self.unsupported.insert(syspath.to_path_buf());
+                None
+            }
+            Err(error) => Some(Err(error)),
+        }
+    }
+
+    /// The USB device at `syspath` was detached
+    ///
+    /// Returns a notification if it was added before.
+    pub fn removed(&mut self, syspath: &Path) -> Option<Hotplug<D>> {
+        self.unsupported.remove(syspath);
+        self.present.remove(syspath).map(Hotplug::Removed)
+    }
+}
@@ -1,79 +1,60 @@
+#[cfg(feature = "async")]
+pub mod asynchronous;
+pub mod bindings;
+pub mod config;
+pub mod descriptor;
 pub mod device;
+mod error;
+pub mod event_bus;
+pub mod hotplug;
+pub mod registry;
+pub mod report;
+pub mod selector;
+pub mod transport;
+mod udev;
 
-use crate::device::{ryosmkfx::RyosMkFx, tyon::Tyon, Device};
-use failure::{format_err, Error};
+pub use crate::error::{Error, Result};
 
-pub fn find_devices() -> Result<Vec<Device>, Error> {
-    let context = libudev::Context::new().unwrap();
-    let mut enumerator = libudev::Enumerator::new(&context)?;
-    enumerator.match_subsystem("usb")?;
-    enumerator.match_attribute("idVendor", "1e7d")?;
-    Ok(enumerator
-        .scan_devices()?
-        .filter(|parent| parent.attribute_value("idProduct").is_some())
-        .map(|parent| -> Result<Device, Error> {
-            let mut enumerator = libudev::Enumerator::new(&context)?;
-            enumerator.match_subsystem("hidraw")?;
-            enumerator.match_parent(&parent)?;
-            match parent
-                .attribute_value("idProduct")
-                .unwrap()
-                .to_str()
-                .unwrap()
-            {
-                // Ryos MK FX
-                "2fda" => Some(Device::RyosMkFx(RyosMkFx::new(
-                    enumerator
-                        .scan_devices()
-                        .unwrap()
-                        .filter_map(|device| {
-                            if let Some(devnode) = device.devnode() {
-                                Some(devnode.to_path_buf())
-                            } else {
-                                None
-                            }
-                        })
-                        .collect(),
-                )?)),
-                // Tyon Black
-                "2e4a" => Some(Device::Tyon(Tyon::new(
-                    enumerator
-                        .scan_devices()
-                        .unwrap()
-                        .filter_map(|device| {
-                            if let Some(devnode) = device.devnode() {
-                                Some(devnode.to_path_buf())
-                            } else {
-                                None
-                            }
-                        })
-                        .collect(),
-                )?)),
-                // Tyon White
-                "2e4b" => Some(Device::Tyon(Tyon::new(
-                    enumerator
-                        .scan_devices()
-                        .unwrap()
-                        .filter_map(|device| {
-                            if let Some(devnode) = device.devnode() {
-                                Some(devnode.to_path_buf())
-                            } else {
-                                None
-                            }
-                        })
-                        .collect(),
-                )?)),
-                _ => None,
-            }
-            .ok_or_else(|| format_err!("Incompatible Roccat device"))
-        })
-        .filter_map(|device| {
-            if let Ok(device) = device {
-                Some(device)
-            } else {
-                println!("{}", device.err().unwrap());
+use crate::{device::Device, selector::Selector, udev::Candidate};
+use log::warn;
+
+/// Opens all supported devices, ordered by bus path
+///
+/// Fails with the first error if there were devices but none of them could be opened.
+pub fn find_devices() -> Result<Vec<Device>> {
+    let context = libudev::Context::new()?;
+    let mut first_error = None;
+    let devices: Vec<Device> = udev::candidates(&context)?
+        .into_iter()
+        .map(|candidate| candidate.and_then(Candidate::open))
+        .filter_map(|device| match device {
+            Ok(device) => Some(device),
+            Err(error) => {
+                warn!("Skipping device: {}", error);
+                first_error.get_or_insert(error);
                 None
             }
         })
-        .collect())
+        .collect();
+
+    // Only fail if nothing could be opened, so one broken device doesn't hide the others
+    match first_error {
+        Some(error) if devices.is_empty() => Err(error),
+        _ => Ok(devices),
+    }
+}
+
+/// Opens the device picked by a selector like `ryosmkfx`, `tyon#2`, `serial:XXXX` or
+/// `/dev/hidraw3`, see `Selector`
+pub fn open(selector: &str) -> Result<Device> {
+    let selector: Selector = selector.parse()?;
+    let context = libudev::Context::new()?;
+    // Unsupported products can't be what was selected
+    let (candidates, first_error) = udev::supported(&context)?;
+
+    // A device that couldn't be looked at may well be the one that was meant
+    match selector.select(candidates) {
+        Some(candidate) => candidate.open(),
+        None => Err(first_error.unwrap_or(Error::DeviceNotFound)),
+    }
 }
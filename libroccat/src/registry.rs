@@ -0,0 +1,183 @@
+use crate::{
+    device::{
+        interface::{self, HidrawNode, InterfaceSpec},
+        ryosmkfx::{self, RyosMkFx},
+        tyon::{self, Tyon},
+        Device,
+    },
+    Error,
+};
+use std::{path::PathBuf, sync::RwLock};
+
+/// USB vendor ID of Roccat
+pub const ROCCAT_VENDOR_ID: u16 = 0x1e7d;
+
+static REGISTRY: RwLock<Registry> = RwLock::new(Registry::new());
+
+/// Products supported out of the box
+const BUILTIN_PRODUCTS: [Product; 3] = [
+    Product {
+        vendor_id: ROCCAT_VENDOR_ID,
+        product_id: 0x2fda,
+        name: "Ryos MK FX",
+        family: Family::RyosMkFx,
+        interfaces: &ryosmkfx::INTERFACES,
+        capabilities: &[
+            Capability::Profiles,
+            Capability::Lights,
+            Capability::CustomLights,
+            Capability::Keys,
+            Capability::Events,
+        ],
+    },
+    Product {
+        vendor_id: ROCCAT_VENDOR_ID,
+        product_id: 0x2e4a,
+        name: "Tyon Black",
+        family: Family::Tyon,
+        interfaces: &tyon::INTERFACES,
+        capabilities: &[Capability::Profiles],
+    },
+    Product {
+        vendor_id: ROCCAT_VENDOR_ID,
+        product_id: 0x2e4b,
+        name: "Tyon White",
+        family: Family::Tyon,
+        interfaces: &tyon::INTERFACES,
+        capabilities: &[Capability::Profiles],
+    },
+];
+
+/// The driver a product is handled by
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Family {
+    RyosMkFx,
+    Tyon,
+}
+
+impl Family {
+    /// Name used to select devices of this family, like `ryosmkfx`
+    pub fn name(self) -> &'static str {
+        match self {
+            Family::RyosMkFx => "ryosmkfx",
+            Family::Tyon => "tyon",
+        }
+    }
+
+    /// Opens a device of this family from hidraw paths given in interface order
+    pub fn open(self, paths: Vec<PathBuf>) -> Result<Device, Error> {
+        Ok(match self {
+            Family::RyosMkFx => Device::RyosMkFx(RyosMkFx::new(paths)?),
+            Family::Tyon => Device::Tyon(Tyon::new(paths)?),
+        })
+    }
+}
+
+/// Something a product can do
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Capability {
+    Profiles,
+    Lights,
+    CustomLights,
+    Keys,
+    Events,
+}
+
+/// A supported USB product and how to drive it
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Product {
+    pub vendor_id: u16,
+    pub product_id: u16,
+    /// Marketing name
+    pub name: &'static str,
+    pub family: Family,
+    /// Interfaces the driver needs, in the family's interface order
+    pub interfaces: &'static [InterfaceSpec],
+    pub capabilities: &'static [Capability],
+}
+
+impl Product {
+    pub fn has_capability(&self, capability: Capability) -> bool {
+        self.capabilities.contains(&capability)
+    }
+
+    /// Picks this product's interfaces among `nodes` and opens it
+    pub fn open(&self, nodes: &[HidrawNode]) -> Result<Device, Error> {
+        self.family
+            .open(interface::classify(nodes, self.interfaces)?)
+    }
+}
+
+/// Supported products, the builtin ones plus registered ones
+///
+/// The functions of this module use a process wide registry, separate instances are useful to
+/// keep registrations apart, e.g. in tests.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Registry {
+    registered: Vec<Product>,
+}
+
+impl Registry {
+    /// Creates a registry with only the builtin products
+    pub const fn new() -> Self {
+        Self {
+            registered: Vec::new(),
+        }
+    }
+
+    /// Lists all supported products, registered ones first
+    pub fn products(&self) -> Vec<Product> {
+        let builtin = BUILTIN_PRODUCTS.iter().filter(|builtin| {
+            !self.registered.iter().any(|product| {
+                (product.vendor_id, product.product_id) == (builtin.vendor_id, builtin.product_id)
+            })
+        });
+        self.registered.iter().chain(builtin).cloned().collect()
+    }
+
+    /// Looks up a supported product by its USB IDs
+    pub fn find_product(&self, vendor_id: u16, product_id: u16) -> Option<Product> {
+        self.products()
+            .into_iter()
+            .find(|product| (product.vendor_id, product.product_id) == (vendor_id, product_id))
+    }
+
+    /// Adds a product, e.g. a regional variant handled by an existing family
+    ///
+    /// Replaces an earlier registration or builtin product with the same IDs.
+    pub fn register_product(&mut self, product: Product) {
+        self.registered.retain(|registered| {
+            (registered.vendor_id, registered.product_id) != (product.vendor_id, product.product_id)
+        });
+        self.registered.push(product);
+    }
+
+    /// Whether `find_devices` should look at USB devices from this vendor
+    pub fn is_known_vendor(&self, vendor_id: u16) -> bool {
+        vendor_id == ROCCAT_VENDOR_ID
+            || self
+                .registered
+                .iter()
+                .any(|product| product.vendor_id == vendor_id)
+    }
+}
+
+/// Lists all supported products, registered ones first
+pub fn products() -> Vec<Product> {
+    REGISTRY.read().unwrap().products()
+}
+
+/// Looks up a supported product by its USB IDs
+pub fn find_product(vendor_id: u16, product_id: u16) -> Option<Product> {
+    REGISTRY.read().unwrap().find_product(vendor_id, product_id)
+}
+
+/// Adds a product to the process wide registry, see `Registry::register_product`
+pub fn register_product(product: Product) {
+    REGISTRY.write().unwrap().register_product(product);
+}
+
+/// Whether `find_devices` should look at USB devices from this vendor
+pub(crate) fn is_known_vendor(vendor_id: u16) -> bool {
+    REGISTRY.read().unwrap().is_known_vendor(vendor_id)
+}
@@ -0,0 +1,63 @@
+use crate::Error;
+use std::convert::TryFrom;
+
+/// A value with a fixed size, little-endian encoding inside a HID report
+///
+/// Implemented for plain integers and arrays here, and derived with `#[derive(ReportField)]` for
+/// report structs and `#[repr(u8)]` enums. Derived enums reject discriminants they don't know.
+pub trait ReportField: Sized {
+    /// Size of the encoded value in bytes
+    const SIZE: usize;
+
+    /// Writes the value to the start of `bytes`, which is at least `SIZE` bytes long
+    fn encode(&self, bytes: &mut [u8]);
+
+    /// Reads a value from the start of `bytes`, which is at least `SIZE` bytes long
+    fn decode(bytes: &[u8]) -> Result<Self, Error>;
+}
+
+impl ReportField for u8 {
+    const SIZE: usize = 1;
+
+    fn encode(&self, bytes: &mut [u8]) {
+        bytes[0] = *self;
+    }
+
+    fn decode(bytes: &[u8]) -> Result<Self, Error> {
+        Ok(bytes[0])
+    }
+}
+
+impl ReportField for u16 {
+    const SIZE: usize = 2;
+
+    fn encode(&self, bytes: &mut [u8]) {
+        bytes[..2].copy_from_slice(&self.to_le_bytes());
+    }
+
+    fn decode(bytes: &[u8]) -> Result<Self, Error> {
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+}
+
+impl<T: ReportField, const N: usize> ReportField for [T; N] {
+    const SIZE: usize = T::SIZE * N;
+
+    fn encode(&self, bytes: &mut [u8]) {
+        for (value, bytes) in self.iter().zip(bytes.chunks_mut(T::SIZE)) {
+            value.encode(bytes);
+        }
+    }
+
+    fn decode(bytes: &[u8]) -> Result<Self, Error> {
+        let values = bytes
+            .chunks(T::SIZE)
+            .take(N)
+            .map(T::decode)
+            .collect::<Result<Vec<T>, Error>>()?;
+        match <[T; N]>::try_from(values) {
+            Ok(values) => Ok(values),
+            Err(_) => unreachable!("Decoded the wrong number of array elements"),
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use crate::{device::DeviceId, Error};
+use std::{path::PathBuf, str::FromStr};
+
+pub use crate::udev::Candidate;
+
+/// Picks one device among the attached ones
+///
+/// Parsed from `ryosmkfx` or `tyon#2` (the nth device of a family ordered by bus path, starting
+/// at 1), `serial:XXXX`, `usb:1-4.2` (the bus path) or a hidraw node like `/dev/hidraw3`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Selector {
+    Family { name: String, index: usize },
+    Serial(String),
+    BusPath(String),
+    Hidraw(PathBuf),
+}
+
+impl FromStr for Selector {
+    type Err = Error;
+
+    fn from_str(selector: &str) -> Result<Self, Error> {
+        let invalid = || Error::InvalidSelector {
+            selector: selector.to_string(),
+        };
+
+        if selector.starts_with('/') {
+            return Ok(Selector::Hidraw(PathBuf::from(selector)));
+        }
+        if let Some(serial) = selector.strip_prefix("serial:") {
+            return Ok(Selector::Serial(serial.to_string()));
+        }
+        if let Some(bus_path) = selector.strip_prefix("usb:") {
+            return Ok(Selector::BusPath(bus_path.to_string()));
+        }
+
+        let (name, index) = match selector.split_once('#') {
+            Some((name, index)) => (name, index.parse().map_err(|_| invalid())?),
+            None => (selector, 1),
+        };
+        if name.is_empty() || index == 0 {
+            return Err(invalid());
+        }
+        Ok(Selector::Family {
+            name: name.to_lowercase(),
+            index,
+        })
+    }
+}
+
+impl Selector {
+    /// Picks the selected device among `candidates`, which have to be ordered by bus path
+    pub fn select(self, candidates: Vec<Candidate>) -> Option<Candidate> {
+        match self {
+            Selector::Family { name, index } => candidates
+                .into_iter()
+                .filter(|candidate| candidate.product.family.name() == name)
+                .nth(index - 1),
+            Selector::Serial(serial) => candidates
+                .into_iter()
+                .find(|candidate| candidate.id.serial.as_ref() == Some(&serial)),
+            Selector::BusPath(bus_path) => candidates
+                .into_iter()
+                .find(|candidate| candidate.id.bus_path == bus_path),
+            Selector::Hidraw(path) => candidates
+                .into_iter()
+                .find(|candidate| candidate.nodes.iter().any(|node| node.path == path)),
+        }
+    }
+
+    /// Whether the device with this ID is the selected one among `candidates`, which have to be
+    /// ordered by bus path
+    pub fn selects(&self, id: &DeviceId, candidates: &[Candidate]) -> bool {
+        match self.clone().select(candidates.to_vec()) {
+            Some(candidate) => candidate.id == *id,
+            None => false,
+        }
+    }
+}
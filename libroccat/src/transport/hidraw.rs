@@ -0,0 +1,160 @@
+#[cfg(feature = "async")]
+use super::Reactor;
+use super::Transport;
+use crate::{descriptor, Error};
+use nix::{
+    errno::Errno,
+    fcntl::OFlag,
+    ioctl_readwrite_buf,
+    poll::{poll, PollFd, PollFlags},
+};
+#[cfg(feature = "async")]
+use std::task::{Context, Poll};
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, Read},
+    os::unix::{fs::OpenOptionsExt, io::AsRawFd},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+ioctl_readwrite_buf!(hidiocgfeature, b'H', 0x07, u8);
+ioctl_readwrite_buf!(hidiocsfeature, b'H', 0x06, u8);
+
+/// Talks to a `/dev/hidraw*` node
+///
+/// The node is opened nonblocking, so input reports can be waited for with `poll` or through the
+/// async reactor instead of a blocking read.
+pub struct HidrawTransport {
+    path: PathBuf,
+    file: File,
+}
+
+impl HidrawTransport {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let path = path.as_ref();
+        Ok(Self {
+            path: path.to_path_buf(),
+            file: OpenOptions::new()
+                .read(true)
+                .custom_flags(OFlag::O_NONBLOCK.bits())
+                .open(path)
+                .map_err(|error| match error.kind() {
+                    io::ErrorKind::PermissionDenied => Error::PermissionDenied {
+                        path: path.to_path_buf(),
+                    },
+                    io::ErrorKind::NotFound => Error::DeviceNotFound,
+                    _ => Error::Io(error),
+                })?,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Reads a report that's already waiting, `None` if there's none
+    fn try_read_report(&self, buf: &mut [u8]) -> Result<Option<usize>, Error> {
+        match (&self.file).read(buf) {
+            Ok(len) => Ok(Some(len)),
+            Err(ref error) if error.kind() == io::ErrorKind::WouldBlock => Ok(None),
+            Err(error) => Err(io_error(error)),
+        }
+    }
+
+    /// Waits up to `timeout` milliseconds, or forever if negative, for the node to be readable
+    fn wait_readable(&self, timeout: i32) -> Result<bool, Error> {
+        let mut fds = [PollFd::new(self.file.as_raw_fd(), PollFlags::POLLIN)];
+        match poll(&mut fds, timeout) {
+            Ok(0) | Err(nix::Error::Sys(Errno::EINTR)) => Ok(false),
+            // Also readable on hangup, where `read` reports the disconnect
+            Ok(_) => Ok(true),
+            Err(error) => Err(nix_error(error)),
+        }
+    }
+}
+
+impl Transport for HidrawTransport {
+    fn get_feature_report(&self, buf: &mut [u8]) -> Result<usize, Error> {
+        unsafe { hidiocgfeature(self.file.as_raw_fd(), buf) }
+            .map(|len| len as usize)
+            .map_err(nix_error)
+    }
+
+    fn set_feature_report(&self, buf: &[u8]) -> Result<usize, Error> {
+        let mut buf = buf.to_vec();
+        unsafe { hidiocsfeature(self.file.as_raw_fd(), &mut buf) }
+            .map(|len| len as usize)
+            .map_err(nix_error)
+    }
+
+    fn read_report(&self, buf: &mut [u8]) -> Result<usize, Error> {
+        loop {
+            if let Some(len) = self.try_read_report(buf)? {
+                return Ok(len);
+            }
+            self.wait_readable(-1)?;
+        }
+    }
+
+    fn read_report_timeout(
+        &self,
+        buf: &mut [u8],
+        timeout: Duration,
+    ) -> Result<Option<usize>, Error> {
+        if self.wait_readable(timeout.as_millis().min(i32::MAX as u128) as i32)? {
+            self.try_read_report(buf)
+        } else {
+            Ok(None)
+        }
+    }
+
+    #[cfg(feature = "async")]
+    fn poll_read_report(
+        &self,
+        context: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<Result<usize, Error>> {
+        match self.try_read_report(buf) {
+            Ok(Some(len)) => Poll::Ready(Ok(len)),
+            Ok(None) => {
+                Reactor::get().register(self.file.as_raw_fd(), context.waker());
+                Poll::Pending
+            }
+            Err(error) => Poll::Ready(Err(error)),
+        }
+    }
+
+    fn report_descriptor(&self) -> Result<Option<Vec<u8>>, Error> {
+        match descriptor::read_sysfs(&self.path) {
+            Ok(bytes) => Ok(Some(bytes)),
+            // Not every hidraw node lives in /sys/class/hidraw, e.g. in containers
+            Err(Error::Io(ref error)) if error.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(error),
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl Drop for HidrawTransport {
+    fn drop(&mut self) {
+        Reactor::deregister(self.file.as_raw_fd());
+    }
+}
+
+/// hidraw fails with `ENODEV` or `EIO` once the device is gone
+fn io_error(error: io::Error) -> Error {
+    match error.raw_os_error() {
+        Some(errno) if errno == Errno::ENODEV as i32 || errno == Errno::EIO as i32 => {
+            Error::Disconnected
+        }
+        _ => Error::Io(error),
+    }
+}
+
+fn nix_error(error: nix::Error) -> Error {
+    match error {
+        nix::Error::Sys(errno) => io_error(io::Error::from_raw_os_error(errno as i32)),
+        error => Error::Io(io::Error::other(error)),
+    }
+}
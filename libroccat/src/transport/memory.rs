@@ -0,0 +1,190 @@
+use super::Transport;
+use crate::Error;
+#[cfg(feature = "async")]
+use std::task::{Context, Poll, Waker};
+use std::{
+    collections::{HashMap, VecDeque},
+    io,
+    sync::{Condvar, Mutex},
+    time::{Duration, Instant},
+};
+
+/// An in-memory stand-in for a HID interface
+///
+/// Feature reports are stored by report ID: reading one returns whatever was last stored or
+/// written under that ID. Input reports are queued with `push_input_report` and handed out by
+/// `read_report` in order. After `disconnect` every transfer fails with `Error::Disconnected`.
+#[derive(Default)]
+pub struct MemoryTransport {
+    state: Mutex<MemoryState>,
+    input_available: Condvar,
+}
+
+#[derive(Default)]
+struct MemoryState {
+    feature_reports: HashMap<u8, Vec<u8>>,
+    written_reports: Vec<Vec<u8>>,
+    input_reports: VecDeque<Vec<u8>>,
+    report_descriptor: Option<Vec<u8>>,
+    disconnected: bool,
+    /// Tasks waiting in `poll_read_report`
+    #[cfg(feature = "async")]
+    wakers: Vec<Waker>,
+}
+
+impl MemoryTransport {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Sets the report descriptor returned by `report_descriptor`
+    pub fn set_report_descriptor(&self, descriptor: &[u8]) {
+        self.state.lock().unwrap().report_descriptor = Some(descriptor.to_vec());
+    }
+
+    /// Stores a feature report to be returned by `get_feature_report`
+    pub fn insert_feature_report(&self, report: &[u8]) {
+        let mut state = self.state.lock().unwrap();
+        state.feature_reports.insert(report[0], report.to_vec());
+    }
+
+    /// Gets the feature report currently stored under `report_id`
+    pub fn feature_report(&self, report_id: u8) -> Option<Vec<u8>> {
+        let state = self.state.lock().unwrap();
+        state.feature_reports.get(&report_id).cloned()
+    }
+
+    /// Gets every feature report written so far, oldest first
+    pub fn written_reports(&self) -> Vec<Vec<u8>> {
+        let state = self.state.lock().unwrap();
+        state.written_reports.clone()
+    }
+
+    /// Queues an input report to be returned by `read_report`
+    pub fn push_input_report(&self, report: &[u8]) {
+        let mut state = self.state.lock().unwrap();
+        state.input_reports.push_back(report.to_vec());
+        state.wake();
+        self.input_available.notify_all();
+    }
+
+    /// Makes every following transfer fail as if the device was unplugged
+    pub fn disconnect(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.disconnected = true;
+        state.wake();
+        self.input_available.notify_all();
+    }
+
+    /// Pops the next input report, waiting until `deadline` or forever
+    fn pop_input_report(
+        &self,
+        buf: &mut [u8],
+        deadline: Option<Instant>,
+    ) -> Result<Option<usize>, Error> {
+        let mut state = self.state.lock().unwrap();
+        loop {
+            if let Some(len) = state.try_pop_input_report(buf)? {
+                return Ok(Some(len));
+            }
+            state = match deadline {
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        return Ok(None);
+                    }
+                    self.input_available
+                        .wait_timeout(state, deadline - now)
+                        .unwrap()
+                        .0
+                }
+                None => self.input_available.wait(state).unwrap(),
+            };
+        }
+    }
+}
+
+impl MemoryState {
+    fn try_pop_input_report(&mut self, buf: &mut [u8]) -> Result<Option<usize>, Error> {
+        if self.disconnected {
+            return Err(Error::Disconnected);
+        }
+        Ok(self.input_reports.pop_front().map(|report| {
+            let len = usize::min(buf.len(), report.len());
+            buf[..len].copy_from_slice(&report[..len]);
+            len
+        }))
+    }
+
+    #[cfg(feature = "async")]
+    fn wake(&mut self) {
+        self.wakers.drain(..).for_each(Waker::wake);
+    }
+
+    #[cfg(not(feature = "async"))]
+    fn wake(&mut self) {}
+}
+
+impl Transport for MemoryTransport {
+    fn get_feature_report(&self, buf: &mut [u8]) -> Result<usize, Error> {
+        let state = self.state.lock().unwrap();
+        if state.disconnected {
+            return Err(Error::Disconnected);
+        }
+        let report = state.feature_reports.get(&buf[0]).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("No feature report with ID {:#04x}", buf[0]),
+            )
+        })?;
+        let len = usize::min(buf.len(), report.len());
+        buf[..len].copy_from_slice(&report[..len]);
+        Ok(len)
+    }
+
+    fn set_feature_report(&self, buf: &[u8]) -> Result<usize, Error> {
+        let mut state = self.state.lock().unwrap();
+        if state.disconnected {
+            return Err(Error::Disconnected);
+        }
+        state.feature_reports.insert(buf[0], buf.to_vec());
+        state.written_reports.push(buf.to_vec());
+        Ok(buf.len())
+    }
+
+    fn read_report(&self, buf: &mut [u8]) -> Result<usize, Error> {
+        Ok(self.pop_input_report(buf, None)?.unwrap_or_default())
+    }
+
+    fn read_report_timeout(
+        &self,
+        buf: &mut [u8],
+        timeout: Duration,
+    ) -> Result<Option<usize>, Error> {
+        self.pop_input_report(buf, Some(Instant::now() + timeout))
+    }
+
+    #[cfg(feature = "async")]
+    fn poll_read_report(
+        &self,
+        context: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<Result<usize, Error>> {
+        let mut state = self.state.lock().unwrap();
+        match state.try_pop_input_report(buf) {
+            Ok(Some(len)) => Poll::Ready(Ok(len)),
+            Ok(None) => {
+                let waker = context.waker();
+                if !state.wakers.iter().any(|waiting| waiting.will_wake(waker)) {
+                    state.wakers.push(waker.clone());
+                }
+                Poll::Pending
+            }
+            Err(error) => Poll::Ready(Err(error)),
+        }
+    }
+
+    fn report_descriptor(&self) -> Result<Option<Vec<u8>>, Error> {
+        Ok(self.state.lock().unwrap().report_descriptor.clone())
+    }
+}
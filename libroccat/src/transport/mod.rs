@@ -0,0 +1,131 @@
+mod hidraw;
+mod memory;
+#[cfg(feature = "async")]
+mod reactor;
+mod retry;
+mod wait;
+
+#[cfg(feature = "async")]
+pub(crate) use self::reactor::Reactor;
+pub use self::{hidraw::HidrawTransport, memory::MemoryTransport, retry::*, wait::*};
+use crate::Error;
+#[cfg(feature = "async")]
+use std::{
+    io,
+    task::{Context, Poll},
+};
+use std::{sync::Arc, time::Duration};
+
+/// A single HID interface of a device
+///
+/// Report buffers always start with the report ID, the same way hidraw expects them.
+pub trait Transport: Send + Sync {
+    /// Gets the feature report whose ID is in `buf[0]`, returning the number of bytes read
+    fn get_feature_report(&self, buf: &mut [u8]) -> Result<usize, Error>;
+
+    /// Sends a feature report, returning the number of bytes written
+    fn set_feature_report(&self, buf: &[u8]) -> Result<usize, Error>;
+
+    /// Blocks until an input report arrives, returning the number of bytes read
+    fn read_report(&self, buf: &mut [u8]) -> Result<usize, Error>;
+
+    /// Waits up to `timeout` for an input report, `None` if none arrived
+    ///
+    /// Event readers rely on this returning in time to notice that they should stop.
+    fn read_report_timeout(
+        &self,
+        buf: &mut [u8],
+        timeout: Duration,
+    ) -> Result<Option<usize>, Error>;
+
+    /// Reads an input report if one is waiting, otherwise wakes the task once one arrives
+    ///
+    /// Transports that can only block don't support this and fail right away.
+    #[cfg(feature = "async")]
+    fn poll_read_report(
+        &self,
+        _context: &mut Context<'_>,
+        _buf: &mut [u8],
+    ) -> Poll<Result<usize, Error>> {
+        Poll::Ready(Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "Transport can't wait for input reports without blocking",
+        )
+        .into()))
+    }
+
+    /// Gets the raw HID report descriptor, `None` if the interface can't provide one
+    fn report_descriptor(&self) -> Result<Option<Vec<u8>>, Error> {
+        Ok(None)
+    }
+}
+
+impl<T: Transport + ?Sized> Transport for Box<T> {
+    fn get_feature_report(&self, buf: &mut [u8]) -> Result<usize, Error> {
+        (**self).get_feature_report(buf)
+    }
+
+    fn set_feature_report(&self, buf: &[u8]) -> Result<usize, Error> {
+        (**self).set_feature_report(buf)
+    }
+
+    fn read_report(&self, buf: &mut [u8]) -> Result<usize, Error> {
+        (**self).read_report(buf)
+    }
+
+    fn read_report_timeout(
+        &self,
+        buf: &mut [u8],
+        timeout: Duration,
+    ) -> Result<Option<usize>, Error> {
+        (**self).read_report_timeout(buf, timeout)
+    }
+
+    #[cfg(feature = "async")]
+    fn poll_read_report(
+        &self,
+        context: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<Result<usize, Error>> {
+        (**self).poll_read_report(context, buf)
+    }
+
+    fn report_descriptor(&self) -> Result<Option<Vec<u8>>, Error> {
+        (**self).report_descriptor()
+    }
+}
+
+impl<T: Transport + ?Sized> Transport for Arc<T> {
+    fn get_feature_report(&self, buf: &mut [u8]) -> Result<usize, Error> {
+        (**self).get_feature_report(buf)
+    }
+
+    fn set_feature_report(&self, buf: &[u8]) -> Result<usize, Error> {
+        (**self).set_feature_report(buf)
+    }
+
+    fn read_report(&self, buf: &mut [u8]) -> Result<usize, Error> {
+        (**self).read_report(buf)
+    }
+
+    fn read_report_timeout(
+        &self,
+        buf: &mut [u8],
+        timeout: Duration,
+    ) -> Result<Option<usize>, Error> {
+        (**self).read_report_timeout(buf, timeout)
+    }
+
+    #[cfg(feature = "async")]
+    fn poll_read_report(
+        &self,
+        context: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<Result<usize, Error>> {
+        (**self).poll_read_report(context, buf)
+    }
+
+    fn report_descriptor(&self) -> Result<Option<Vec<u8>>, Error> {
+        (**self).report_descriptor()
+    }
+}
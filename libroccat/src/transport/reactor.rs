@@ -0,0 +1,109 @@
+use nix::{
+    errno::Errno,
+    poll::{poll, PollFd, PollFlags},
+};
+use std::{
+    collections::HashMap,
+    io::{Read, Write},
+    os::unix::{
+        io::{AsRawFd, RawFd},
+        net::UnixStream,
+    },
+    sync::{Mutex, OnceLock},
+    task::Waker,
+    thread,
+};
+
+/// Wakes tasks once the file descriptors they wait on become readable
+///
+/// One thread polls every registered descriptor for all devices of the process, so waiting for
+/// input reports doesn't take a thread per device or a polling interval. Registrations are
+/// one-shot: a task registers again whenever a nonblocking read comes up empty.
+pub(crate) struct Reactor {
+    waiting: Mutex<HashMap<RawFd, Vec<Waker>>>,
+    /// Interrupts the poll so it picks up new registrations
+    notify: UnixStream,
+}
+
+static REACTOR: OnceLock<Reactor> = OnceLock::new();
+
+impl Reactor {
+    /// Gets the process-wide reactor, starting its thread on first use
+    pub(crate) fn get() -> &'static Reactor {
+        REACTOR.get_or_init(|| {
+            let (notify, notified) = UnixStream::pair().expect("Can't create the reactor socket");
+            notify
+                .set_nonblocking(true)
+                .and_then(|()| notified.set_nonblocking(true))
+                .expect("Can't make the reactor socket nonblocking");
+            thread::Builder::new()
+                .name("libroccat-reactor".to_string())
+                .spawn(move || Reactor::get().run(notified))
+                .expect("Can't start the reactor thread");
+            Reactor {
+                waiting: Mutex::new(HashMap::new()),
+                notify,
+            }
+        })
+    }
+
+    /// Wakes `waker` once `fd` is readable or hung up
+    pub(crate) fn register(&self, fd: RawFd, waker: &Waker) {
+        let mut waiting = self.waiting.lock().unwrap();
+        let wakers = waiting.entry(fd).or_default();
+        if !wakers.iter().any(|registered| registered.will_wake(waker)) {
+            wakers.push(waker.clone());
+        }
+        drop(waiting);
+        self.wake_up();
+    }
+
+    /// Forgets `fd` before it's closed, so a reused descriptor doesn't wake stale tasks
+    pub(crate) fn deregister(fd: RawFd) {
+        // Nothing can be registered before the reactor is started
+        if let Some(reactor) = REACTOR.get() {
+            if reactor.waiting.lock().unwrap().remove(&fd).is_some() {
+                reactor.wake_up();
+            }
+        }
+    }
+
+    fn wake_up(&self) {
+        // A full socket already has a wake-up pending
+        let _ = (&self.notify).write(&[1]);
+    }
+
+    fn run(&self, mut notified: UnixStream) {
+        loop {
+            let registered: Vec<RawFd> = self.waiting.lock().unwrap().keys().cloned().collect();
+            let mut fds = vec![PollFd::new(notified.as_raw_fd(), PollFlags::POLLIN)];
+            fds.extend(
+                registered
+                    .iter()
+                    .map(|&fd| PollFd::new(fd, PollFlags::POLLIN)),
+            );
+            match poll(&mut fds, -1) {
+                Ok(_) | Err(nix::Error::Sys(Errno::EINTR)) => (),
+                Err(error) => panic!("Polling for input reports failed: {}", error),
+            }
+
+            let mut buf = [0u8; 64];
+            while let Ok(len) = notified.read(&mut buf) {
+                if len == 0 {
+                    break;
+                }
+            }
+
+            let mut ready = Vec::new();
+            let mut waiting = self.waiting.lock().unwrap();
+            for (fd, polled) in registered.iter().zip(&fds[1..]) {
+                // Hangups and errors count too, the read reports them
+                if matches!(polled.revents(), Some(revents) if !revents.is_empty()) {
+                    ready.extend(waiting.remove(fd).unwrap_or_default());
+                }
+            }
+            drop(waiting);
+            ready.into_iter().for_each(Waker::wake);
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use crate::Error;
+use log::debug;
+use std::{
+    fmt,
+    sync::RwLock,
+    thread,
+    time::{Duration, Instant},
+};
+
+static DEFAULT_RETRY_POLICY: RwLock<RetryPolicy> = RwLock::new(RetryPolicy {
+    attempts: 10,
+    delay: Duration::from_millis(10),
+    deadline: Some(Duration::from_secs(1)),
+});
+
+/// Gets the policy used by report reads and writes that don't specify one
+pub fn default_retry_policy() -> RetryPolicy {
+    *DEFAULT_RETRY_POLICY.read().unwrap()
+}
+
+/// Sets the policy used by report reads and writes that don't specify one
+pub fn set_default_retry_policy(policy: RetryPolicy) {
+    *DEFAULT_RETRY_POLICY.write().unwrap() = policy;
+}
+
+/// How often and for how long a failing report transfer is retried
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct RetryPolicy {
+    /// Number of attempts including the first one
+    pub attempts: u32,
+    /// Time to wait between attempts
+    pub delay: Duration,
+    /// Time after the first attempt from which on no further attempts are started
+    pub deadline: Option<Duration>,
+}
+
+impl RetryPolicy {
+    pub fn new(attempts: u32, delay: Duration, deadline: Option<Duration>) -> Self {
+        Self {
+            attempts,
+            delay,
+            deadline,
+        }
+    }
+
+    /// A policy that gives up after the first failure
+    pub fn once() -> Self {
+        Self::new(1, Duration::from_millis(0), None)
+    }
+
+    /// Runs `transfer` until it succeeds, the policy is exhausted or the error is permanent,
+    /// returning the last error
+    pub fn retry<T, F>(&self, mut transfer: F) -> Result<T, Error>
+    where
+        F: FnMut() -> Result<T, Error>,
+    {
+        let start = Instant::now();
+        let mut attempt = 1;
+        loop {
+            match transfer() {
+                Ok(value) => return Ok(value),
+                Err(error) => {
+                    let out_of_time = self
+                        .deadline
+                        .is_some_and(|deadline| start.elapsed() + self.delay >= deadline);
+                    if attempt >= self.attempts || out_of_time || error.is_permanent() {
+                        return Err(error);
+                    }
+
+                    debug!("Retrying report transfer ({}): {}", attempt, error);
+                    attempt += 1;
+                    thread::sleep(self.delay);
+                }
+            }
+        }
+    }
+}
+
+/// A report whose bytesum didn't match its contents
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ChecksumMismatch {
+    pub report_id: u8,
+    /// The bytesum calculated from the report contents
+    pub expected: u16,
+    /// The bytesum the device sent
+    pub actual: u16,
+}
+
+impl fmt::Display for ChecksumMismatch {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            fmt,
+            "Checksum mismatch in report {:#04x}: expected {:#06x}, got {:#06x}",
+            self.report_id, self.expected, self.actual
+        )
+    }
+}
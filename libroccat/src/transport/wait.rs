@@ -0,0 +1,80 @@
+use crate::Error;
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, RwLock,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+static DEFAULT_WRITE_CHECK_TIMEOUT: RwLock<Option<Duration>> =
+    RwLock::new(Some(Duration::from_secs(5)));
+
+/// Time between two polls of a device's write check status
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Gets the timeout used by write checks that don't specify one
+pub fn default_write_check_timeout() -> Option<Duration> {
+    *DEFAULT_WRITE_CHECK_TIMEOUT.read().unwrap()
+}
+
+/// Sets the timeout used by write checks that don't specify one, `None` waits forever
+pub fn set_default_write_check_timeout(timeout: Option<Duration>) {
+    *DEFAULT_WRITE_CHECK_TIMEOUT.write().unwrap() = timeout;
+}
+
+/// Cancels write checks from another thread
+///
+/// Cancelling aborts every wait that was started with a clone of this token before the call,
+/// waits started afterwards are unaffected.
+#[derive(Clone, Debug, Default)]
+pub struct CancelToken {
+    generation: Arc<AtomicUsize>,
+}
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Aborts all waits currently using this token
+    pub fn cancel(&self) {
+        self.generation.fetch_add(1, Ordering::SeqCst);
+    }
+
+    fn generation(&self) -> usize {
+        self.generation.load(Ordering::SeqCst)
+    }
+}
+
+/// Polls `done` until it returns true, the timeout has passed or `cancel` was cancelled
+///
+/// Fails with `Error::DeviceBusy` on timeout and with `Error::Cancelled` on cancellation.
+pub(crate) fn poll_until<F>(
+    timeout: Option<Duration>,
+    cancel: Option<&CancelToken>,
+    mut done: F,
+) -> Result<(), Error>
+where
+    F: FnMut() -> Result<bool, Error>,
+{
+    let start = Instant::now();
+    let generation = cancel.map(CancelToken::generation);
+    loop {
+        thread::sleep(POLL_INTERVAL);
+
+        if cancel.map(CancelToken::generation) != generation {
+            return Err(Error::Cancelled);
+        }
+
+        if done()? {
+            return Ok(());
+        }
+
+        let elapsed = start.elapsed();
+        if timeout.is_some_and(|timeout| elapsed >= timeout) {
+            return Err(Error::DeviceBusy { elapsed });
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use crate::{
+    device::{interface::HidrawNode, Device, DeviceId},
+    registry::{self, Product},
+    Error,
+};
+
+/// A supported device found through udev that hasn't been opened yet
+#[derive(Clone, Debug, PartialEq)]
+pub struct Candidate {
+    pub id: DeviceId,
+    pub product: Product,
+    pub nodes: Vec<HidrawNode>,
+}
+
+impl Candidate {
+    pub(crate) fn open(self) -> Result<Device, Error> {
+        let mut device = self.product.open(&self.nodes)?;
+        device.set_id(self.id);
+        Ok(device)
+    }
+}
+
+/// Finds all devices from known vendors, ordered by bus path
+///
+/// Unsupported products of known vendors show up as `Error::UnsupportedDevice`.
+pub(crate) fn candidates(
+    context: &libudev::Context,
+) -> Result<Vec<Result<Candidate, Error>>, Error> {
+    let mut enumerator = libudev::Enumerator::new(context)?;
+    enumerator.match_subsystem("usb")?;
+    enumerator.match_property("DEVTYPE", "usb_device")?;
+    let mut candidates: Vec<_> = enumerator
+        .scan_devices()?
+        .filter_map(|parent| probe(context, &parent))
+        .collect();
+    candidates.sort_by(|a, b| match (a, b) {
+        (Ok(a), Ok(b)) => a.id.bus_path.cmp(&b.id.bus_path),
+        (a, b) => a.is_ok().cmp(&b.is_ok()),
+    });
+    Ok(candidates)
+}
+
+/// Finds the supported devices, ordered by bus path, along with the first one that couldn't be
+/// looked at
+pub(crate) fn supported(
+    context: &libudev::Context,
+) -> Result<(Vec<Candidate>, Option<Error>), Error> {
+    let mut first_error = None;
+    let candidates = candidates(context)?
+        .into_iter()
+        .filter_map(|candidate| match candidate {
+            Ok(candidate) => Some(candidate),
+            Err(Error::UnsupportedDevice { .. }) => None,
+            Err(error) => {
+                first_error.get_or_insert(error);
+                None
+            }
+        })
+        .collect();
+    Ok((candidates, first_error))
+}
+
+/// Finds the device with this ID again, e.g. after it was unplugged and plugged back in
+///
+/// Devices with a serial number are matched by it, so they may have moved to another port.
+pub(crate) fn find(id: &DeviceId) -> Result<Candidate, Error> {
+    let context = libudev::Context::new()?;
+    candidates(&context)?
+        .into_iter()
+        .filter_map(|candidate| candidate.ok())
+        .find(|candidate| {
+            (candidate.id.vendor_id, candidate.id.product_id) == (id.vendor_id, id.product_id)
+                && match id.serial {
+                    Some(ref serial) => candidate.id.serial.as_ref() == Some(serial),
+                    None => candidate.id.bus_path == id.bus_path,
+                }
+        })
+        .ok_or(Error::DeviceNotFound)
+}
+
+/// Looks at a udev `usb_device`, `None` if it isn't from a vendor in the registry
+pub(crate) fn probe(
+    context: &libudev::Context,
+    parent: &libudev::Device,
+) -> Option<Result<Candidate, Error>> {
+    let id = DeviceId {
+        bus_path: parent.sysname().to_string_lossy().into_owned(),
+        serial: parent
+            .attribute_value("serial")
+            .map(|serial| serial.to_string_lossy().trim().to_string()),
+        vendor_id: usb_id(parent, "idVendor")?,
+        product_id: usb_id(parent, "idProduct")?,
+    };
+    if !registry::is_known_vendor(id.vendor_id) {
+        return None;
+    }
+
+    let probe = || -> Result<Candidate, Error> {
+        let product = registry::find_product(id.vendor_id, id.product_id).ok_or_else(|| {
+            Error::UnsupportedDevice {
+                product_id: format!("{:04x}", id.product_id),
+            }
+        })?;
+
+        let mut enumerator = libudev::Enumerator::new(context)?;
+        enumerator.match_subsystem("hidraw")?;
+        enumerator.match_parent(parent)?;
+        let nodes = enumerator
+            .scan_devices()?
+            .filter_map(|device| HidrawNode::from_udev(&device))
+            .collect();
+        Ok(Candidate {
+            id: id.clone(),
+            product,
+            nodes,
+        })
+    };
+    Some(probe())
+}
+
+/// Parses a hexadecimal USB ID attribute like `idVendor`
+fn usb_id(device: &libudev::Device, attribute: &str) -> Option<u16> {
+    let value = device.attribute_value(attribute)?.to_str()?;
+    u16::from_str_radix(value.trim(), 16).ok()
+}
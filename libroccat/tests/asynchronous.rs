@@ -0,0 +1,140 @@
+#![cfg(feature = "async")]
+
+use futures::{executor::block_on, future, StreamExt};
+use libroccat::{
+    asynchronous::AsyncDevice,
+    device::ryosmkfx::{Emulator, Macro},
+    transport::{HidrawTransport, Transport},
+    Error,
+};
+use nix::{sys::stat::Mode, unistd::mkfifo};
+use std::{env, fs, io::Write, process, thread, time::Duration};
+
+#[test]
+fn requests() {
+    let emulator = Emulator::new();
+    let device = AsyncDevice::new(emulator.open().unwrap());
+
+    block_on(async {
+        device.set_profile(4).await.unwrap();
+        assert_eq!(device.get_profile().await.unwrap(), 4);
+        assert_eq!(device.get_info().await.unwrap().firmware_version, 0x6f);
+
+        let mut lights = device.get_lights(2).await.unwrap();
+        lights.profile = 2;
+        lights.brightness = 1;
+        device.set_lights(lights).await.unwrap();
+        assert_eq!(device.get_lights(2).await.unwrap().brightness, 1);
+    });
+    assert_eq!(emulator.profile(), 4);
+}
+
+#[test]
+fn key_and_macro_requests() {
+    let emulator = Emulator::new();
+    let device = AsyncDevice::new(emulator.open().unwrap());
+
+    block_on(async {
+        let mut keys = device.get_keys_primary(3).await.unwrap();
+        assert_eq!(keys.profile_index, 2);
+        keys.keys[0] = 0x05;
+        device.set_keys_primary(keys).await.unwrap();
+        assert_eq!(device.get_keys_primary(3).await.unwrap().keys[0], 0x05);
+        assert!(device.get_keys_easyzone(6).await.is_err());
+
+        let macro_: Macro = "name Test\ndown A\nup A".parse().unwrap();
+        device.set_macro(2, 3, macro_.clone()).await.unwrap();
+        assert_eq!(device.get_macro(2, 3).await.unwrap(), macro_);
+
+        device.set_custom_lights_active(true).await.unwrap();
+        assert!(device.get_custom_lights_active().await.unwrap());
+        device.get_custom_lights().await.unwrap();
+    });
+}
+
+#[test]
+fn event_stream() {
+    let emulator = Emulator::new();
+    let device = AsyncDevice::new(emulator.open().unwrap());
+    let mut events = device.events();
+
+    let injector = emulator.clone();
+    std::thread::spawn(move || {
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        injector.inject_key(0x10, true).unwrap();
+        injector.inject_key(0x10, false).unwrap();
+    });
+
+    block_on(async {
+        let pressed = events.next().await.unwrap().unwrap();
+        assert_eq!(pressed.sdk_index(), 0x10);
+        let released = events.next().await.unwrap().unwrap();
+        assert_eq!(released.sdk_index(), 0x10);
+    });
+
+    drop(device);
+    assert!(block_on(events.next()).is_none());
+}
+
+#[test]
+fn stream_feeds_subscribers() {
+    let emulator = Emulator::new();
+    let device = AsyncDevice::new(emulator.open().unwrap());
+    let subscriber = device.device().subscribe();
+    let mut events = device.events();
+
+    emulator.inject_key(0x10, true).unwrap();
+    block_on(events.next()).unwrap().unwrap();
+    // Only read through the stream, the reader thread has stopped
+    let event = subscriber.recv_timeout(Duration::from_millis(0)).unwrap();
+    assert_eq!(event.unwrap().sdk_index(), 0x10);
+}
+
+#[test]
+fn stream_ends_when_lost() {
+    let emulator = Emulator::new();
+    let device = AsyncDevice::new(emulator.open().unwrap());
+    let mut events = device.events();
+
+    emulator.unplug();
+    block_on(async {
+        match events.next().await {
+            Some(Err(error)) => assert!(matches!(*error, Error::Disconnected)),
+            event => panic!("Expected the device to be lost, got {:?}", event),
+        }
+        // There's no way to reopen it
+        assert!(events.next().await.is_none());
+    });
+}
+
+#[test]
+fn hidraw_wakes_when_readable() {
+    // A FIFO stands in for the hidraw node, input reports are what's written to it
+    let path = env::temp_dir().join(format!("libroccat-hidraw-{}", process::id()));
+    let _ = fs::remove_file(&path);
+    mkfifo(&path, Mode::S_IRUSR | Mode::S_IWUSR).unwrap();
+    let transport = HidrawTransport::open(&path).unwrap();
+    let mut input = fs::OpenOptions::new().write(true).open(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    let mut buf = [0u8; 5];
+    let writer = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(20));
+        input.write_all(&[0x03, 0x00, 0xfb, 0x10, 0x01]).unwrap();
+        input
+    });
+    let len = block_on(future::poll_fn(|context| {
+        transport.poll_read_report(context, &mut buf)
+    }))
+    .unwrap();
+    assert_eq!(&buf[..len], &[0x03, 0x00, 0xfb, 0x10, 0x01]);
+
+    // Nothing is waiting, so reading doesn't block
+    let _input = writer.join().unwrap();
+    assert_eq!(
+        transport
+            .read_report_timeout(&mut buf, Duration::from_millis(0))
+            .unwrap(),
+        None
+    );
+}
@@ -0,0 +1,145 @@
+mod common;
+
+use common::event;
+use libroccat::{
+    bindings::{Action, Bindings, EventPattern},
+    device::ryosmkfx::EventType,
+    Error,
+};
+use std::{
+    io::Read,
+    os::unix::net::UnixListener,
+    path::PathBuf,
+    process,
+    time::{Duration, Instant},
+};
+
+const TABLE: &str = "
+# Comments and empty lines are ignored
+
+macro:3        exec    echo \"$ROCCAT_EVENT $ROCCAT_DATA\"
+quicklaunch    fifo    /tmp/roccat.fifo
+profile:0x02   socket  /tmp/roccat.sock profile {data}
+";
+
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("libroccat-{}-{}", process::id(), name))
+}
+
+#[test]
+fn parse() {
+    let bindings: Bindings = TABLE.parse().unwrap();
+    assert_eq!(bindings.bindings.len(), 3);
+    assert_eq!(
+        bindings.bindings[0].pattern,
+        EventPattern {
+            type_: EventType::Macro,
+            data: Some(3),
+        }
+    );
+    assert_eq!(
+        bindings.bindings[1].action,
+        Action::Fifo {
+            path: "/tmp/roccat.fifo".into(),
+            message: "{event} {data}".to_string(),
+        }
+    );
+    assert_eq!(
+        bindings.bindings[2].action,
+        Action::Socket {
+            path: "/tmp/roccat.sock".into(),
+            message: "profile {data}".to_string(),
+        }
+    );
+
+    let macro_3 = event(EventType::Macro, 3);
+    assert_eq!(bindings.matching(&macro_3).count(), 1);
+    assert_eq!(bindings.matching(&event(EventType::Macro, 4)).count(), 0);
+    assert_eq!(
+        bindings.matching(&event(EventType::Quicklaunch, 9)).count(),
+        1
+    );
+    assert_eq!(bindings.matching(&event(EventType::Profile, 2)).count(), 1);
+}
+
+#[test]
+fn parse_errors() {
+    for (table, line) in &[
+        ("bogus exec true", 1),
+        ("\nmacro:300 exec true", 2),
+        ("macro teleport somewhere", 1),
+        ("macro exec", 1),
+        ("macro", 1),
+    ] {
+        match table.parse::<Bindings>() {
+            Err(Error::InvalidBinding { line: actual, .. }) => assert_eq!(actual, *line),
+            other => panic!("{:?} parsed as {:?}", table, other),
+        }
+    }
+}
+
+#[test]
+fn socket_and_fifo() {
+    let socket_path = temp_path("socket");
+    let listener = UnixListener::bind(&socket_path).unwrap();
+    let action = Action::Socket {
+        path: socket_path.clone(),
+        message: "{event} {data}".to_string(),
+    };
+    action.run(&event(EventType::TimerStart, 7)).unwrap();
+    let mut message = String::new();
+    listener
+        .accept()
+        .unwrap()
+        .0
+        .read_to_string(&mut message)
+        .unwrap();
+    assert_eq!(message, "timer_start 7\n");
+
+    // A listener that stops accepting fails the action once its backlog is full
+    let start = Instant::now();
+    let failed = (0..10_000).any(|_| action.run(&event(EventType::TimerStart, 7)).is_err());
+    assert!(failed);
+    assert!(start.elapsed() < Duration::from_secs(5));
+    drop(listener);
+    std::fs::remove_file(&socket_path).unwrap();
+
+    // Without a reader, writing to a FIFO is skipped instead of blocking
+    let fifo_path = temp_path("fifo");
+    process::Command::new("mkfifo")
+        .arg(&fifo_path)
+        .status()
+        .unwrap();
+    let action = Action::Fifo {
+        path: fifo_path.clone(),
+        message: "{event}".to_string(),
+    };
+    action.run(&event(EventType::TimerStop, 0)).unwrap();
+    std::fs::remove_file(&fifo_path).unwrap();
+}
+
+#[test]
+fn exec() {
+    let output = temp_path("exec");
+    let table = format!(
+        "multimedia exec echo \"$ROCCAT_EVENT $ROCCAT_DATA\" > {}",
+        output.display()
+    );
+    let bindings: Bindings = table.parse().unwrap();
+    assert_eq!(bindings.handle(&event(EventType::Multimedia, 5)), 1);
+
+    let start = Instant::now();
+    loop {
+        if let Ok(contents) = std::fs::read_to_string(&output) {
+            if contents == "multimedia 5\n" {
+                break;
+            }
+        }
+        assert!(
+            start.elapsed() < Duration::from_secs(5),
+            "Command didn't run"
+        );
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    std::fs::remove_file(&output).unwrap();
+}
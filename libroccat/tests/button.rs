@@ -0,0 +1,203 @@
+use libroccat::{
+    device::{
+        button::*,
+        ryosmkfx::*,
+        tyon::{self, Tyon, BUTTON_COUNT},
+    },
+    transport::MemoryTransport,
+    Error,
+};
+
+#[test]
+fn codes_round_trip() {
+    for &family in &[ButtonFamily::Keyboard, ButtonFamily::Mouse] {
+        for code in 0..=0xff {
+            if let Ok(button_type) = ButtonType::from_code(family, code) {
+                assert_eq!(button_type.code(family).unwrap(), code, "{:?}", button_type);
+            }
+        }
+    }
+
+    let keyboard = ButtonFamily::Keyboard;
+    assert_eq!(
+        ButtonType::from_code(keyboard, 0x04).unwrap(),
+        ButtonType::Key(0x04)
+    );
+    assert_eq!(
+        ButtonType::from_code(keyboard, 0xd2).unwrap(),
+        ButtonType::Multimedia(MultimediaKey::VolumeDown)
+    );
+    assert_eq!(
+        ButtonType::from_code(keyboard, 0xdc).unwrap(),
+        ButtonType::MacroSlot(10)
+    );
+    assert_eq!(
+        ButtonType::from_code(ButtonFamily::Mouse, 0x04).unwrap(),
+        ButtonType::DoubleClick
+    );
+    match ButtonType::from_code(ButtonFamily::Mouse, 0xff) {
+        Err(Error::InvalidValue { type_name, value }) => {
+            assert_eq!((type_name, value), ("ButtonType", 0xff))
+        }
+        result => panic!("Unexpected {:?}", result),
+    }
+}
+
+#[test]
+fn defaults_decode() {
+    let tables: [&[ButtonConfig]; 4] = [
+        &DEFAULT_KEYS_FUNCTION,
+        &DEFAULT_KEYS_MACRO,
+        &DEFAULT_KEYS_THUMBSTER,
+        &DEFAULT_KEYS_EASYZONE,
+    ];
+    let mut unknown = Vec::new();
+    for config in tables.iter().flat_map(|table| table.iter()) {
+        let button_type = config.button_type(ButtonFamily::Keyboard).unwrap();
+        assert_eq!(
+            ButtonConfig::from_type(ButtonFamily::Keyboard, button_type).unwrap(),
+            *config
+        );
+        assert_eq!(config.modifiers().unwrap(), ButtonModifiers::empty());
+        assert!(config.is_confirmed(ButtonFamily::Keyboard), "{:?}", config);
+        if let ButtonType::Unknown(code) = button_type {
+            unknown.push(code);
+        }
+    }
+    unknown.sort_unstable();
+    assert_eq!(unknown, [0x82, 0xa3, 0xa7, 0xc0, 0xc1, 0xc2, 0xc3]);
+
+    // The function keys' Fn layer isn't taken as HID usages
+    assert!(!is_key_usage(0x82));
+    assert!(!is_key_usage(0xa3));
+    assert_eq!(
+        DEFAULT_KEYS_THUMBSTER[0]
+            .button_type(ButtonFamily::Keyboard)
+            .unwrap(),
+        ButtonType::ProfileDown
+    );
+}
+
+#[test]
+fn confirmed_codes() {
+    let keyboard = ButtonFamily::Keyboard;
+    for &button_type in &[
+        ButtonType::Key(0x04),
+        ButtonType::Multimedia(MultimediaKey::Mute),
+        ButtonType::MacroSlot(3),
+        ButtonType::Easyshift,
+        ButtonType::Unknown(0xc1),
+    ] {
+        assert!(button_type.is_confirmed(keyboard), "{:?}", button_type);
+    }
+    // Only roccat-tools knows these
+    for &button_type in &[
+        ButtonType::Shortcut,
+        ButtonType::Timer,
+        ButtonType::ProfileCycle,
+        ButtonType::EasyshiftLock,
+    ] {
+        assert!(!button_type.is_confirmed(keyboard), "{:?}", button_type);
+    }
+    assert!(!ButtonType::Click.is_confirmed(ButtonFamily::Mouse));
+    assert!(!ButtonConfig::new(0xff, 0x00, 0x00).is_confirmed(keyboard));
+}
+
+#[test]
+fn validation() {
+    let keyboard = ButtonFamily::Keyboard;
+    let mouse = ButtonFamily::Mouse;
+
+    let shortcut =
+        ButtonConfig::shortcut(mouse, ButtonModifiers::CTRL | ButtonModifiers::ALT, 0x06).unwrap();
+    assert_eq!(shortcut, ButtonConfig::new(0x05, 0x06, 0x06));
+    assert_eq!(shortcut.button_type(mouse).unwrap(), ButtonType::Shortcut);
+    assert_eq!(
+        shortcut.modifiers().unwrap(),
+        ButtonModifiers::CTRL | ButtonModifiers::ALT
+    );
+    assert!(ButtonConfig::new(0x05, 0x10, 0x06).modifiers().is_err());
+    #[allow(deprecated)]
+    let ctrl = ButtonModifiers::from(ButtonModifier::Ctrl);
+    assert_eq!(ctrl, ButtonModifiers::CTRL);
+    assert_eq!(
+        ButtonConfig::shortcut(keyboard, ButtonModifiers::CTRL, 0x06).unwrap(),
+        ButtonConfig::new(0xbf, 0x02, 0x06)
+    );
+
+    assert_eq!(
+        ButtonConfig::from_type(keyboard, ButtonType::ProfileUp).unwrap(),
+        ButtonConfig::new(0xb0, 0x00, 0x00)
+    );
+    assert_eq!(
+        ButtonConfig::from_type(mouse, ButtonType::ProfileUp).unwrap(),
+        ButtonConfig::new(0x11, 0x00, 0x00)
+    );
+    assert_eq!(
+        ButtonConfig::from_type(keyboard, ButtonType::Unknown(0xc1)).unwrap(),
+        ButtonConfig::new(0xc1, 0x00, 0x00)
+    );
+
+    for &(family, button_type) in &[
+        (mouse, ButtonType::Key(0x04)),
+        (mouse, ButtonType::MacroSlot(1)),
+        (mouse, ButtonType::Unknown(0xc1)),
+        (keyboard, ButtonType::Macro),
+        (keyboard, ButtonType::Click),
+        (keyboard, ButtonType::Key(0xf1)),
+        (keyboard, ButtonType::Unknown(0xff)),
+        (keyboard, ButtonType::Shortcut),
+    ] {
+        match ButtonConfig::from_type(family, button_type) {
+            Err(Error::UnsupportedButtonType {
+                button_type: unsupported,
+                family: unsupported_family,
+            }) => assert_eq!((unsupported, unsupported_family), (button_type, family)),
+            result => panic!("Unexpected {:?}", result),
+        }
+    }
+    assert!(ButtonConfig::from_type(keyboard, ButtonType::MacroSlot(11)).is_err());
+}
+
+#[test]
+fn tyon_buttons() {
+    let mouse = ButtonFamily::Mouse;
+    let disabled = ButtonConfig::from_type(mouse, ButtonType::Disabled).unwrap();
+    let primary = MemoryTransport::new();
+    // Selecting a profile writes its index into the `Control` report, which reads back as the
+    // status, so profile 2 has to be used: index 1 is `ControlStatus::Ok`
+    primary.insert_feature_report(&[0x04, 0x01, 0x00]);
+    let mut report = vec![0x07, 0x63, 0x01];
+    for _ in 0..BUTTON_COUNT {
+        report.extend_from_slice(&[disabled.type_, 0x00, 0x00]);
+    }
+    primary.insert_feature_report(&report);
+    let tyon = Tyon::from_transports(vec![primary]).unwrap();
+
+    let mut buttons = tyon.get_buttons(2).unwrap();
+    assert_eq!(buttons, [disabled; BUTTON_COUNT]);
+    buttons[0] = ButtonConfig::shortcut(mouse, ButtonModifiers::CTRL, 0x06).unwrap();
+    buttons[1] = ButtonConfig::from_type(mouse, ButtonType::CpiUp).unwrap();
+    tyon.set_buttons(2, &buttons).unwrap();
+    assert_eq!(tyon.get_buttons(2).unwrap(), buttons);
+
+    // Keyboard codes aren't written to mice
+    let interface = tyon.get_interface(tyon::Interface::Primary).unwrap();
+    let written = interface.written_reports().len();
+    let mut invalid = buttons;
+    invalid[2] = ButtonConfig::from_type(ButtonFamily::Keyboard, ButtonType::ProfileUp).unwrap();
+    assert!(tyon.set_buttons(2, &invalid).is_err());
+    assert_eq!(interface.written_reports().len(), written);
+
+    // The device answering for another profile is caught
+    report[2] = 0x00;
+    interface.insert_feature_report(&report);
+    match tyon.get_buttons(2) {
+        Err(Error::SelectionMismatch {
+            field,
+            expected,
+            actual,
+        }) => assert_eq!((field, expected, actual), ("profile index", 1, 0)),
+        result => panic!("Unexpected {:?}", result),
+    }
+}
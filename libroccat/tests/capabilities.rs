@@ -0,0 +1,68 @@
+use libroccat::{
+    device::{
+        ryosmkfx::{Emulator, DEFAULT_KEYS_PRIMARY},
+        tyon::Tyon,
+        Rgb, RoccatDevice,
+    },
+    transport::MemoryTransport,
+};
+
+/// Only uses what every device can do
+fn cycle_profile(device: &dyn RoccatDevice) -> u8 {
+    let next = device.get_profile().unwrap() % device.profile_count() + 1;
+    device.set_profile(next).unwrap();
+    device.get_profile().unwrap()
+}
+
+#[test]
+fn ryosmkfx_capabilities() {
+    let emulator = Emulator::new();
+    let device = emulator.open().unwrap();
+    let device: &dyn RoccatDevice = &device;
+
+    assert_eq!(device.name(), "Ryos MK FX");
+    assert_eq!(device.id(), None);
+    assert_eq!(
+        device.firmware_info().unwrap().unwrap().firmware_version,
+        0x6f
+    );
+    assert_eq!(cycle_profile(device), 2);
+    assert!(device.as_dpi_control().is_none());
+    assert!(device.as_event_source().is_some());
+    assert!(device.as_key_labels().is_some());
+
+    let lighting = device.as_per_key_lighting().unwrap();
+    lighting.set_per_key_lighting_active(true).unwrap();
+    assert!(emulator.custom_lights_active());
+    let red = Rgb {
+        red: 0xff,
+        green: 0,
+        blue: 0,
+    };
+    lighting.set_key_colors(&[None, Some(red)]).unwrap();
+
+    let remapping = device.as_key_remapping().unwrap();
+    let mut usages = remapping.get_key_usages(1).unwrap();
+    assert_eq!(usages.len(), remapping.key_count());
+    assert_eq!(&usages[..], &DEFAULT_KEYS_PRIMARY[..]);
+    usages[0] = 0x29;
+    remapping.set_key_usages(1, &usages).unwrap();
+    assert_eq!(remapping.get_key_usages(1).unwrap()[0], 0x29);
+    assert!(remapping.set_key_usages(1, &usages[1..]).is_err());
+}
+
+#[test]
+fn tyon_capabilities() {
+    let primary = MemoryTransport::new();
+    primary.insert_feature_report(&[0x05, 0x03, 0x04]);
+    let tyon = Tyon::from_transports(vec![primary]).unwrap();
+    let device: &dyn RoccatDevice = &tyon;
+
+    assert_eq!(device.name(), "Tyon");
+    assert_eq!(device.firmware_info().unwrap(), None);
+    assert_eq!(cycle_profile(device), 1);
+    assert!(device.as_per_key_lighting().is_none());
+    assert!(device.as_key_remapping().is_none());
+    assert!(device.as_event_source().is_none());
+    assert!(device.as_key_labels().is_none());
+}
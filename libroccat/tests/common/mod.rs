@@ -0,0 +1,40 @@
+//! Helpers shared by the integration tests, each of which only uses some of them
+#![allow(dead_code)]
+
+use libroccat::{
+    device::{
+        interface::HidrawNode,
+        ryosmkfx::{Event, EventType},
+        DeviceId,
+    },
+    registry::{self, ROCCAT_VENDOR_ID},
+    selector::Candidate,
+};
+use std::{path::PathBuf, time::Instant};
+
+/// An Events interface report of `type_` carrying `data`
+pub fn event(type_: EventType, data: u8) -> Event {
+    Event::from_bytes(&[0x03, 0x00, type_ as u8, data, 0x00]).unwrap()
+}
+
+/// An Events interface report for a key by effect index, received at `timestamp`
+pub fn key_event(effect_index: u8, pressed: bool, timestamp: Instant) -> Event {
+    Event::from_bytes_at(&[0x03, 0x00, 0xfb, effect_index, pressed as u8], timestamp).unwrap()
+}
+
+/// An attached Roccat device with a single hidraw node
+pub fn candidate(product_id: u16, bus_path: &str, serial: Option<&str>, hidraw: &str) -> Candidate {
+    Candidate {
+        id: DeviceId {
+            bus_path: bus_path.to_string(),
+            serial: serial.map(str::to_string),
+            vendor_id: ROCCAT_VENDOR_ID,
+            product_id,
+        },
+        product: registry::find_product(ROCCAT_VENDOR_ID, product_id).unwrap(),
+        nodes: vec![HidrawNode {
+            path: PathBuf::from(hidraw),
+            ..HidrawNode::default()
+        }],
+    }
+}
@@ -0,0 +1,92 @@
+mod common;
+
+use common::candidate;
+use libroccat::{
+    config::{Config, Setting},
+    device::{ryosmkfx::KeyboardLayout, RoccatDevice},
+    Error,
+};
+
+#[test]
+fn parse_config() {
+    let config: Config = "
+        # device         setting  value
+        ryosmkfx         layout   de
+
+        serial:0123ABCD  layout   UK
+    "
+    .parse()
+    .unwrap();
+    assert_eq!(config.entries.len(), 2);
+    assert_eq!(
+        config.entries[0].setting,
+        Setting::Layout(KeyboardLayout::De)
+    );
+    assert_eq!(
+        config.entries[1].setting,
+        Setting::Layout(KeyboardLayout::Uk)
+    );
+    assert_eq!("".parse::<Config>().unwrap(), Config::default());
+}
+
+#[test]
+fn invalid_config() {
+    let lines = [
+        ("tyon#0 layout de", "Invalid device tyon#0"),
+        ("ryosmkfx", "Missing setting"),
+        ("ryosmkfx layout", "Missing value"),
+        ("ryosmkfx layout dvorak", "Unknown layout dvorak"),
+        ("ryosmkfx color red", "Unknown setting color"),
+        ("ryosmkfx layout de fr", "Unexpected text after the value"),
+    ];
+    for &(line, expected) in &lines {
+        match format!("# comment\n{}", line).parse::<Config>() {
+            Err(Error::InvalidConfig { line: 2, message }) => assert_eq!(message, expected),
+            result => panic!("Expected {:?} to be invalid, got {:?}", line, result),
+        }
+    }
+}
+
+#[test]
+fn layout_per_device() {
+    let candidates = vec![
+        candidate(0x2fda, "1-1", None, "/dev/hidraw0"),
+        candidate(0x2fda, "1-2", Some("0123ABCD"), "/dev/hidraw1"),
+        candidate(0x2e4a, "1-3", None, "/dev/hidraw2"),
+    ];
+    let config: Config = "
+        ryosmkfx         layout  de
+        ryosmkfx#2       layout  fr
+        serial:0123ABCD  layout  nordic
+        usb:1-3          layout  uk
+    "
+    .parse()
+    .unwrap();
+
+    let layout = |index: usize| config.layout_for(&candidates[index].id, &candidates);
+    assert_eq!(layout(0), Some(KeyboardLayout::De));
+    // The last line picking a device wins
+    assert_eq!(layout(1), Some(KeyboardLayout::Nordic));
+    assert_eq!(layout(2), Some(KeyboardLayout::Uk));
+    assert_eq!(
+        Config::default().layout_for(&candidates[0].id, &candidates),
+        None
+    );
+}
+
+#[test]
+fn layout_through_device() {
+    let emulator = libroccat::device::ryosmkfx::Emulator::new();
+    let device = emulator.open().unwrap();
+    let device: &dyn RoccatDevice = &device;
+
+    let labels = device.as_key_labels().unwrap();
+    assert_eq!(labels.layout(), KeyboardLayout::Us);
+    labels.set_layout(KeyboardLayout::De);
+    assert_eq!(labels.layout(), KeyboardLayout::De);
+
+    // Emulated devices can't be selected, so configurations leave them alone
+    let config: Config = "ryosmkfx layout fr".parse().unwrap();
+    config.apply(device).unwrap();
+    assert_eq!(labels.layout(), KeyboardLayout::De);
+}
@@ -0,0 +1,228 @@
+use libroccat::{
+    descriptor::{ApplicationUsage, ReportDescriptor, ReportInfo, ReportKind},
+    device::ryosmkfx::{Emulator, Interface, RyosMkFx},
+    transport::{MemoryTransport, Transport},
+    Error,
+};
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+/// Vendor page, one 8 byte feature report and one 1000 byte input report
+const VENDOR_DESCRIPTOR: &[u8] = &[
+    0x06, 0x00, 0xff, // Usage Page (Vendor Defined 0xff00)
+    0x09, 0x01, // Usage (1)
+    0xa1, 0x01, // Collection (Application)
+    0x75, 0x08, // Report Size (8)
+    0x85, 0x13, // Report ID (0x13)
+    0x95, 0x07, // Report Count (7)
+    0x09, 0x01, // Usage (1)
+    0xb1, 0x02, // Feature (Data, Variable, Absolute)
+    0x85, 0x20, // Report ID (0x20)
+    0x96, 0xe7, 0x03, // Report Count (999)
+    0x09, 0x01, // Usage (1)
+    0x81, 0x02, // Input (Data, Variable, Absolute)
+    0xc0, // End Collection
+];
+
+/// Boot keyboard style descriptor without report IDs
+const KEYBOARD_DESCRIPTOR: &[u8] = &[
+    0x05, 0x01, // Usage Page (Generic Desktop)
+    0x09, 0x06, // Usage (Keyboard)
+    0xa1, 0x01, // Collection (Application)
+    0x05, 0x07, // Usage Page (Keyboard)
+    0x75, 0x01, // Report Size (1)
+    0x95, 0x08, // Report Count (8)
+    0x81, 0x02, // Input (Data, Variable, Absolute)
+    0x75, 0x08, // Report Size (8)
+    0x95, 0x07, // Report Count (7)
+    0x81, 0x00, // Input (Data, Array, Absolute)
+    0x75, 0x01, // Report Size (1)
+    0x95, 0x05, // Report Count (5)
+    0x91, 0x02, // Output (Data, Variable, Absolute)
+    0xc0, // End Collection
+];
+
+#[test]
+fn parse_vendor_reports() {
+    let descriptor = ReportDescriptor::parse(VENDOR_DESCRIPTOR).unwrap();
+    assert_eq!(
+        descriptor.applications,
+        vec![ApplicationUsage {
+            usage_page: 0xff00,
+            usage: 0x01
+        }]
+    );
+    assert_eq!(
+        descriptor.report(ReportKind::Feature, 0x13),
+        Some(&ReportInfo {
+            id: 0x13,
+            kind: ReportKind::Feature,
+            len: 8
+        })
+    );
+    assert_eq!(
+        descriptor
+            .report(ReportKind::Input, 0x20)
+            .map(|report| report.len),
+        Some(1000)
+    );
+    assert_eq!(descriptor.report(ReportKind::Feature, 0x20), None);
+}
+
+#[test]
+fn parse_without_report_ids() {
+    let descriptor = ReportDescriptor::parse(KEYBOARD_DESCRIPTOR).unwrap();
+    assert_eq!(descriptor.applications[0].usage_page, 0x01);
+    assert_eq!(descriptor.applications[0].usage, 0x06);
+    assert_eq!(
+        descriptor
+            .report(ReportKind::Input, 0)
+            .map(|report| report.len),
+        Some(8)
+    );
+    assert_eq!(
+        descriptor
+            .report(ReportKind::Output, 0)
+            .map(|report| report.len),
+        Some(1)
+    );
+}
+
+#[test]
+fn malformed_descriptor() {
+    // Truncated Report Count (16 bit)
+    assert!(matches!(
+        ReportDescriptor::parse(&[0x75, 0x08, 0x96, 0x01]),
+        Err(Error::MalformedDescriptor { position: 2 })
+    ));
+    // End Collection without a collection
+    assert!(matches!(
+        ReportDescriptor::parse(&[0xc0]),
+        Err(Error::MalformedDescriptor { position: 0 })
+    ));
+}
+
+#[test]
+fn emulator_descriptor() {
+    let emulator = Emulator::new();
+    let interfaces = emulator.interfaces();
+    RyosMkFx::from_transports(interfaces).unwrap();
+}
+
+/// Declares the ryosmkfx feature reports with their real lengths, except for overrides
+fn ryosmkfx_descriptor(overrides: &[(u8, Option<u16>)]) -> Vec<u8> {
+    let reports = [
+        (0x04, 3),
+        (0x05, 3),
+        (0x06, 125),
+        (0x07, 95),
+        (0x08, 35),
+        (0x09, 23),
+        (0x0a, 8),
+        (0x0b, 294),
+        (0x0d, 32),
+        (0x0e, 2002),
+        (0x0f, 7),
+        (0x13, 8),
+        (0x18, 232),
+    ];
+
+    let mut descriptor = vec![0x06, 0x00, 0xff, 0x09, 0x01, 0xa1, 0x01, 0x75, 0x08];
+    for &(id, len) in &reports {
+        let len = match overrides
+            .iter()
+            .find(|&&(override_id, _)| override_id == id)
+        {
+            Some(&(_, Some(len))) => len,
+            Some(&(_, None)) => continue,
+            None => len,
+        };
+        let [low, high] = (len - 1).to_le_bytes();
+        descriptor.extend_from_slice(&[0x85, id, 0x96, low, high, 0x09, 0x01, 0xb1, 0x02]);
+    }
+    descriptor.push(0xc0);
+    descriptor
+}
+
+fn open_with_descriptor(descriptor: &[u8]) -> Result<RyosMkFx<MemoryTransport>, Error> {
+    let primary = MemoryTransport::new();
+    primary.set_report_descriptor(descriptor);
+    RyosMkFx::from_transports(vec![primary, MemoryTransport::new()])
+}
+
+#[test]
+fn report_layout_mismatch() {
+    assert!(open_with_descriptor(&ryosmkfx_descriptor(&[])).is_ok());
+
+    match open_with_descriptor(&ryosmkfx_descriptor(&[(0x0d, Some(30))])) {
+        Err(Error::ReportLayout {
+            kind,
+            report_id,
+            expected,
+            actual,
+        }) => assert_eq!(
+            (kind, report_id, expected, actual),
+            (ReportKind::Feature, 0x0d, 32, Some(30))
+        ),
+        result => panic!("Expected a report layout error, got {:?}", result.err()),
+    }
+
+    assert!(matches!(
+        open_with_descriptor(&ryosmkfx_descriptor(&[(0x18, None)])),
+        Err(Error::ReportLayout {
+            report_id: 0x18,
+            actual: None,
+            ..
+        })
+    ));
+}
+
+#[test]
+fn verify_reopened_interfaces() {
+    let primary = Arc::new(MemoryTransport::new());
+    primary.set_report_descriptor(&ryosmkfx_descriptor(&[]));
+    let events = Arc::new(MemoryTransport::new());
+    let device = RyosMkFx::from_transports(vec![primary, Arc::clone(&events)]).unwrap();
+
+    let attempts = Arc::new(AtomicUsize::new(0));
+    let reopen_attempts = Arc::clone(&attempts);
+    device.set_reopen(Some(Box::new(move || {
+        // Comes back with different firmware the first time
+        let overrides: &[_] = match reopen_attempts.fetch_add(1, Ordering::SeqCst) {
+            0 => &[(0x0d, Some(30))],
+            _ => &[],
+        };
+        let primary = Arc::new(MemoryTransport::new());
+        primary.set_report_descriptor(&ryosmkfx_descriptor(overrides));
+        Ok(vec![primary, Arc::new(MemoryTransport::new())])
+    })));
+    events.disconnect();
+
+    let start = Instant::now();
+    while attempts.load(Ordering::SeqCst) < 2 {
+        assert!(start.elapsed() < Duration::from_secs(5), "Not reopened");
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    while device
+        .get_interface(Interface::Events)
+        .unwrap()
+        .read_report_timeout(&mut [0; 5], Duration::from_millis(0))
+        .is_err()
+    {
+        assert!(start.elapsed() < Duration::from_secs(5), "Not reopened");
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(
+        device
+            .get_interface(Interface::Primary)
+            .unwrap()
+            .report_descriptor()
+            .unwrap(),
+        Some(ryosmkfx_descriptor(&[]))
+    );
+}
@@ -0,0 +1,248 @@
+use libroccat::{device::ryosmkfx::*, Error};
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+fn wait_for_event<T: libroccat::transport::Transport>(device: &RyosMkFx<T>) -> Event {
+    let start = Instant::now();
+    while start.elapsed() < Duration::from_secs(5) {
+        if let Some(event) = device.get_event() {
+            return event;
+        }
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    panic!("No event arrived");
+}
+
+#[test]
+fn profile() {
+    let emulator = Emulator::new();
+    let device = emulator.open().unwrap();
+
+    assert_eq!(device.get_profile().unwrap(), 1);
+    device.set_profile(3).unwrap();
+    assert_eq!(emulator.profile(), 3);
+    assert_eq!(device.get_profile().unwrap(), 3);
+
+    emulator.switch_profile(5).unwrap();
+    assert_eq!(device.get_profile().unwrap(), 5);
+    let event = wait_for_event(&device);
+    assert_eq!(event.type_, EventType::ProfileStart);
+    assert_eq!(event.data, 5);
+}
+
+#[test]
+fn device_info() {
+    let device = Emulator::new().open().unwrap();
+    assert_eq!(device.get_info().unwrap().firmware_version, 0x6f);
+}
+
+#[test]
+fn lights() {
+    let emulator = Emulator::new();
+    emulator.set_busy_reads(2);
+    let device = emulator.open().unwrap();
+
+    let mut lights = device.get_lights(2).unwrap();
+    assert_eq!(lights.profile, 1);
+    assert_eq!(lights.brightness, 4);
+
+    lights.profile = 2;
+    lights.brightness = 1;
+    device.set_lights(&lights).unwrap();
+    assert_eq!(device.get_lights(2).unwrap().brightness, 1);
+    assert_eq!(device.get_lights(1).unwrap().brightness, 4);
+}
+
+#[test]
+fn control_critical_status() {
+    let emulator = Emulator::new();
+    let device = emulator.open().unwrap();
+
+    emulator.queue_control_status(ControlStatus::Busy);
+    emulator.queue_control_status(ControlStatus::Critical1);
+    match device.get_lights(1) {
+        Err(Error::CriticalStatus(status)) => assert_eq!(status, ControlStatus::Critical1),
+        result => panic!("Expected a critical status, got {:?}", result.err()),
+    }
+
+    emulator.queue_control_status(ControlStatus::Invalid);
+    assert!(matches!(device.get_keys_primary(1), Err(Error::Rejected)));
+
+    assert!(device.get_lights(1).is_ok());
+}
+
+#[test]
+fn keys() {
+    let emulator = Emulator::new();
+    let device = emulator.open().unwrap();
+
+    let keys = device.get_keys_primary(1).unwrap();
+    assert_eq!(&keys.keys[..], &DEFAULT_KEYS_PRIMARY[..]);
+
+    let mut function = device.get_keys_function(4).unwrap();
+    assert_eq!(function.profile_index, 3);
+    function.keys[0].type_ = 0x3b;
+    device.set_keys_function(function).unwrap();
+    assert_eq!(device.get_keys_function(4).unwrap().keys[0].type_, 0x3b);
+    assert_eq!(device.get_keys_function(1).unwrap().keys[0].type_, 0x3a);
+    assert_eq!(emulator.profile_report(0x07, 4).unwrap()[3], 0x3b);
+
+    let easyzone = device.get_keys_easyzone(2).unwrap();
+    assert_eq!(easyzone.keys[1].type_, DEFAULT_KEYS_EASYZONE[1].type_);
+    device.get_keys_macro(1).unwrap();
+    device.get_keys_thumbster(1).unwrap();
+    assert_eq!(device.get_keys_extra(1).unwrap().capslock, 0xff);
+}
+
+#[test]
+fn custom_lights() {
+    let emulator = Emulator::new();
+    emulator.set_busy_reads(1);
+    let device = emulator.open().unwrap();
+
+    assert!(!device.get_custom_lights_active().unwrap());
+    device.set_custom_lights_active(true).unwrap();
+    assert!(emulator.custom_lights_active());
+
+    let mut data = LightLayerData::default();
+    data.set_key_state(0, true);
+    data.set_key_red(0, 0xff);
+    device
+        .set_custom_lights(&CustomLights::new(LightLayer::from_data(&data)))
+        .unwrap();
+    let data = device.get_custom_lights().unwrap().light_layer.get_data();
+    assert!(data.get_key_state(0));
+    assert_eq!(data.get_key_red(0), 0xff);
+
+    emulator.queue_light_control_status(LightControlWriteCheck::Invalid);
+    assert!(matches!(
+        device.set_custom_lights(&CustomLights::new(LightLayer::default())),
+        Err(Error::Rejected)
+    ));
+}
+
+#[test]
+fn key_events() {
+    let emulator = Emulator::new();
+    let device = emulator.open().unwrap();
+
+    emulator.inject_key(0x2a, true).unwrap();
+    emulator.inject_key(0x2a, false).unwrap();
+
+    let press = wait_for_event(&device);
+    assert_eq!(press.type_, EventType::Effect);
+    assert_eq!(press.sdk_index(), 0x2a);
+    assert_eq!(press.action, EventAction::Key(EventKeyAction::Press));
+
+    let release = wait_for_event(&device);
+    assert_eq!(release.sdk_index(), 0x2a);
+    assert_eq!(release.action, EventAction::Key(EventKeyAction::Release));
+}
+
+#[test]
+fn unknown_events() {
+    let emulator = Emulator::new();
+    let device = emulator.open().unwrap();
+
+    // An event type and a key action the firmware isn't known to send
+    for &raw in &[
+        [0x03, 0x00, 0x42, 0x01, 0x02],
+        [0x03, 0x00, 0xfb, 0x10, 0x07],
+    ] {
+        emulator.inject_event(raw);
+        let event = wait_for_event(&device);
+        assert_eq!(event.type_, EventType::Unknown);
+        assert_eq!(event.decode(), DecodedEvent::Unknown { raw });
+    }
+}
+
+#[test]
+fn write_check_timeout() {
+    let emulator = Emulator::new();
+    emulator.set_busy_reads(usize::MAX);
+    let mut device = emulator.open().unwrap();
+    device.set_write_check_timeout(Some(Duration::from_millis(200)));
+
+    let start = Instant::now();
+    assert!(matches!(
+        device.get_lights(1),
+        Err(Error::DeviceBusy { .. })
+    ));
+    assert!(start.elapsed() < Duration::from_secs(2));
+}
+
+#[test]
+fn write_check_cancel() {
+    let emulator = Emulator::new();
+    emulator.set_busy_reads(usize::MAX);
+    let mut device = emulator.open().unwrap();
+    device.set_write_check_timeout(None);
+
+    let cancel = device.cancel_token();
+    let canceller = std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(200));
+        cancel.cancel();
+    });
+
+    assert!(matches!(device.get_keys_primary(1), Err(Error::Cancelled)));
+    canceller.join().unwrap();
+
+    emulator.set_busy_reads(0);
+    device.get_keys_primary(1).unwrap();
+}
+
+fn wait_for_error<T: libroccat::transport::Transport>(device: &RyosMkFx<T>) -> Arc<Error> {
+    let start = Instant::now();
+    while start.elapsed() < Duration::from_secs(5) {
+        if let Some(Err(error)) = device.next_event() {
+            return error;
+        }
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    panic!("No error arrived");
+}
+
+#[test]
+fn drop_stops_event_reader() {
+    let device = Emulator::new().open().unwrap();
+    let start = Instant::now();
+    drop(device);
+    assert!(start.elapsed() < Duration::from_secs(1));
+}
+
+#[test]
+fn unplug_is_reported() {
+    let emulator = Emulator::new();
+    let device = emulator.open().unwrap();
+
+    emulator.unplug();
+    assert!(matches!(*wait_for_error(&device), Error::Disconnected));
+    assert!(matches!(device.get_profile(), Err(Error::Disconnected)));
+    assert!(device.get_event().is_none());
+}
+
+#[test]
+fn reopen_after_unplug() {
+    let emulator = Emulator::new();
+    let device = emulator.open().unwrap();
+    let reopen_emulator = emulator.clone();
+    device.set_reopen(Some(Box::new(move || Ok(reopen_emulator.interfaces()))));
+
+    emulator.unplug();
+    assert!(matches!(*wait_for_error(&device), Error::Disconnected));
+
+    emulator.replug();
+    let start = Instant::now();
+    while device.get_profile().is_err() {
+        assert!(
+            start.elapsed() < Duration::from_secs(5),
+            "Device wasn't reopened"
+        );
+        std::thread::sleep(Duration::from_millis(10));
+    }
+
+    emulator.inject_key(0x10, true).unwrap();
+    assert_eq!(wait_for_event(&device).sdk_index(), 0x10);
+}
@@ -0,0 +1,90 @@
+use libroccat::{
+    device::ryosmkfx::Emulator,
+    event_bus::{EventBus, Overflow},
+};
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+#[test]
+fn every_subscriber_gets_every_event() {
+    let bus = EventBus::new();
+    let first = bus.subscribe();
+    let second = bus.subscribe();
+
+    bus.publish(1);
+    bus.publish(2);
+    assert_eq!(first.try_recv(), Some(1));
+    assert_eq!(first.try_recv(), Some(2));
+    assert_eq!(first.try_recv(), None);
+    assert_eq!(second.try_recv(), Some(1));
+    assert_eq!(second.try_recv(), Some(2));
+
+    drop(first);
+    assert_eq!(bus.subscriber_count(), 1);
+}
+
+#[test]
+fn overflow() {
+    let bus = EventBus::new();
+    let oldest = bus.subscribe_with(2, Overflow::DropOldest);
+    let newest = bus.subscribe_with(2, Overflow::DropNewest);
+    for event in 1..=4 {
+        bus.publish(event);
+    }
+
+    assert_eq!(oldest.dropped(), 2);
+    assert_eq!(oldest.try_recv(), Some(3));
+    assert_eq!(oldest.try_recv(), Some(4));
+    assert_eq!(newest.dropped(), 2);
+    assert_eq!(newest.try_recv(), Some(1));
+    assert_eq!(newest.try_recv(), Some(2));
+    assert!(newest.is_empty());
+}
+
+#[test]
+fn recv_timeout_and_close() {
+    let bus = EventBus::<u8>::new();
+    let subscriber = bus.subscribe();
+
+    let start = Instant::now();
+    assert_eq!(subscriber.recv_timeout(Duration::from_millis(20)), None);
+    assert!(start.elapsed() >= Duration::from_millis(20));
+
+    let publisher = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(10));
+        bus.publish(7);
+    });
+    assert_eq!(subscriber.recv_timeout(Duration::from_secs(5)), Some(7));
+    publisher.join().unwrap();
+
+    // The bus was dropped with the publisher thread
+    assert!(subscriber.is_closed());
+    assert_eq!(subscriber.recv(), None);
+}
+
+#[test]
+fn device_subscribers() {
+    let emulator = Emulator::new();
+    let device = emulator.open().unwrap();
+    let lighting = device.subscribe();
+    let stats = device.subscribe();
+
+    emulator.inject_key(0x20, true).unwrap();
+    let timeout = Duration::from_secs(5);
+    let event = lighting.recv_timeout(timeout).unwrap().unwrap();
+    assert_eq!(event.sdk_index(), 0x20);
+    let event = stats.recv_timeout(timeout).unwrap().unwrap();
+    assert_eq!(event.sdk_index(), 0x20);
+
+    // The device's own queue isn't drained by subscribers
+    let start = Instant::now();
+    while device.get_event().is_none() {
+        assert!(start.elapsed() < timeout, "No event arrived");
+        thread::sleep(Duration::from_millis(1));
+    }
+
+    drop(device);
+    assert!(lighting.recv().is_none());
+}
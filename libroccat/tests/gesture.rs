@@ -0,0 +1,136 @@
+mod common;
+
+use common::key_event;
+use libroccat::device::ryosmkfx::*;
+use std::time::{Duration, Instant};
+
+const KEY: u8 = 0x11;
+const OTHER_KEY: u8 = 0x12;
+
+fn ms(millis: u64) -> Duration {
+    Duration::from_millis(millis)
+}
+
+fn sdk(effect_index: u8) -> GestureKey {
+    GestureKey::Sdk(effect_index_to_sdk_index(effect_index))
+}
+
+/// Feeds events and polls once at `end`, collecting the gestures
+fn recognize(recognizer: &mut GestureRecognizer, events: &[Event], end: Instant) -> Vec<Gesture> {
+    let mut gestures: Vec<GestureEvent> = events
+        .iter()
+        .flat_map(|event| recognizer.update(event))
+        .collect();
+    gestures.extend(recognizer.poll(end));
+    gestures.into_iter().map(|event| event.gesture).collect()
+}
+
+#[test]
+fn tap_and_double_tap() {
+    let start = Instant::now();
+    let mut recognizer = GestureRecognizer::default();
+
+    let tap = [
+        key_event(KEY, true, start + ms(0)),
+        key_event(KEY, false, start + ms(100)),
+    ];
+    assert!(recognizer.update(&tap[0]).is_empty());
+    assert!(recognizer.update(&tap[1]).is_empty());
+    assert_eq!(recognizer.next_deadline(), Some(start + ms(400)));
+    assert!(recognizer.poll(start + ms(399)).is_empty());
+    let gestures = recognizer.poll(start + ms(400));
+    assert_eq!(gestures[0].gesture, Gesture::Tap(sdk(KEY)));
+    assert_eq!(gestures[0].timestamp, start + ms(400));
+
+    let double_tap = [
+        key_event(KEY, true, start + ms(1000)),
+        key_event(KEY, false, start + ms(1050)),
+        key_event(KEY, true, start + ms(1200)),
+        key_event(KEY, false, start + ms(1250)),
+    ];
+    assert_eq!(
+        recognize(&mut recognizer, &double_tap, start + ms(2000)),
+        vec![Gesture::DoubleTap(sdk(KEY))]
+    );
+    assert_eq!(recognizer.next_deadline(), None);
+}
+
+#[test]
+fn long_press() {
+    let start = Instant::now();
+    let mut recognizer = GestureRecognizer::default();
+
+    recognizer.update(&key_event(KEY, true, start + ms(0)));
+    // Until then another key could still make it a chord
+    assert_eq!(recognizer.next_deadline(), Some(start + ms(50)));
+    assert!(recognizer.poll(start + ms(50)).is_empty());
+    assert_eq!(recognizer.next_deadline(), Some(start + ms(500)));
+    let gestures = recognizer.poll(start + ms(600));
+    assert_eq!(gestures[0].gesture, Gesture::LongPress(sdk(KEY)));
+    assert_eq!(gestures[0].timestamp, start + ms(500));
+    // Releasing after the long press was reported completes nothing
+    assert!(recognizer
+        .update(&key_event(KEY, false, start + ms(900)))
+        .is_empty());
+
+    // Without polling, the long press is reported on release; holds in between are nothing
+    let events = [
+        key_event(KEY, true, start + ms(1000)),
+        key_event(KEY, false, start + ms(1700)),
+        key_event(KEY, true, start + ms(2000)),
+        key_event(KEY, false, start + ms(2400)),
+    ];
+    assert_eq!(
+        recognize(&mut recognizer, &events, start + ms(3000)),
+        vec![Gesture::LongPress(sdk(KEY))]
+    );
+}
+
+#[test]
+fn chord() {
+    let start = Instant::now();
+    let mut recognizer = GestureRecognizer::default();
+    let events = [
+        key_event(OTHER_KEY, true, start + ms(0)),
+        key_event(KEY, true, start + ms(20)),
+        key_event(KEY, false, start + ms(800)),
+        key_event(OTHER_KEY, false, start + ms(810)),
+    ];
+    assert_eq!(
+        recognize(&mut recognizer, &events, start + ms(2000)),
+        vec![Gesture::Chord(vec![sdk(OTHER_KEY), sdk(KEY)])]
+    );
+
+    // Too far apart for a chord
+    let events = [
+        key_event(OTHER_KEY, true, start + ms(3000)),
+        key_event(KEY, true, start + ms(3100)),
+        key_event(KEY, false, start + ms(3200)),
+    ];
+    let mut expected = vec![Gesture::LongPress(sdk(OTHER_KEY)), Gesture::Tap(sdk(KEY))];
+    let mut gestures = recognize(&mut recognizer, &events, start + ms(4000));
+    expected.sort_by_key(|gesture| format!("{:?}", gesture));
+    gestures.sort_by_key(|gesture| format!("{:?}", gesture));
+    assert_eq!(gestures, expected);
+}
+
+#[test]
+fn extra_keys() {
+    let start = Instant::now();
+    let mut recognizer = GestureRecognizer::new(GestureConfig {
+        double_tap_window: Duration::from_secs(0),
+        ..GestureConfig::default()
+    });
+
+    let m3 = Event::from_bytes_at(&[0x03, 0x0c, 0xfa, 0x00, 0x00], start).unwrap();
+    let gestures = recognizer.update(&m3);
+    assert_eq!(
+        gestures[0].gesture,
+        Gesture::Tap(GestureKey::Rad(EventRadSubtype::M3))
+    );
+    assert!(!GestureKey::Rad(EventRadSubtype::M3).has_release());
+
+    // Other counters aren't keys
+    let w = Event::from_bytes_at(&[0x03, 0x01, 0xfa, 0x00, 0x00], start).unwrap();
+    assert!(recognizer.update(&w).is_empty());
+}
@@ -0,0 +1,95 @@
+use libroccat::{
+    device::DeviceId,
+    hotplug::{Hotplug, Tracker},
+    registry::ROCCAT_VENDOR_ID,
+    Error,
+};
+use std::{
+    cell::Cell,
+    path::{Path, PathBuf},
+};
+
+const RYOSMKFX: &str = "/sys/devices/pci0000:00/0000:00:14.0/usb1/1-2";
+const UNSUPPORTED: &str = "/sys/devices/pci0000:00/0000:00:14.0/usb1/1-3";
+const OTHER_VENDOR: &str = "/sys/devices/pci0000:00/0000:00:14.0/usb1/1-4";
+const BROKEN: &str = "/sys/devices/pci0000:00/0000:00:14.0/usb1/1-5";
+
+fn id(bus_path: &str) -> DeviceId {
+    DeviceId {
+        bus_path: bus_path.to_string(),
+        serial: None,
+        vendor_id: ROCCAT_VENDOR_ID,
+        product_id: 0x2fda,
+    }
+}
+
+/// Opens devices by the last component of their syspath, counting the attempts
+fn open(syspath: &Path, attempts: &Cell<usize>) -> Option<Result<(DeviceId, String), Error>> {
+    attempts.set(attempts.get() + 1);
+    let bus_path = syspath.file_name().unwrap().to_string_lossy().into_owned();
+    match syspath.to_str().unwrap() {
+        RYOSMKFX => Some(Ok((id(&bus_path), bus_path))),
+        UNSUPPORTED => Some(Err(Error::UnsupportedDevice {
+            product_id: "2e2c".to_string(),
+        })),
+        BROKEN => Some(Err(Error::Disconnected)),
+        _ => None,
+    }
+}
+
+fn added<D>(notification: Option<Result<Hotplug<D>, Error>>) -> D {
+    match notification {
+        Some(Ok(Hotplug::Added(device))) => device,
+        _ => panic!("Expected a device to be added"),
+    }
+}
+
+#[test]
+fn add_and_remove() {
+    let attempts = Cell::new(0);
+    let mut tracker = Tracker::new(|syspath: &Path| open(syspath, &attempts));
+    let syspath = PathBuf::from(RYOSMKFX);
+
+    assert_eq!(added(tracker.added(&syspath)), "1-2");
+    // Further hidraw nodes of the same device
+    assert!(tracker.added(&syspath).is_none());
+    assert_eq!(attempts.get(), 1);
+
+    match tracker.removed(&syspath) {
+        Some(Hotplug::Removed(removed)) => assert_eq!(removed, id("1-2")),
+        _ => panic!("Expected the device to be removed"),
+    }
+    assert!(tracker.removed(&syspath).is_none());
+
+    // Plugged back in
+    assert_eq!(added(tracker.added(&syspath)), "1-2");
+}
+
+#[test]
+fn skip_unsupported() {
+    let attempts = Cell::new(0);
+    let mut tracker = Tracker::new(|syspath: &Path| open(syspath, &attempts));
+
+    for _ in 0..3 {
+        assert!(tracker.added(Path::new(UNSUPPORTED)).is_none());
+        assert!(tracker.added(Path::new(OTHER_VENDOR)).is_none());
+    }
+    // Unsupported products are only looked at once while they're attached
+    assert_eq!(attempts.get(), 4);
+    assert!(tracker.removed(Path::new(UNSUPPORTED)).is_none());
+    assert!(tracker.added(Path::new(UNSUPPORTED)).is_none());
+    assert_eq!(attempts.get(), 5);
+}
+
+#[test]
+fn pass_on_errors() {
+    let attempts = Cell::new(0);
+    let mut tracker = Tracker::new(|syspath: &Path| open(syspath, &attempts));
+
+    assert!(matches!(
+        tracker.added(Path::new(BROKEN)),
+        Some(Err(Error::Disconnected))
+    ));
+    // Not added, so there's nothing to remove
+    assert!(tracker.removed(Path::new(BROKEN)).is_none());
+}
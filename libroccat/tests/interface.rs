@@ -0,0 +1,69 @@
+use libroccat::{
+    device::{
+        interface::{self, HidrawNode},
+        ryosmkfx, tyon,
+    },
+    Error,
+};
+use std::path::PathBuf;
+
+fn node(path: &str, interface_number: Option<u8>, usage_pages: &[u16]) -> HidrawNode {
+    HidrawNode {
+        path: PathBuf::from(path),
+        interface_number,
+        usage_pages: usage_pages.to_vec(),
+    }
+}
+
+#[test]
+fn classify_by_interface_number() {
+    // udev listed the event interface first
+    let nodes = [
+        node("/dev/hidraw4", Some(1), &[0xff00]),
+        node("/dev/hidraw3", Some(0), &[0x01, 0x0c]),
+    ];
+    assert_eq!(
+        interface::classify(&nodes, &ryosmkfx::INTERFACES).unwrap(),
+        vec![PathBuf::from("/dev/hidraw3"), PathBuf::from("/dev/hidraw4")]
+    );
+}
+
+#[test]
+fn classify_checks_usage_pages() {
+    let nodes = [
+        node("/dev/hidraw0", Some(0), &[0xff01]),
+        node("/dev/hidraw1", Some(1), &[0xff01]),
+    ];
+    match interface::classify(&nodes, &tyon::INTERFACES) {
+        Err(Error::InterfaceNotFound {
+            name,
+            interface_number,
+        }) => assert_eq!((name, interface_number), ("mouse", 0)),
+        result => panic!("Expected a missing interface, got {:?}", result),
+    }
+
+    // Without a readable descriptor only the interface number counts
+    let nodes = [
+        node("/dev/hidraw0", Some(0), &[]),
+        node("/dev/hidraw1", Some(1), &[]),
+    ];
+    assert_eq!(
+        interface::classify(&nodes, &tyon::INTERFACES).unwrap(),
+        vec![PathBuf::from("/dev/hidraw0")]
+    );
+}
+
+#[test]
+fn classify_missing_interface() {
+    let nodes = [
+        node("/dev/hidraw0", Some(0), &[0x01]),
+        node("/dev/hidraw1", None, &[0xff00]),
+    ];
+    assert!(matches!(
+        interface::classify(&nodes, &ryosmkfx::INTERFACES),
+        Err(Error::InterfaceNotFound {
+            name: "events",
+            interface_number: 1
+        })
+    ));
+}
@@ -0,0 +1,72 @@
+use libroccat::device::ryosmkfx::*;
+
+#[test]
+fn lookups() {
+    assert_eq!(KeyId::ALL.len(), SDK_KEY_COUNT as usize + 3);
+    for sdk_index in 0..SDK_KEY_COUNT {
+        let key = KeyId::from_sdk_index(sdk_index).unwrap();
+        assert_eq!(key.sdk_index(), Some(sdk_index));
+        assert_eq!(KeyId::from_name(key.name()), Some(key));
+        assert_eq!(key.light_index(), Some(sdk_index_to_light_index(sdk_index)));
+        assert_eq!(
+            KeyId::from_light_index(key.light_index().unwrap()),
+            Some(key)
+        );
+        if let Some(usage) = key.hid_usage() {
+            assert_eq!(KeyId::from_hid_usage(usage), Some(key));
+        }
+    }
+    assert_eq!(KeyId::from_sdk_index(SDK_KEY_COUNT), None);
+
+    assert_eq!(KeyId::from_name("kpenter"), Some(KeyId::KpEnter));
+    assert_eq!("Thumbster2".parse::<KeyId>().unwrap(), KeyId::Thumbster2);
+    assert!("Hyper".parse::<KeyId>().is_err());
+    assert_eq!(KeyId::Thumbster2.sdk_index(), None);
+    assert_eq!(KeyId::M1.hid_usage(), None);
+
+    assert_eq!(KeyId::Esc.sdk_index(), Some(0));
+    assert_eq!(KeyId::from_hid_usage(0x29), Some(KeyId::Esc));
+    assert_eq!(KeyId::from_hid_usage(0x32), Some(KeyId::Backslash));
+    assert_eq!(KeyId::from_hid_usage(0x64), Some(KeyId::NonUsBackslash));
+}
+
+#[test]
+fn events_name_keys() {
+    let event = Event::from_bytes(&[0x03, 0x00, 0xfb, 0x11, 0x01]).unwrap();
+    assert_eq!(KeyId::from_sdk_index(event.sdk_index()), Some(KeyId::Esc));
+}
+
+#[test]
+fn geometry() {
+    let ansi = Geometry::new(PhysicalLayout::Ansi);
+    let iso = Geometry::new(PhysicalLayout::Iso);
+    assert_eq!(ansi.keys().len(), KeyId::ALL.len() - 1);
+    assert_eq!(iso.keys().len(), KeyId::ALL.len());
+    assert!(ansi.get(KeyId::NonUsBackslash).is_none());
+
+    for geometry in &[&ansi, &iso] {
+        for (i, a) in geometry.keys().iter().enumerate() {
+            assert_eq!(geometry.get(a.key), Some(a));
+            let (x, y) = a.center();
+            assert_eq!(geometry.key_at(x, y), Some(a.key));
+            for b in &geometry.keys()[i + 1..] {
+                let overlap = a.x < b.x + b.width
+                    && b.x < a.x + a.width
+                    && a.y < b.y + b.height
+                    && b.y < a.y + a.height;
+                assert!(!overlap, "{} overlaps {}", a.key, b.key);
+            }
+        }
+    }
+
+    // Enter is two rows tall on ISO boards
+    assert_eq!(ansi.get(KeyId::Enter).unwrap().height, 1.0);
+    assert_eq!(iso.get(KeyId::Enter).unwrap().height, 2.0);
+    assert_eq!(ansi.size(), iso.size());
+
+    let (x, y) = iso.get(KeyId::G).unwrap().center();
+    let near = iso.keys_within(x, y, 1.2);
+    assert_eq!(near[0], KeyId::G);
+    assert!(near.contains(&KeyId::T) && near.contains(&KeyId::B) && near.contains(&KeyId::H));
+    assert!(!near.contains(&KeyId::J));
+}
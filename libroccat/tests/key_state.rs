@@ -0,0 +1,80 @@
+mod common;
+
+use common::key_event;
+use libroccat::device::ryosmkfx::*;
+use std::time::{Duration, Instant};
+
+#[test]
+fn press_and_release() {
+    let start = Instant::now();
+    let first = key_event(0x11, true, start);
+    let second = key_event(0x12, true, start + Duration::from_millis(10));
+    let (a, b) = (first.sdk_index(), second.sdk_index());
+
+    let mut keys = KeyState::new();
+    assert_eq!(keys.update(&second), Some(KeyChange::Pressed { key: b }));
+    assert_eq!(keys.update(&first), Some(KeyChange::Pressed { key: a }));
+    // A repeated press keeps the original press time
+    assert_eq!(
+        keys.update(&key_event(0x12, true, start + Duration::from_secs(1))),
+        None
+    );
+    assert!(keys.is_pressed(a) && keys.is_pressed(b));
+    assert_eq!(keys.pressed_keys(), vec![a, b]);
+    assert_eq!(keys.pressed_at(b), Some(start + Duration::from_millis(10)));
+    assert!(keys.hold_duration(a).is_some());
+
+    let release = key_event(0x12, false, start + Duration::from_millis(250));
+    assert_eq!(
+        keys.update(&release),
+        Some(KeyChange::Released {
+            key: b,
+            held: Some(Duration::from_millis(240)),
+        })
+    );
+    assert!(!keys.is_pressed(b));
+    assert_eq!(keys.hold_duration(b), None);
+    assert_eq!(keys.pressed_keys(), vec![a]);
+
+    keys.clear();
+    assert!(keys.pressed_keys().is_empty());
+}
+
+#[test]
+fn ignores_other_events() {
+    let mut keys = KeyState::new();
+    let profile = Event::from_bytes(&[0x03, 0x00, 0x01, 0x02, 0x00]).unwrap();
+    assert_eq!(keys.update(&profile), None);
+
+    let release = key_event(0x11, false, Instant::now());
+    assert_eq!(
+        keys.update(&release),
+        Some(KeyChange::Released {
+            key: release.sdk_index(),
+            held: None,
+        })
+    );
+}
+
+#[test]
+fn emulated_events_are_timestamped() {
+    let emulator = Emulator::new();
+    let device = emulator.open().unwrap();
+    let events = device.subscribe();
+
+    let before = Instant::now();
+    emulator.inject_key(0x20, true).unwrap();
+    let event = events
+        .recv_timeout(Duration::from_secs(5))
+        .unwrap()
+        .unwrap();
+    assert!(event.timestamp >= before && event.timestamp <= Instant::now());
+    // Equality is about the report, not about when it arrived
+    let later = key_event(event.data, true, Instant::now() + Duration::from_secs(1));
+    assert_eq!(event, later);
+    assert_ne!(event, key_event(event.data, false, event.timestamp));
+
+    let mut keys = KeyState::new();
+    keys.update(&event);
+    assert!(keys.is_pressed(0x20));
+}
@@ -0,0 +1,227 @@
+use libroccat::{
+    device::{button::*, ryosmkfx::*},
+    Error,
+};
+
+const LAYERS: [KeyLayer; 3] = [KeyLayer::Normal, KeyLayer::Fn, KeyLayer::Easyshift];
+
+/// Ids of the `Keys*` reports written so far
+fn written_keys_reports(emulator: &Emulator) -> Vec<u8> {
+    emulator
+        .written_reports()
+        .iter()
+        .map(|report| report[0])
+        .filter(|id| (KeysPrimary::REPORT_ID..=KeysEasyzone::REPORT_ID).contains(id))
+        .collect()
+}
+
+#[test]
+fn default_round_trip() {
+    for config in DEFAULT_KEYS_FUNCTION
+        .iter()
+        .chain(&DEFAULT_KEYS_MACRO)
+        .chain(&DEFAULT_KEYS_THUMBSTER)
+        .chain(&DEFAULT_KEYS_EASYZONE)
+    {
+        let action = KeyAction::from_button_config(*config);
+        assert_eq!(action.to_button_config().unwrap(), *config, "{:?}", action);
+    }
+    for &usage in &DEFAULT_KEYS_PRIMARY {
+        assert_eq!(KeyAction::from_primary(usage).to_primary(), Some(usage));
+    }
+
+    let defaults = Keymap::new(1, PhysicalLayout::Ansi);
+    let mut keymap = Keymap::new(1, PhysicalLayout::Ansi);
+    for &key in &KeyId::ALL {
+        for &layer in &LAYERS {
+            if let Some(action) = keymap.get(key, layer) {
+                keymap.set(key, layer, action).unwrap();
+            }
+        }
+    }
+    assert_eq!(keymap, defaults);
+
+    // The backslash key sends `Non-US #` on ISO boards
+    let iso = Keymap::new(1, PhysicalLayout::Iso);
+    assert_eq!(
+        iso.get(KeyId::Backslash, KeyLayer::Normal),
+        Some(KeyAction::key(0x32))
+    );
+    assert_eq!(
+        defaults.get(KeyId::Backslash, KeyLayer::Normal),
+        Some(KeyAction::key(0x31))
+    );
+}
+
+#[test]
+fn actions() {
+    let keymap = Keymap::new(1, PhysicalLayout::Ansi);
+    assert_eq!(
+        keymap.get(KeyId::A, KeyLayer::Normal),
+        Some(KeyAction::key(0x04))
+    );
+    assert_eq!(
+        keymap.get(KeyId::A, KeyLayer::Easyshift),
+        Some(KeyAction::key(0x04))
+    );
+    assert_eq!(keymap.get(KeyId::A, KeyLayer::Fn), None);
+    assert_eq!(
+        keymap.get(KeyId::F1, KeyLayer::Normal),
+        Some(KeyAction::key(0x3a))
+    );
+    assert_eq!(
+        keymap.get(KeyId::F5, KeyLayer::Fn),
+        Some(KeyAction::Multimedia(MultimediaKey::PreviousTrack))
+    );
+    assert_eq!(
+        keymap.get(KeyId::M1, KeyLayer::Normal),
+        Some(KeyAction::Macro(1))
+    );
+    assert_eq!(
+        keymap.get(KeyId::M5, KeyLayer::Easyshift),
+        Some(KeyAction::Macro(10))
+    );
+    assert_eq!(
+        keymap.get(KeyId::Thumbster1, KeyLayer::Normal),
+        Some(KeyAction::ProfileDown)
+    );
+    assert_eq!(
+        keymap.get(KeyId::Thumbster2, KeyLayer::Normal),
+        Some(KeyAction::Easyshift)
+    );
+
+    let shortcut = KeyAction::Key {
+        usage: 0x06,
+        modifiers: ButtonModifiers::CTRL | ButtonModifiers::SHIFT,
+    };
+    assert_eq!(
+        shortcut.to_button_config().unwrap(),
+        ButtonConfig::new(0xbf, 0x03, 0x06)
+    );
+    assert_eq!(
+        KeyAction::from_button_config(ButtonConfig::new(0xbf, 0x03, 0x06)),
+        shortcut
+    );
+    assert_eq!(
+        KeyAction::from_button_config(ButtonConfig::new(0xb4, 0x00, 0x00)),
+        KeyAction::Function(ButtonType::Timer)
+    );
+    // Unknown functions are kept as they are
+    let unknown = ButtonConfig::new(0xc1, 0x00, 0x00);
+    assert_eq!(
+        KeyAction::from_button_config(unknown),
+        KeyAction::Other(unknown)
+    );
+
+    match KeyAction::Macro(11).to_button_config() {
+        Err(Error::OutOfRange { field, .. }) => assert_eq!(field, "Macro slot"),
+        result => panic!("Unexpected {:?}", result),
+    }
+
+    let mut keymap = Keymap::new(1, PhysicalLayout::Ansi);
+    match keymap.set(KeyId::A, KeyLayer::Normal, shortcut) {
+        Err(Error::UnsupportedKeyAction { key, layer }) => {
+            assert_eq!((key, layer), ("A", "normal"))
+        }
+        result => panic!("Unexpected {:?}", result),
+    }
+    assert!(keymap
+        .set(KeyId::Q, KeyLayer::Fn, KeyAction::Disabled)
+        .is_err());
+    keymap.set(KeyId::A, KeyLayer::Easyshift, shortcut).unwrap();
+    assert_eq!(keymap.get(KeyId::A, KeyLayer::Easyshift), Some(shortcut));
+}
+
+#[test]
+fn write_changes() {
+    let emulator = Emulator::new();
+    let device = emulator.open().unwrap();
+
+    let mut keymap = device.get_keymap(2).unwrap();
+    assert!(!keymap.is_modified());
+    assert_eq!(device.set_keymap(&mut keymap).unwrap(), 0);
+    assert!(written_keys_reports(&emulator).is_empty());
+
+    keymap
+        .set(KeyId::A, KeyLayer::Normal, KeyAction::key(0x05))
+        .unwrap();
+    keymap
+        .set(
+            KeyId::M2,
+            KeyLayer::Normal,
+            KeyAction::Multimedia(MultimediaKey::Mute),
+        )
+        .unwrap();
+    assert_eq!(
+        keymap.changes(),
+        KeymapChanges {
+            primary: true,
+            macro_: true,
+            ..KeymapChanges::default()
+        }
+    );
+    assert_eq!(device.set_keymap(&mut keymap).unwrap(), 2);
+    assert_eq!(
+        written_keys_reports(&emulator),
+        vec![KeysPrimary::REPORT_ID, KeysMacro::REPORT_ID]
+    );
+    assert!(!keymap.is_modified());
+    assert_eq!(device.set_keymap(&mut keymap).unwrap(), 0);
+
+    let read = device.get_keymap(2).unwrap();
+    assert_eq!(read, keymap);
+    assert_eq!(
+        read.get(KeyId::A, KeyLayer::Normal),
+        Some(KeyAction::key(0x05))
+    );
+    assert_eq!(
+        device
+            .get_keymap(1)
+            .unwrap()
+            .get(KeyId::A, KeyLayer::Normal),
+        Some(KeyAction::key(0x04))
+    );
+
+    // Keymaps that weren't read from the device are written in full
+    let mut defaults = Keymap::new(2, PhysicalLayout::Ansi);
+    assert_eq!(device.set_keymap(&mut defaults).unwrap(), 6);
+    assert_eq!(device.get_keymap(2).unwrap(), defaults);
+}
+
+#[test]
+fn unconfirmed_codes_read_back() {
+    let emulator = Emulator::new();
+    let device = emulator.open().unwrap();
+    let shortcut = KeyAction::Key {
+        usage: 0x06,
+        modifiers: ButtonModifiers::CTRL,
+    };
+
+    let mut keymap = device.get_keymap(1).unwrap();
+    keymap.set(KeyId::A, KeyLayer::Easyshift, shortcut).unwrap();
+    assert_eq!(device.set_keymap(&mut keymap).unwrap(), 1);
+    assert_eq!(
+        device
+            .get_keymap(1)
+            .unwrap()
+            .get(KeyId::A, KeyLayer::Easyshift),
+        Some(shortcut)
+    );
+
+    // A firmware without the shortcut code stores something else, which reading back catches
+    emulator.set_unknown_button_codes(&[0xbf]);
+    let mut keymap = device.get_keymap(2).unwrap();
+    keymap.set(KeyId::A, KeyLayer::Easyshift, shortcut).unwrap();
+    match device.set_keymap(&mut keymap) {
+        Err(Error::UnconfirmedButtonCode {
+            family,
+            written,
+            stored,
+        }) => assert_eq!(
+            (family, written, stored),
+            (ButtonFamily::Keyboard, 0xbf, 0x00)
+        ),
+        result => panic!("Unexpected {:?}", result),
+    }
+    assert!(keymap.is_modified());
+}
@@ -0,0 +1,67 @@
+use libroccat::{device::ryosmkfx::*, Error};
+use std::collections::HashSet;
+
+#[test]
+fn labels() {
+    for &layout in &KeyboardLayout::ALL {
+        let geometry = layout.geometry();
+        let mut labels = HashSet::new();
+        for &key in &KeyId::ALL {
+            match layout.label(key) {
+                Some(label) => {
+                    assert!(geometry.get(key).is_some(), "{} {}", layout, key);
+                    assert!(labels.insert(label.to_lowercase()), "{} {}", layout, label);
+                    assert_eq!(layout.key(label), Some(key));
+                }
+                None => assert!(geometry.get(key).is_none(), "{} {}", layout, key),
+            }
+        }
+        assert_eq!(KeyboardLayout::from_name(layout.name()), Some(layout));
+    }
+}
+
+#[test]
+fn localized_keys() {
+    let us = KeyboardLayout::Us;
+    assert_eq!(us.physical(), PhysicalLayout::Ansi);
+    assert_eq!(us.key("Y"), Some(KeyId::Y));
+    assert_eq!(us.label(KeyId::NonUsBackslash), None);
+
+    let de = KeyboardLayout::De;
+    assert_eq!(de.physical(), PhysicalLayout::Iso);
+    assert_eq!(de.key("Y"), Some(KeyId::Z));
+    assert_eq!(de.key("z"), Some(KeyId::Y));
+    assert_eq!(de.key("ö"), Some(KeyId::Semicolon));
+    assert_eq!(de.label(KeyId::Backslash), Some("#"));
+    // Key names still work where they aren't labels
+    assert_eq!(de.key("Semicolon"), Some(KeyId::Semicolon));
+
+    let uk = KeyboardLayout::Uk;
+    assert_eq!(uk.key("#"), Some(KeyId::Backslash));
+    assert_eq!(uk.key("\\"), Some(KeyId::NonUsBackslash));
+
+    let fr = KeyboardLayout::Fr;
+    assert_eq!(fr.key("A"), Some(KeyId::Q));
+    assert_eq!(fr.key("&"), Some(KeyId::Num1));
+    assert_eq!(fr.key("M"), Some(KeyId::Semicolon));
+
+    assert_eq!(KeyboardLayout::Nordic.key("Å"), Some(KeyId::LeftBracket));
+
+    assert_eq!("DE".parse::<KeyboardLayout>().unwrap(), KeyboardLayout::De);
+    assert!(matches!(
+        "dvorak".parse::<KeyboardLayout>(),
+        Err(Error::UnknownLayout { .. })
+    ));
+}
+
+#[test]
+fn device_layout() {
+    let emulator = Emulator::new();
+    let device = emulator.open().unwrap();
+    assert_eq!(device.layout(), KeyboardLayout::Us);
+    device.set_layout(KeyboardLayout::De);
+    assert_eq!(
+        device.layout().key("Z").and_then(KeyId::sdk_index),
+        Some(45)
+    );
+}
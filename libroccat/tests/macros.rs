@@ -0,0 +1,191 @@
+use libroccat::{device::ryosmkfx::*, Error};
+use std::time::Duration;
+
+const SELECT_ALL: &str = "\
+# Selects everything
+name Select all
+loop 2
+down LeftCtrl
+down A 20
+up A
+up 0xe0 5
+";
+
+fn select_all() -> Macro {
+    Macro::new(
+        "Select all",
+        2,
+        vec![
+            MacroStep::new(0xe0, MacroAction::Down, Duration::from_millis(0)),
+            MacroStep::new(0x04, MacroAction::Down, Duration::from_millis(20)),
+            MacroStep::new(0x04, MacroAction::Up, Duration::from_millis(0)),
+            MacroStep::new(0xe0, MacroAction::Up, Duration::from_millis(5)),
+        ],
+    )
+}
+
+#[test]
+fn text_round_trip() {
+    let macro_: Macro = SELECT_ALL.parse().unwrap();
+    assert_eq!(macro_, select_all());
+    assert_eq!(macro_.to_string().parse::<Macro>().unwrap(), macro_);
+
+    let filed: Macro = "name Copy\nset Editing".parse().unwrap();
+    assert_eq!(filed.set_name, "Editing");
+    assert_eq!(filed.to_string(), "name Copy\nset Editing\nloop 1\n");
+
+    let empty: Macro = "".parse().unwrap();
+    assert_eq!(empty, Macro::default());
+    assert_eq!(empty.loop_count, 1);
+
+    // Usages without a key on the board are written in hexadecimal
+    let unnamed = Macro::new(
+        "",
+        1,
+        vec![MacroStep::new(
+            0x68,
+            MacroAction::Down,
+            Duration::from_millis(0),
+        )],
+    );
+    assert_eq!(unnamed.to_string(), "loop 1\ndown 0x68\n");
+    assert_eq!(unnamed.to_string().parse::<Macro>().unwrap(), unnamed);
+}
+
+#[test]
+fn parse_errors() {
+    for &(text, line) in &[
+        ("press A", 1),
+        ("name Test\n\ndown NoSuchKey", 3),
+        ("down", 1),
+        ("down A soon", 1),
+        ("down A 10 20", 1),
+        ("loop many", 1),
+    ] {
+        match text.parse::<Macro>() {
+            Err(Error::InvalidMacro {
+                line: error_line, ..
+            }) => {
+                assert_eq!(error_line, line, "{}", text)
+            }
+            result => panic!("Unexpected {:?} for {}", result, text),
+        }
+    }
+}
+
+#[test]
+fn validation() {
+    assert!(select_all().validate().is_ok());
+
+    let step = MacroStep::new(0x04, MacroAction::Down, Duration::from_millis(0));
+    let mut macro_ = Macro::new("", 1, vec![step; MACRO_MAX_STEPS]);
+    assert!(macro_.validate().is_ok());
+    macro_.steps.push(step);
+    match macro_.validate() {
+        Err(Error::OutOfRange { field, .. }) => assert_eq!(field, "Macro step count"),
+        result => panic!("Unexpected {:?}", result),
+    }
+
+    let long_name = Macro::new(&"x".repeat(MACRO_MAX_NAME_LEN + 1), 1, Vec::new());
+    match long_name.validate() {
+        Err(Error::OutOfRange { field, .. }) => assert_eq!(field, "Macro name length"),
+        result => panic!("Unexpected {:?}", result),
+    }
+    let mut long_set_name = select_all();
+    long_set_name.set_name = "x".repeat(MACRO_MAX_NAME_LEN + 1);
+    match long_set_name.validate() {
+        Err(Error::OutOfRange { field, .. }) => assert_eq!(field, "Macro set name length"),
+        result => panic!("Unexpected {:?}", result),
+    }
+    assert!(Macro::new("", 0, Vec::new()).validate().is_err());
+    assert!("down A 65536".parse::<Macro>().is_err());
+    assert!("down A 65535".parse::<Macro>().is_ok());
+}
+
+#[test]
+fn device_round_trip() {
+    let emulator = Emulator::new();
+    let device = emulator.open().unwrap();
+
+    assert_eq!(device.get_macro(3, 4).unwrap(), Macro::default());
+    device.set_macro(3, 4, &select_all()).unwrap();
+    assert_eq!(device.get_macro(3, 4).unwrap(), select_all());
+    assert_eq!(device.get_macro(3, 5).unwrap(), Macro::default());
+    assert_eq!(device.get_macro(2, 4).unwrap(), Macro::default());
+
+    for &(profile, slot, field) in &[
+        (0, 1, "Profile"),
+        (6, 1, "Profile"),
+        (1, 0, "Macro slot"),
+        (1, 11, "Macro slot"),
+    ] {
+        match device.get_macro(profile, slot) {
+            Err(Error::OutOfRange {
+                field: error_field, ..
+            }) => assert_eq!(error_field, field),
+            result => panic!("Unexpected {:?}", result),
+        }
+        assert!(device.set_macro(profile, slot, &select_all()).is_err());
+    }
+
+    // Invalid macros aren't written
+    let written = emulator.written_reports().len();
+    let invalid = Macro::new("", 0, Vec::new());
+    assert!(device.set_macro(1, 1, &invalid).is_err());
+    assert_eq!(emulator.written_reports().len(), written);
+}
+
+/// Payload of a macro report holding no steps, see `Emulator::store_macro_report`
+fn vendor_payload(profile_index: u8, slot_index: u8) -> Vec<u8> {
+    let mut payload = vec![0u8; 1997];
+    payload[..3].copy_from_slice(&[profile_index, slot_index, 0x01]);
+    payload[3] = 0x5a;
+    payload[27..31].copy_from_slice(b"Work");
+    payload[51..55].copy_from_slice(b"Copy");
+    payload
+}
+
+#[test]
+fn vendor_fields_kept() {
+    let emulator = Emulator::new();
+    let device = emulator.open().unwrap();
+
+    emulator.store_macro_report(2, 3, &vendor_payload(1, 2));
+    let mut macro_ = device.get_macro(2, 3).unwrap();
+    assert_eq!(macro_.name, "Copy");
+    assert_eq!(macro_.set_name, "Work");
+    assert_eq!(macro_.unused[0], 0x5a);
+
+    // Changing the steps leaves the set name and unknown bytes alone
+    macro_.steps = select_all().steps;
+    device.set_macro(2, 3, &macro_).unwrap();
+    let report = emulator.macro_report(2, 3).unwrap();
+    assert_eq!(report[6], 0x5a);
+    assert_eq!(&report[30..35], b"Work\0");
+    assert_eq!(device.get_macro(2, 3).unwrap(), macro_);
+}
+
+#[test]
+fn selection_checked() {
+    let emulator = Emulator::new();
+    let device = emulator.open().unwrap();
+
+    // Indices in the report, then the field that doesn't match, expected and actual value
+    for &(profile_index, slot_index, field, expected, actual) in &[
+        (0, 2, "profile index", 1, 0),
+        (1, 4, "macro slot index", 2, 4),
+    ] {
+        emulator.store_macro_report(2, 3, &vendor_payload(profile_index, slot_index));
+        match device.get_macro(2, 3) {
+            Err(Error::SelectionMismatch {
+                field: error_field,
+                expected: error_expected,
+                actual: error_actual,
+            }) => assert_eq!(
+                (error_field, error_expected, error_actual),
+                (field, expected, actual)
+            ),
+            result => panic!("Unexpected {:?}", result),
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use libroccat::{
+    device::{interface::HidrawNode, Device},
+    registry::{self, Capability, Family, Product, Registry, ROCCAT_VENDOR_ID},
+    Error,
+};
+
+#[test]
+fn builtin_products() {
+    let product = registry::find_product(ROCCAT_VENDOR_ID, 0x2fda).unwrap();
+    assert_eq!(product.name, "Ryos MK FX");
+    assert_eq!(product.family, Family::RyosMkFx);
+    assert!(product.has_capability(Capability::CustomLights));
+
+    let tyons = registry::products()
+        .into_iter()
+        .filter(|product| product.family == Family::Tyon)
+        .count();
+    assert_eq!(tyons, 2);
+    assert!(!registry::find_product(ROCCAT_VENDOR_ID, 0x2e4b)
+        .unwrap()
+        .has_capability(Capability::Events));
+
+    assert_eq!(registry::find_product(ROCCAT_VENDOR_ID, 0x0001), None);
+}
+
+#[test]
+fn register_product() {
+    let mut registry = Registry::new();
+    let ryosmkfx = registry.find_product(ROCCAT_VENDOR_ID, 0x2fda).unwrap();
+    registry.register_product(Product {
+        product_id: 0x2fdb,
+        name: "Ryos MK FX (Regional)",
+        ..ryosmkfx
+    });
+    registry.register_product(Product {
+        product_id: 0x2fdb,
+        name: "Ryos MK FX (Nordic)",
+        ..ryosmkfx
+    });
+
+    let product = registry.find_product(ROCCAT_VENDOR_ID, 0x2fdb).unwrap();
+    assert_eq!(product.name, "Ryos MK FX (Nordic)");
+    assert_eq!(product.family, Family::RyosMkFx);
+    assert_eq!(
+        registry
+            .products()
+            .iter()
+            .filter(|product| product.product_id == 0x2fdb)
+            .count(),
+        1
+    );
+
+    // Other registries, including the process wide one, are left alone
+    assert_eq!(Registry::new().find_product(ROCCAT_VENDOR_ID, 0x2fdb), None);
+    assert_eq!(registry::find_product(ROCCAT_VENDOR_ID, 0x2fdb), None);
+
+    assert!(!registry.is_known_vendor(0x1234));
+    registry.register_product(Product {
+        vendor_id: 0x1234,
+        ..product
+    });
+    assert!(registry.is_known_vendor(0x1234));
+}
+
+#[test]
+fn open_missing_interfaces() {
+    let product = registry::find_product(ROCCAT_VENDOR_ID, 0x2e4a).unwrap();
+    let nodes = [HidrawNode {
+        interface_number: Some(1),
+        ..HidrawNode::default()
+    }];
+    let result: Result<Device, Error> = product.open(&nodes);
+    assert!(matches!(
+        result,
+        Err(Error::InterfaceNotFound {
+            interface_number: 0,
+            ..
+        })
+    ));
+}
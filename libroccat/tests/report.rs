@@ -0,0 +1,149 @@
+use libroccat::{
+    device::ryosmkfx::{
+        Control, DecodedEvent, Event, EventAction, EventKeyAction, EventLiveRecordingAction,
+        EventRadSubtype, EventSubtype, EventType, KeysEasyzone, KeysExtra, LightControl,
+        LightControlState, LightEffect, Lights,
+    },
+    Error,
+};
+
+fn lights_bytes() -> Vec<u8> {
+    let mut lights = Lights::default();
+    lights.effect = LightEffect::Breathing;
+    lights.to_bytes()
+}
+
+fn fix_bytesum(bytes: &mut [u8]) {
+    let end = bytes.len() - 2;
+    let bytesum = bytes[..end]
+        .iter()
+        .fold(0u16, |sum, b| sum.wrapping_add(u16::from(*b)));
+    bytes[end..].copy_from_slice(&bytesum.to_le_bytes());
+}
+
+#[test]
+fn round_trip() {
+    let bytes = lights_bytes();
+    assert_eq!(bytes.len(), 32);
+    assert_eq!(&bytes[..2], &[0x0d, 0x20]);
+    assert_eq!(&bytes[16..18], &[0xff, 0x00]);
+
+    let lights = Lights::from_bytes(&bytes).unwrap();
+    assert_eq!(lights.effect as u8, LightEffect::Breathing as u8);
+    assert_eq!({ lights.red }, 0xff);
+    assert_eq!(lights.to_bytes(), bytes);
+}
+
+#[test]
+fn unknown_enum_value() {
+    let mut bytes = lights_bytes();
+    bytes[7] = 0x42;
+    fix_bytesum(&mut bytes);
+
+    match Lights::from_bytes(&bytes) {
+        Err(Error::InvalidValue { type_name, value }) => {
+            assert_eq!((type_name, value), ("LightEffect", 0x42))
+        }
+        result => panic!("Expected an invalid value, got {:?}", result),
+    }
+}
+
+#[test]
+fn wrong_length() {
+    let bytes = KeysExtra::default().to_bytes();
+    assert!(matches!(
+        KeysExtra::from_bytes(&bytes[..bytes.len() - 1]),
+        Err(Error::ReportLength {
+            report_id: 0x0a,
+            expected: 8,
+            actual: 7
+        })
+    ));
+}
+
+#[test]
+fn events() {
+    let event = Event::from_bytes(&[0x03, 0x00, 0xfb, 0x10, 0x01]).unwrap();
+    assert_eq!(event.type_, EventType::Effect);
+    assert_eq!(event.subtype, EventSubtype::None(0x00));
+    assert_eq!(event.action, EventAction::Key(EventKeyAction::Press));
+
+    assert!(matches!(
+        Event::from_bytes(&[0x03, 0x00, 0x42, 0x00, 0x00]),
+        Err(Error::InvalidValue {
+            type_name: "EventType",
+            value: 0x42
+        })
+    ));
+    assert!(Event::from_bytes(&[0x03, 0x00, 0xfb, 0x10, 0x07]).is_err());
+}
+
+#[test]
+fn decoded_events() {
+    let press = Event::from_bytes(&[0x03, 0x00, 0xfb, 0x10, 0x01]).unwrap();
+    assert_eq!(
+        press.decode(),
+        DecodedEvent::KeyPress {
+            key: press.sdk_index()
+        }
+    );
+    assert_eq!(
+        DecodedEvent::from_bytes(&[0x03, 0x00, 0x02, 0x03, 0x00]).unwrap(),
+        DecodedEvent::ProfileChanged { profile: 3 }
+    );
+    assert_eq!(
+        DecodedEvent::from_bytes(&[0x03, 0x00, 0x04, 0x05, 0x03]).unwrap(),
+        DecodedEvent::LiveRecording {
+            action: EventLiveRecordingAction::EndSuccess,
+            data: 5
+        }
+    );
+    assert_eq!(
+        DecodedEvent::from_bytes(&[0x03, 0x0a, 0xfa, 0x00, 0x00]).unwrap(),
+        DecodedEvent::Rad(EventRadSubtype::M1)
+    );
+    assert_eq!(
+        DecodedEvent::from_bytes(&[0x03, 0x00, 0x0d, 0x01, 0x00]).unwrap(),
+        DecodedEvent::Other {
+            type_: EventType::TimerStart,
+            data: 1
+        }
+    );
+
+    // Values the firmware isn't known to send are kept instead of rejected
+    for raw in &[
+        [0x03, 0x00, 0x42, 0x00, 0x00],
+        [0x03, 0x00, 0xfb, 0x10, 0x07],
+        [0x03, 0x7f, 0xfa, 0x00, 0x00],
+        [0x03, 0x00, 0x00, 0x00, 0x00],
+    ] {
+        assert_eq!(
+            DecodedEvent::from_bytes(raw).unwrap(),
+            DecodedEvent::Unknown { raw: *raw }
+        );
+    }
+    assert!(DecodedEvent::from_bytes(&[0x03, 0x00, 0x02]).is_err());
+}
+
+#[test]
+fn headers() {
+    let bytes = LightControl::new(LightControlState::Custom).to_bytes();
+    assert_eq!(LightControl::REPORT_ID, 0x13);
+    assert_eq!(&bytes[..3], &[0x13, 0x08, 0x01]);
+
+    let bytes = KeysEasyzone::default().to_bytes();
+    assert_eq!(bytes.len(), KeysEasyzone::LEN);
+    assert_eq!(&bytes[..3], &[0x0b, 0x26, 0x01]);
+
+    assert_eq!(Control::new(0x02, 0xb1).to_bytes(), vec![0x04, 0x02, 0xb1]);
+
+    let mut bytes = KeysExtra::default().to_bytes();
+    bytes[0] = 0x0b;
+    assert!(matches!(
+        KeysExtra::from_bytes(&bytes),
+        Err(Error::ReportId {
+            expected: 0x0a,
+            actual: 0x0b
+        })
+    ));
+}
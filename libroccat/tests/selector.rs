@@ -0,0 +1,74 @@
+mod common;
+
+use common::candidate;
+use libroccat::{selector::Selector, Error};
+use std::path::PathBuf;
+
+#[test]
+fn parse_selectors() {
+    assert_eq!(
+        "ryosmkfx".parse::<Selector>().unwrap(),
+        Selector::Family {
+            name: "ryosmkfx".to_string(),
+            index: 1
+        }
+    );
+    assert_eq!(
+        "Tyon#2".parse::<Selector>().unwrap(),
+        Selector::Family {
+            name: "tyon".to_string(),
+            index: 2
+        }
+    );
+    assert_eq!(
+        "serial:0123ABCD".parse::<Selector>().unwrap(),
+        Selector::Serial("0123ABCD".to_string())
+    );
+    assert_eq!(
+        "usb:1-4.2".parse::<Selector>().unwrap(),
+        Selector::BusPath("1-4.2".to_string())
+    );
+    assert_eq!(
+        "/dev/hidraw3".parse::<Selector>().unwrap(),
+        Selector::Hidraw(PathBuf::from("/dev/hidraw3"))
+    );
+}
+
+#[test]
+fn invalid_selectors() {
+    for selector in &["tyon#0", "tyon#x", "#2", ""] {
+        match selector.parse::<Selector>() {
+            Err(Error::InvalidSelector { selector: invalid }) => assert_eq!(&invalid, selector),
+            result => panic!("Expected {:?} to be invalid, got {:?}", selector, result),
+        }
+    }
+}
+
+#[test]
+fn select_candidates() {
+    // Ordered by bus path, like `find_devices` lists them
+    let candidates = vec![
+        candidate(0x2e4a, "1-1", None, "/dev/hidraw0"),
+        candidate(0x2fda, "1-2", Some("0123ABCD"), "/dev/hidraw1"),
+        candidate(0x2e4b, "1-3", Some("4567EF01"), "/dev/hidraw2"),
+    ];
+    let select = |selector: &str| {
+        selector
+            .parse::<Selector>()
+            .unwrap()
+            .select(candidates.clone())
+            .map(|candidate| candidate.id.bus_path)
+    };
+
+    assert_eq!(select("ryosmkfx").as_deref(), Some("1-2"));
+    assert_eq!(select("tyon").as_deref(), Some("1-1"));
+    assert_eq!(select("tyon#2").as_deref(), Some("1-3"));
+    assert_eq!(select("tyon#3"), None);
+    assert_eq!(select("serial:4567EF01").as_deref(), Some("1-3"));
+    assert_eq!(select("serial:89AB"), None);
+    assert_eq!(select("usb:1-2").as_deref(), Some("1-2"));
+    assert_eq!(select("usb:1-4"), None);
+    assert_eq!(select("/dev/hidraw2").as_deref(), Some("1-3"));
+    assert_eq!(select("/dev/hidraw5"), None);
+    assert_eq!(select("kone"), None);
+}
@@ -0,0 +1,270 @@
+use libroccat::{
+    device::{ryosmkfx::RyosMkFx, tyon::Tyon},
+    transport::{MemoryTransport, RetryPolicy, Transport},
+    Error,
+};
+use std::{
+    io,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+#[test]
+fn memory_feature_reports() {
+    let transport = MemoryTransport::new();
+    transport.insert_feature_report(&[0x05, 0x03, 0x02]);
+
+    let mut buf = [0x05, 0x00, 0x00];
+    assert_eq!(transport.get_feature_report(&mut buf).unwrap(), 3);
+    assert_eq!(buf, [0x05, 0x03, 0x02]);
+
+    let mut buf = [0x06, 0x00, 0x00];
+    assert!(transport.get_feature_report(&mut buf).is_err());
+
+    transport.set_feature_report(&[0x05, 0x03, 0x04]).unwrap();
+    assert_eq!(
+        transport.feature_report(0x05).unwrap(),
+        vec![0x05, 0x03, 0x04]
+    );
+    assert_eq!(transport.written_reports(), vec![vec![0x05, 0x03, 0x04]]);
+}
+
+#[test]
+fn memory_input_reports() {
+    let transport = Arc::new(MemoryTransport::new());
+
+    let reader = Arc::clone(&transport);
+    let handle = std::thread::spawn(move || {
+        let mut buf = [0u8; 5];
+        let len = reader.read_report(&mut buf).unwrap();
+        buf[..len].to_vec()
+    });
+
+    transport.push_input_report(&[0x03, 0x00, 0xfb, 0x11, 0x01]);
+    assert_eq!(handle.join().unwrap(), vec![0x03, 0x00, 0xfb, 0x11, 0x01]);
+}
+
+#[test]
+fn tyon_profile() {
+    let primary = MemoryTransport::new();
+    primary.insert_feature_report(&[0x05, 0x03, 0x01]);
+    let tyon = Tyon::from_transports(vec![primary, MemoryTransport::new()]).unwrap();
+
+    assert_eq!(tyon.get_profile().unwrap(), 2);
+    tyon.set_profile(4).unwrap();
+    assert_eq!(tyon.get_profile().unwrap(), 4);
+    match tyon.set_profile(6) {
+        Err(Error::OutOfRange { field, value, .. }) => assert_eq!((field, value), ("Profile", 6)),
+        result => panic!("Expected an out of range error, got {:?}", result.err()),
+    }
+}
+
+#[test]
+fn ryosmkfx_profile() {
+    let primary = MemoryTransport::new();
+    primary.insert_feature_report(&[0x05, 0x03, 0x52]);
+    let ryosmkfx = RyosMkFx::from_transports(vec![primary, MemoryTransport::new()]).unwrap();
+
+    assert_eq!(ryosmkfx.get_profile().unwrap(), 3);
+    ryosmkfx.set_profile(1).unwrap();
+    assert_eq!(ryosmkfx.get_profile().unwrap(), 1);
+}
+
+#[test]
+fn memory_read_timeout() {
+    let transport = MemoryTransport::new();
+    let mut buf = [0u8; 5];
+    let timeout = Duration::from_millis(10);
+    assert_eq!(
+        transport.read_report_timeout(&mut buf, timeout).unwrap(),
+        None
+    );
+
+    transport.push_input_report(&[0x03, 0x00, 0xfb, 0x11, 0x01]);
+    assert_eq!(
+        transport.read_report_timeout(&mut buf, timeout).unwrap(),
+        Some(5)
+    );
+
+    transport.disconnect();
+    assert!(matches!(
+        transport.read_report_timeout(&mut buf, timeout),
+        Err(Error::Disconnected)
+    ));
+    assert!(matches!(
+        transport.get_feature_report(&mut buf),
+        Err(Error::Disconnected)
+    ));
+}
+
+#[test]
+fn ryosmkfx_events() {
+    let events = Arc::new(MemoryTransport::new());
+    let ryosmkfx =
+        RyosMkFx::from_transports(vec![Arc::new(MemoryTransport::new()), Arc::clone(&events)])
+            .unwrap();
+
+    events.push_input_report(&[0x03, 0x00, 0xfb, 0x11, 0x01]);
+    let event = loop {
+        if let Some(event) = ryosmkfx.get_event() {
+            break event;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(1));
+    };
+    assert_eq!(event.sdk_index(), 0x00);
+}
+
+/// Fails the first `failures` transfers, then passes them on to a `MemoryTransport`
+struct FlakyTransport {
+    inner: MemoryTransport,
+    failures: usize,
+    attempts: AtomicUsize,
+}
+
+impl FlakyTransport {
+    fn new(failures: usize) -> Self {
+        Self {
+            inner: MemoryTransport::new(),
+            failures,
+            attempts: AtomicUsize::new(0),
+        }
+    }
+
+    fn attempt(&self) -> Result<(), Error> {
+        if self.attempts.fetch_add(1, Ordering::SeqCst) < self.failures {
+            return Err(io::Error::other("Flaky transfer").into());
+        }
+        Ok(())
+    }
+}
+
+impl Transport for FlakyTransport {
+    fn get_feature_report(&self, buf: &mut [u8]) -> Result<usize, Error> {
+        self.attempt()?;
+        self.inner.get_feature_report(buf)
+    }
+
+    fn set_feature_report(&self, buf: &[u8]) -> Result<usize, Error> {
+        self.attempt()?;
+        self.inner.set_feature_report(buf)
+    }
+
+    fn read_report(&self, buf: &mut [u8]) -> Result<usize, Error> {
+        self.inner.read_report(buf)
+    }
+
+    fn read_report_timeout(
+        &self,
+        buf: &mut [u8],
+        timeout: Duration,
+    ) -> Result<Option<usize>, Error> {
+        self.inner.read_report_timeout(buf, timeout)
+    }
+}
+
+fn lights_report(bytesum_offset: u16) -> Vec<u8> {
+    let mut report = vec![0x0d, 0x20, 0x00, 0x04];
+    report.resize(30, 0x00);
+    let bytesum = report.iter().map(|b| u16::from(*b)).sum::<u16>() + bytesum_offset;
+    report.extend_from_slice(&bytesum.to_le_bytes());
+    report
+}
+
+#[test]
+fn retry_checksum_mismatch() {
+    use libroccat::device::ryosmkfx::Lights;
+
+    let transport = FlakyTransport::new(0);
+    transport.inner.insert_feature_report(&lights_report(1));
+
+    let policy = RetryPolicy::new(3, Duration::from_millis(0), None);
+    let error = Lights::read_with_retry(&transport, &policy).unwrap_err();
+    let mismatch = match error {
+        Error::Checksum(mismatch) => mismatch,
+        error => panic!("Expected a checksum mismatch, got {:?}", error),
+    };
+    assert_eq!(mismatch.report_id, 0x0d);
+    assert_eq!(mismatch.actual, mismatch.expected + 1);
+    assert_eq!(transport.attempts.load(Ordering::SeqCst), 3);
+
+    transport.inner.insert_feature_report(&lights_report(0));
+    let lights = Lights::read_with_retry(&transport, &policy).unwrap();
+    assert_eq!(lights.brightness, 4);
+}
+
+#[test]
+fn retry_flaky_transfers() {
+    use libroccat::device::ryosmkfx::Lights;
+
+    let policy = RetryPolicy::new(3, Duration::from_millis(1), None);
+
+    let transport = FlakyTransport::new(2);
+    Lights::default()
+        .write_with_retry(&transport, &policy)
+        .unwrap();
+    assert_eq!(transport.attempts.load(Ordering::SeqCst), 3);
+
+    let transport = FlakyTransport::new(3);
+    transport.inner.insert_feature_report(&lights_report(0));
+    assert!(Lights::read_with_retry(&transport, &policy).is_err());
+    assert_eq!(transport.attempts.load(Ordering::SeqCst), 3);
+}
+
+#[test]
+fn retry_deadline() {
+    use libroccat::device::ryosmkfx::Lights;
+
+    let transport = FlakyTransport::new(usize::MAX);
+    let policy = RetryPolicy::new(
+        u32::MAX,
+        Duration::from_millis(20),
+        Some(Duration::from_millis(100)),
+    );
+
+    let start = Instant::now();
+    assert!(Lights::read_with_retry(&transport, &policy).is_err());
+    assert!(start.elapsed() < Duration::from_millis(500));
+    assert!(transport.attempts.load(Ordering::SeqCst) <= 5);
+}
+
+#[test]
+fn retry_permanent_errors() {
+    struct Unplugged(AtomicUsize);
+
+    impl Transport for Unplugged {
+        fn get_feature_report(&self, _buf: &mut [u8]) -> Result<usize, Error> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            Err(Error::Disconnected)
+        }
+
+        fn set_feature_report(&self, _buf: &[u8]) -> Result<usize, Error> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            Err(Error::Disconnected)
+        }
+
+        fn read_report(&self, _buf: &mut [u8]) -> Result<usize, Error> {
+            Err(Error::Disconnected)
+        }
+
+        fn read_report_timeout(
+            &self,
+            _buf: &mut [u8],
+            _timeout: Duration,
+        ) -> Result<Option<usize>, Error> {
+            Err(Error::Disconnected)
+        }
+    }
+
+    use libroccat::device::ryosmkfx::Lights;
+
+    let transport = Unplugged(AtomicUsize::new(0));
+    let policy = RetryPolicy::new(5, Duration::from_millis(0), None);
+    assert!(matches!(
+        Lights::read_with_retry(&transport, &policy),
+        Err(Error::Disconnected)
+    ));
+    assert_eq!(transport.0.load(Ordering::SeqCst), 1);
+}
@@ -45,36 +45,17 @@ local MATRIX_ROWS = 25
 local MATRIX_COLS = 95
 local KEY_WIDTH = 4
 
-local KEYS = {
-    [0]   = {x =  7, y =  2}, [1]   = {x = 15, y =  2}, [2]   = {x = 19, y =  2}, [3]   = {x = 23, y =  2},
-    [4]   = {x = 27, y =  2}, [5]   = {x = 33, y =  2}, [6]   = {x = 37, y =  2}, [7]   = {x = 41, y =  2},
-    [8]   = {x = 45, y =  2}, [9]   = {x = 51, y =  2}, [10]  = {x = 55, y =  2}, [11]  = {x = 59, y =  2},
-    [12]  = {x = 63, y =  2}, [13]  = {x = 68, y =  2}, [14]  = {x = 72, y =  2}, [15]  = {x = 76, y =  2},
-    [16]  = {x =  2, y =  7}, [17]  = {x =  7, y =  7}, [18]  = {x = 11, y =  7}, [19]  = {x = 15, y =  7},
-    [20]  = {x = 19, y =  7}, [21]  = {x = 23, y =  7}, [22]  = {x = 27, y =  7}, [23]  = {x = 31, y =  7},
-    [24]  = {x = 35, y =  7}, [25]  = {x = 39, y =  7}, [26]  = {x = 43, y =  7}, [27]  = {x = 47, y =  7},
-    [28]  = {x = 51, y =  7}, [29]  = {x = 55, y =  7}, [30]  = {x = 63, y =  7}, [31]  = {x = 68, y =  7},
-    [32]  = {x = 72, y =  7}, [33]  = {x = 76, y =  7}, [34]  = {x = 81, y =  7}, [35]  = {x = 85, y =  7},
-    [36]  = {x = 89, y =  7}, [37]  = {x = 93, y =  7}, [38]  = {x =  2, y = 11}, [39]  = {x =  8, y = 11},
-    [40]  = {x = 13, y = 11}, [41]  = {x = 17, y = 11}, [42]  = {x = 21, y = 11}, [43]  = {x = 25, y = 11},
-    [44]  = {x = 29, y = 11}, [45]  = {x = 33, y = 11}, [46]  = {x = 37, y = 11}, [47]  = {x = 41, y = 11},
-    [48]  = {x = 45, y = 11}, [49]  = {x = 49, y = 11}, [50]  = {x = 53, y = 11}, [51]  = {x = 57, y = 11},
-    [52]  = {x = 62, y = 15}, [53]  = {x = 68, y = 11}, [54]  = {x = 72, y = 11}, [55]  = {x = 76, y = 11},
-    [56]  = {x = 81, y = 11}, [57]  = {x = 85, y = 11}, [58]  = {x = 89, y = 11}, [59]  = {x = 93, y = 13},
-    [60]  = {x =  2, y = 15}, [61]  = {x =  9, y = 15}, [62]  = {x = 14, y = 15}, [63]  = {x = 18, y = 15},
-    [64]  = {x = 22, y = 15}, [65]  = {x = 26, y = 15}, [66]  = {x = 30, y = 15}, [67]  = {x = 34, y = 15},
-    [68]  = {x = 38, y = 15}, [69]  = {x = 42, y = 15}, [70]  = {x = 46, y = 15}, [71]  = {x = 50, y = 15},
-    [72]  = {x = 54, y = 15}, [73]  = {x = 58, y = 15}, [74]  = {x = 81, y = 15}, [75]  = {x = 85, y = 15},
-    [76]  = {x = 89, y = 15}, [77]  = {x =  2, y = 19}, [78]  = {x =  8, y = 19}, [79]  = {x = 12, y = 19},
-    [80]  = {x = 16, y = 19}, [81]  = {x = 20, y = 19}, [82]  = {x = 24, y = 19}, [83]  = {x = 28, y = 19},
-    [84]  = {x = 32, y = 19}, [85]  = {x = 36, y = 19}, [86]  = {x = 40, y = 19}, [87]  = {x = 44, y = 19},
-    [88]  = {x = 48, y = 19}, [89]  = {x = 52, y = 19}, [90]  = {x = 61, y = 19}, [91]  = {x = 72, y = 19},
-    [92]  = {x = 81, y = 19}, [93]  = {x = 85, y = 19}, [94]  = {x = 89, y = 19}, [95]  = {x = 93, y = 21},
-    [96]  = {x =  2, y = 23}, [97]  = {x =  8, y = 23}, [98]  = {x = 13, y = 23}, [99]  = {x = 18, y = 23},
-    [100] = {x = 31, y = 23}, [101] = {x = 49, y = 23}, [102] = {x = 53, y = 23}, [103] = {x = 57, y = 23},
-    [104] = {x = 62, y = 23}, [105] = {x = 68, y = 23}, [106] = {x = 72, y = 23}, [107] = {x = 76, y = 23},
-    [108] = {x = 83, y = 23}, [109] = {x = 89, y = 23},
-}
+-- Key centers on the matrix, by SDK index
+local KEYS = {}
+
+for _, key in ipairs(libroccat.geometry("iso")) do
+    if key.sdk_index then
+        KEYS[key.sdk_index] = {
+            x = math.floor((key.x + key.width / 2) * KEY_WIDTH + 0.5),
+            y = math.floor((key.y + key.height / 2) * KEY_WIDTH + 0.5),
+        }
+    end
+end
 
 local LIGHT_POSITION_MATRIX = {}
 
@@ -1,15 +1,34 @@
 use failure::Error;
-use libroccat;
+use libroccat::{
+    config::Config,
+    device::{ryosmkfx::KeyboardLayout, RoccatDevice},
+    transport::Transport,
+};
 use rlua::prelude::*;
 use std::{
     self,
+    cell::RefCell,
     fs::File,
     io::{prelude::*, BufReader},
 };
 
-pub fn run_script(path: &str) -> Result<(), Error> {
+/// How scripts get their devices
+#[derive(Clone, Debug, Default)]
+pub struct ScriptOptions {
+    /// Runs against an emulated Ryos MK FX instead of attached devices
+    pub emulate: bool,
+    /// Settings applied to every device a script opens
+    pub config: Config,
+    /// Layout of every keyboard a script opens, overriding the configuration
+    pub layout: Option<KeyboardLayout>,
+}
+
+impl LuaUserData for ScriptOptions {}
+
+pub fn run_script(path: &str, options: ScriptOptions) -> Result<(), Error> {
     let lua = Lua::new();
     lua.context(|context| {
+        context.set_named_registry_value("options", options)?;
         context.globals().set("libroccat", Libroccat)?;
 
         let file = File::open(path).map_err(rlua::Error::external)?;
@@ -31,21 +50,72 @@ impl LuaUserData for Libroccat {
     fn add_methods<'lua, M: LuaUserDataMethods<'lua, Self>>(methods: &mut M) {
         methods.add_function("find_devices", |lua, ()| {
             let table = lua.create_table()?;
+            if options(lua)?.emulate {
+                let emulator = libroccat::device::ryosmkfx::Emulator::new();
+                let device = emulator.open().map_err(rlua::Error::external)?;
+                configure(lua, &device)?;
+                table.set(1, RyosMkFx::new(device))?;
+                return Ok(table);
+            }
             for (i, device) in libroccat::find_devices()
                 .map_err(rlua::Error::external)?
                 .into_iter()
                 .enumerate()
             {
-                match device {
-                    libroccat::device::Device::RyosMkFx(device) => {
-                        table.set(i + 1, RyosMkFx(device))?
-                    }
-                    libroccat::device::Device::Tyon(device) => table.set(i + 1, Tyon(device))?,
-                };
+                table.set(i + 1, device_to_lua(lua, device)?)?;
             }
             Ok(table)
         });
 
+        methods.add_function("open", |lua, selector: String| {
+            device_to_lua(
+                lua,
+                libroccat::open(&selector).map_err(rlua::Error::external)?,
+            )
+        });
+
+        methods.add_function("watch_devices", |_, ()| {
+            Ok(DeviceMonitor(
+                libroccat::hotplug::monitor().map_err(rlua::Error::external)?,
+            ))
+        });
+
+        methods.add_function("emulate_ryosmkfx", |_, ()| {
+            Ok(RyosMkFxEmulator(
+                libroccat::device::ryosmkfx::Emulator::new(),
+            ))
+        });
+
+        // Looks a Ryos MK FX key up by name or SDK index
+        methods.add_function("key", |lua, key: LuaValue| {
+            use libroccat::device::ryosmkfx::KeyId;
+            use std::convert::TryFrom;
+
+            let key = match key {
+                LuaValue::String(name) => KeyId::from_name(name.to_str()?),
+                LuaValue::Integer(sdk_index) => {
+                    u8::try_from(sdk_index).ok().and_then(KeyId::from_sdk_index)
+                }
+                _ => None,
+            };
+            key.map(|key| key_to_table(lua, key)).transpose()
+        });
+
+        // Ryos MK FX key rectangles in key widths for the "ansi" or "iso" layout, or labelled
+        // for a keyboard layout like "de"
+        methods.add_function("geometry", |lua, layout: String| {
+            use libroccat::device::ryosmkfx::{Geometry, KeyboardLayout, PhysicalLayout};
+
+            match layout.as_str() {
+                "ansi" => geometry_to_table(lua, &Geometry::new(PhysicalLayout::Ansi), None),
+                "iso" => geometry_to_table(lua, &Geometry::new(PhysicalLayout::Iso), None),
+                name => {
+                    let layout: KeyboardLayout = name.parse().map_err(rlua::Error::external)?;
+                    geometry_to_table(lua, &layout.geometry(), Some(layout))
+                }
+            }
+        });
+
         methods.add_function("sleep", |_, time| {
             std::thread::sleep(std::time::Duration::from_millis(time));
             Ok(())
@@ -53,120 +123,255 @@ impl LuaUserData for Libroccat {
     }
 }
 
-struct RyosMkFx(libroccat::device::ryosmkfx::RyosMkFx);
+fn options(lua: LuaContext) -> LuaResult<ScriptOptions> {
+    let options: LuaAnyUserData = lua.named_registry_value("options")?;
+    let options = options.borrow::<ScriptOptions>()?;
+    Ok(options.clone())
+}
 
-impl RyosMkFx {
-    fn get_event_table<'lua>(&self, lua: LuaContext<'lua>) -> LuaResult<Option<LuaTable<'lua>>> {
-        use libroccat::device::ryosmkfx::{
-            EventKeyAction, EventLiveRecordingAction, EventRadSubtype, EventType,
-        };
+/// Applies the configuration and the layout given on the command line
+fn configure(lua: LuaContext, device: &dyn RoccatDevice) -> LuaResult<()> {
+    let options = options(lua)?;
+    options
+        .config
+        .apply(device)
+        .map_err(rlua::Error::external)?;
+    if let (Some(layout), Some(labels)) = (options.layout, device.as_key_labels()) {
+        labels.set_layout(layout);
+    }
+    Ok(())
+}
+
+fn device_to_lua(lua: LuaContext, device: libroccat::device::Device) -> LuaResult<LuaValue> {
+    configure(lua, &device)?;
+    match device {
+        libroccat::device::Device::RyosMkFx(device) => RyosMkFx::new(device).to_lua(lua),
+        libroccat::device::Device::Tyon(device) => Tyon(device).to_lua(lua),
+    }
+}
+
+fn key_to_table(lua: LuaContext, key: libroccat::device::ryosmkfx::KeyId) -> LuaResult<LuaTable> {
+    let table = lua.create_table()?;
+    table.set("name", key.name())?;
+    table.set("sdk_index", key.sdk_index())?;
+    table.set("light_index", key.light_index())?;
+    table.set("hid_usage", key.hid_usage())?;
+    Ok(table)
+}
+
+fn geometry_to_table<'lua>(
+    lua: LuaContext<'lua>,
+    geometry: &libroccat::device::ryosmkfx::Geometry,
+    layout: Option<libroccat::device::ryosmkfx::KeyboardLayout>,
+) -> LuaResult<LuaTable<'lua>> {
+    let table = lua.create_table()?;
+    for (i, rect) in geometry.keys().iter().enumerate() {
+        let key = key_to_table(lua, rect.key)?;
+        key.set("x", rect.x)?;
+        key.set("y", rect.y)?;
+        key.set("width", rect.width)?;
+        key.set("height", rect.height)?;
+        key.set("label", layout.and_then(|layout| layout.label(rect.key)))?;
+        table.set(i + 1, key)?;
+    }
+    Ok(table)
+}
 
-        if let Some(event) = self.0.get_event() {
+struct DeviceMonitor(libroccat::hotplug::Monitor);
+
+impl LuaUserData for DeviceMonitor {
+    fn add_methods<'lua, M: LuaUserDataMethods<'lua, Self>>(methods: &mut M) {
+        // Waits for the next attached or detached device, forever without a timeout
+        methods.add_method("next", |lua, this, timeout: Option<u64>| {
+            use libroccat::hotplug::Hotplug;
+
+            let notification = match timeout {
+                Some(timeout) => this
+                    .0
+                    .next_timeout(std::time::Duration::from_millis(timeout)),
+                None => this.0.receiver().recv().ok(),
+            };
             let table = lua.create_table()?;
-            if event.type_ == EventType::Rad {
-                table.set(
-                    "subtype",
-                    match unsafe { event.subtype.rad } {
-                        EventRadSubtype::W => "w",
-                        EventRadSubtype::A => "a",
-                        EventRadSubtype::S => "s",
-                        EventRadSubtype::D => "d",
-                        EventRadSubtype::Thumbster1 => "thumbster_1",
-                        EventRadSubtype::Thumbster2 => "thumbster_2",
-                        EventRadSubtype::Thumbster3 => "thumbster_3",
-                        EventRadSubtype::Easyshift => "easyshift",
-                        EventRadSubtype::Multimedia => "multimedia",
-                        EventRadSubtype::M1 => "m1",
-                        EventRadSubtype::M2 => "m2",
-                        EventRadSubtype::M3 => "m3",
-                        EventRadSubtype::M4 => "m4",
-                        EventRadSubtype::M5 => "m5",
-                        EventRadSubtype::MacroShortcut => "macro_shortcut",
-                        EventRadSubtype::Talk => "talk",
-                        EventRadSubtype::MacroLifeRec => "macro_life_rec",
-                        EventRadSubtype::Backlight => "backlight",
-                        EventRadSubtype::Total => "total",
-                    },
-                )?;
+            match notification.transpose().map_err(rlua::Error::external)? {
+                Some(Hotplug::Added(device)) => {
+                    table.set("event", "added")?;
+                    table.set("id", device.id().map(|id| id.to_string()))?;
+                    table.set("device", device_to_lua(lua, device)?)?;
+                }
+                Some(Hotplug::Removed(id)) => {
+                    table.set("event", "removed")?;
+                    table.set("id", id.to_string())?;
+                }
+                None => return Ok(None),
             }
-            table.set(
-                "type",
-                match event.type_ {
-                    EventType::Unknown => "unknown",
-                    EventType::ProfileStart => "profile_start",
-                    EventType::Profile => "profile",
-                    EventType::Macro => "macro",
-                    EventType::LiveRecording => "live_recording",
-                    EventType::Quicklaunch => "quicklaunch",
-                    EventType::Easyshift => "easyshift",
-                    EventType::Multimedia => "multimedia",
-                    EventType::Backlight => "backlight",
-                    EventType::TimerStart => "timer_start",
-                    EventType::TimerStop => "timer_stop",
-                    EventType::OpenDriver => "open_driver",
-                    EventType::LedMacro => "led_macro",
-                    EventType::Rad => "rad",
-                    EventType::Effect => "effect",
-                    EventType::Layer => "layer",
-                    EventType::EasyshiftSelf => "easyshift_self",
-                    EventType::Talk => "talk",
-                },
-            )?;
-            table.set(
-                "data",
-                match event.type_ {
-                    EventType::Effect => event.sdk_index(),
-                    _ => event.data,
-                },
-            )?;
-            if event.type_ == EventType::Effect || event.type_ == EventType::LiveRecording {
-                table.set(
-                    "action",
-                    match event.type_ {
-                        EventType::Effect => match unsafe { event.action.key } {
-                            EventKeyAction::Release => "release",
-                            EventKeyAction::Press => "press",
-                        },
-                        EventType::LiveRecording => match unsafe { event.action.live_recording } {
-                            EventLiveRecordingAction::Start => "start",
-                            EventLiveRecordingAction::MacroKeySelected => "macro_key_selected",
-                            EventLiveRecordingAction::EndSuccess => "end_success",
-                            EventLiveRecordingAction::EndAbort => "end_abort",
-                            EventLiveRecordingAction::InvalidKey => "invalid_key",
-                        },
-                        _ => unreachable!(),
-                    },
-                )?;
+            Ok(Some(table))
+        });
+    }
+}
+
+/// The device and the keys held according to the events the script took so far
+struct RyosMkFx<T: Transport + 'static>(
+    libroccat::device::ryosmkfx::RyosMkFx<T>,
+    RefCell<libroccat::device::ryosmkfx::KeyState>,
+);
+
+impl<T: Transport + 'static> RyosMkFx<T> {
+    fn new(device: libroccat::device::ryosmkfx::RyosMkFx<T>) -> Self {
+        RyosMkFx(device, RefCell::default())
+    }
+
+    fn get_event_table<'lua>(&self, lua: LuaContext<'lua>) -> LuaResult<Option<LuaTable<'lua>>> {
+        self.track_event(lua, self.0.next_event())
+    }
+
+    /// Updates the held keys, adding how long a released key was held in milliseconds as `held`
+    /// and the label of pressed and released keys as `label`
+    fn track_event<'lua>(
+        &self,
+        lua: LuaContext<'lua>,
+        event: Option<libroccat::device::ryosmkfx::EventResult>,
+    ) -> LuaResult<Option<LuaTable<'lua>>> {
+        use libroccat::device::ryosmkfx::{KeyChange, KeyId};
+
+        match event {
+            Some(Ok(event)) => {
+                let change = self.1.borrow_mut().update(&event);
+                let table = event_to_table(lua, event)?;
+                let key = match change {
+                    Some(KeyChange::Pressed { key }) | Some(KeyChange::Released { key, .. }) => {
+                        KeyId::from_sdk_index(key)
+                    }
+                    None => None,
+                };
+                if let Some(key) = key {
+                    table.set("label", self.0.layout().label(key))?;
+                }
+                if let Some(KeyChange::Released {
+                    held: Some(held), ..
+                }) = change
+                {
+                    table.set("held", held.as_millis() as u64)?;
+                }
+                Ok(Some(table))
+            }
+            Some(Err(error)) => {
+                if let libroccat::Error::Disconnected = *error {
+                    self.1.borrow_mut().clear();
+                }
+                Err(rlua::Error::external(error))
             }
-            return Ok(Some(table));
+            None => Ok(None),
+        }
+    }
+}
+
+/// Read errors like the device being unplugged are raised in the script
+fn event_result_to_table<'lua>(
+    lua: LuaContext<'lua>,
+    event: Option<libroccat::device::ryosmkfx::EventResult>,
+) -> LuaResult<Option<LuaTable<'lua>>> {
+    match event {
+        Some(event) => event_to_table(lua, event.map_err(rlua::Error::external)?).map(Some),
+        None => Ok(None),
+    }
+}
+
+fn event_to_table(
+    lua: LuaContext,
+    event: libroccat::device::ryosmkfx::Event,
+) -> LuaResult<LuaTable> {
+    use libroccat::device::ryosmkfx::{DecodedEvent, EventKeyAction};
+
+    let table = lua.create_table()?;
+    table.set("type", event.type_.name())?;
+    table.set("data", event.data)?;
+    match event.decode() {
+        DecodedEvent::KeyPress { key } => {
+            table.set("data", key)?;
+            table.set("action", EventKeyAction::Press.name())?;
         }
-        Ok(None)
+        DecodedEvent::KeyRelease { key } => {
+            table.set("data", key)?;
+            table.set("action", EventKeyAction::Release.name())?;
+        }
+        DecodedEvent::LiveRecording { action, .. } => table.set("action", action.name())?,
+        DecodedEvent::Rad(subtype) => table.set("subtype", subtype.name())?,
+        DecodedEvent::Unknown { raw } => table.set("raw", raw.to_vec())?,
+        DecodedEvent::ProfileChanged { .. }
+        | DecodedEvent::Multimedia(_)
+        | DecodedEvent::Other { .. } => (),
+    }
+    Ok(table)
+}
+
+/// A subscription to a device's events, independent of `get_event` and other subscribers
+struct EventSubscriber(libroccat::event_bus::Subscriber<libroccat::device::ryosmkfx::EventResult>);
+
+impl LuaUserData for EventSubscriber {
+    fn add_methods<'lua, M: LuaUserDataMethods<'lua, Self>>(methods: &mut M) {
+        // Blocks for up to `timeout` milliseconds, or until an event arrives if there's none
+        methods.add_method("recv", |lua, this, timeout: Option<u64>| {
+            let event = match timeout {
+                Some(timeout) => this
+                    .0
+                    .recv_timeout(std::time::Duration::from_millis(timeout)),
+                None => this.0.recv(),
+            };
+            event_result_to_table(lua, event)
+        });
+
+        methods.add_method("try_recv", |lua, this, ()| {
+            event_result_to_table(lua, this.0.try_recv())
+        });
+
+        methods.add_method("dropped", |_, this, ()| Ok(this.0.dropped()));
     }
 }
 
-impl LuaUserData for RyosMkFx {
+impl<T: Transport + 'static> LuaUserData for RyosMkFx<T> {
     fn add_methods<'lua, M: LuaUserDataMethods<'lua, Self>>(methods: &mut M) {
         methods.add_method("name", |_, _, ()| Ok("ryosmkfx"));
 
+        methods.add_method("id", |_, this, ()| Ok(this.0.id().map(|id| id.to_string())));
+
         methods.add_method("get_event", |lua, this, ()| loop {
-            if let Some(table) = this.get_event_table(lua)? {
+            let event = this.0.next_event_timeout(std::time::Duration::from_secs(1));
+            if let Some(table) = this.track_event(lua, event)? {
                 return Ok(Some(table));
             }
-            std::thread::sleep(std::time::Duration::from_millis(5));
         });
 
         methods.add_method("get_event_timed", |lua, this, timeout| {
-            let start = std::time::Instant::now();
-            let duration = std::time::Duration::from_millis(timeout);
+            let event = this
+                .0
+                .next_event_timeout(std::time::Duration::from_millis(timeout));
+            this.track_event(lua, event)
+        });
 
-            while start.elapsed() < duration {
-                if let Some(table) = this.get_event_table(lua)? {
-                    return Ok(Some(table));
-                }
-                std::thread::sleep(std::time::Duration::from_millis(5));
-            }
+        // Held keys only change as the script takes events
+        methods.add_method("is_pressed", |_, this, key| {
+            Ok(this.1.borrow().is_pressed(key))
+        });
+
+        methods.add_method("pressed_keys", |_, this, ()| {
+            Ok(this.1.borrow().pressed_keys())
+        });
+
+        methods.add_method("hold_duration", |_, this, key| {
+            Ok(this
+                .1
+                .borrow()
+                .hold_duration(key)
+                .map(|held| held.as_millis() as u64))
+        });
+
+        methods.add_method("subscribe", |_, this, capacity: Option<usize>| {
+            use libroccat::event_bus::{Overflow, DEFAULT_CAPACITY};
 
-            Ok(None)
+            let capacity = capacity.unwrap_or(DEFAULT_CAPACITY);
+            Ok(EventSubscriber(
+                this.0.subscribe_with(capacity, Overflow::DropOldest),
+            ))
         });
 
         methods.add_method("get_event_immediate", |lua, this, ()| {
@@ -185,6 +390,37 @@ impl LuaUserData for RyosMkFx {
             this.0.set_profile(profile).map_err(rlua::Error::external)
         });
 
+        methods.add_method("layout", |_, this, ()| Ok(this.0.layout().name()));
+
+        methods.add_method("set_layout", |_, this, layout: String| {
+            this.0
+                .set_layout(layout.parse().map_err(rlua::Error::external)?);
+            Ok(())
+        });
+
+        // Labelled key rectangles for the device's layout
+        methods.add_method("geometry", |lua, this, ()| {
+            let layout = this.0.layout();
+            geometry_to_table(lua, &layout.geometry(), Some(layout))
+        });
+
+        methods.add_method("key_label", |_, this, sdk_index| {
+            use libroccat::device::ryosmkfx::KeyId;
+
+            Ok(KeyId::from_sdk_index(sdk_index).and_then(|key| this.0.layout().label(key)))
+        });
+
+        // Gets the SDK index of the key with a label in the device's layout
+        methods.add_method("key_index", |_, this, label: String| {
+            Ok(this.0.layout().key(&label).and_then(|key| key.sdk_index()))
+        });
+
+        methods.add_method_mut("set_write_timeout", |_, this, timeout: Option<u64>| {
+            this.0
+                .set_write_check_timeout(timeout.map(std::time::Duration::from_millis));
+            Ok(())
+        });
+
         methods.add_method("get_lights", |lua, this, profile| {
             use libroccat::device::ryosmkfx::*;
 
@@ -383,6 +619,57 @@ impl LuaUserData for RyosMkFx {
 
             Ok(())
         });
+
+        // Macros are passed in their text form, see `libroccat::device::ryosmkfx::Macro`
+        methods.add_method("get_macro", |_, this, (profile, slot)| {
+            let macro_ = this
+                .0
+                .get_macro(profile, slot)
+                .map_err(rlua::Error::external)?;
+            Ok(macro_.to_string())
+        });
+
+        methods.add_method(
+            "set_macro",
+            |_, this, (profile, slot, text): (u8, u8, String)| {
+                use libroccat::device::ryosmkfx::Macro;
+
+                let macro_: Macro = text.parse().map_err(rlua::Error::external)?;
+                this.0
+                    .set_macro(profile, slot, &macro_)
+                    .map_err(rlua::Error::external)
+            },
+        );
+    }
+}
+
+struct RyosMkFxEmulator(libroccat::device::ryosmkfx::Emulator);
+
+impl LuaUserData for RyosMkFxEmulator {
+    fn add_methods<'lua, M: LuaUserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_method("open", |lua, this, ()| {
+            let device = this.0.open().map_err(rlua::Error::external)?;
+            configure(lua, &device)?;
+            Ok(RyosMkFx::new(device))
+        });
+
+        methods.add_method("get_profile", |_, this, ()| Ok(this.0.profile()));
+
+        methods.add_method("switch_profile", |_, this, profile| {
+            this.0
+                .switch_profile(profile)
+                .map_err(rlua::Error::external)
+        });
+
+        methods.add_method("inject_key", |_, this, (key, pressed)| {
+            this.0
+                .inject_key(key, pressed)
+                .map_err(rlua::Error::external)
+        });
+
+        methods.add_method("get_custom_lights_active", |_, this, ()| {
+            Ok(this.0.custom_lights_active())
+        });
     }
 }
 
@@ -392,8 +679,10 @@ impl LuaUserData for Tyon {
     fn add_methods<'lua, M: LuaUserDataMethods<'lua, Self>>(methods: &mut M) {
         methods.add_method("name", |_, _, ()| Ok("tyon"));
 
+        methods.add_method("id", |_, this, ()| Ok(this.0.id().map(|id| id.to_string())));
+
         methods.add_method("get_profile", |_, this, ()| {
-            Ok(this.0.get_profile().map_err(rlua::Error::external)?)
+            this.0.get_profile().map_err(rlua::Error::external)
         });
 
         methods.add_method("set_profile", |_, this, profile| {
@@ -1,35 +1,52 @@
 mod libroccat_lua;
 
 use clap::{App, SubCommand};
-use failure::{bail, Error, ResultExt};
+use failure::{bail, Error};
+use libroccat::device::RoccatDevice;
 use log::error;
 use std::thread;
 
 fn run() -> Result<(), Error> {
-    #[cfg_attr(rustfmt, rustfmt_skip)]
+    #[rustfmt::skip]
     let matches = App::new("roccat-tools")
         .author("Ash Lea <ashlea@protonmail.com>")
         .about("Controls Roccat devices")
         .subcommand(SubCommand::with_name("list")
             .about("List attached devices")
         )
+        .subcommand(SubCommand::with_name("monitor")
+            .about("Print devices as they are attached and detached")
+        )
+        .subcommand(SubCommand::with_name("supported")
+            .about("List supported products")
+        )
         .subcommand(SubCommand::with_name("run")
             .about("Run scripts")
             .args_from_usage("
+                --emulate         'Run against an emulated Ryos MK FX instead of attached devices'
+                --config=[config] 'Device configuration, one `<device> layout <layout>` per line'
+                --layout=[layout] 'Keyboard layout of every keyboard, e.g. de, overriding the configuration'
                 <script>...
             ")
         )
+        .subcommand(SubCommand::with_name("bind")
+            .about("Run host actions for device events until interrupted")
+            .args_from_usage("
+                -d, --device=[device] 'Device to watch, e.g. ryosmkfx#2 or serial:XXXX (default: ryosmkfx)'
+                <bindings>            'Binding table, one `<event>[:<data>] <exec|fifo|socket> <argument>` per line'
+            ")
+        )
         .subcommand(SubCommand::with_name("get")
             .about("Get a property of a device")
             .args_from_usage("
-                <device>   'Device to get from'
+                <device>   'Device to get from, e.g. ryosmkfx, tyon#2, serial:XXXX or /dev/hidraw3'
                 <property> 'Property to get'
             ")
         )
         .subcommand(SubCommand::with_name("set")
             .about("Set a property of a device")
             .args_from_usage("
-                <device>   'Device to set on'
+                <device>   'Device to set on, e.g. ryosmkfx, tyon#2, serial:XXXX or /dev/hidraw3'
                 <property> 'Property to set'
                 <value>    'Value of property'
             ")
@@ -37,18 +54,50 @@ fn run() -> Result<(), Error> {
         .get_matches();
 
     if let Some(_matches) = matches.subcommand_matches("list") {
-        for (i, device) in libroccat::find_devices()?.iter().enumerate() {
-            println!("{}: {}", i, device.get_common_name());
+        for device in libroccat::find_devices()? {
+            println!("{}", describe(&device));
+        }
+    }
+
+    if let Some(_matches) = matches.subcommand_matches("monitor") {
+        use libroccat::hotplug::Hotplug;
+
+        for notification in libroccat::hotplug::monitor()? {
+            match notification {
+                Ok(Hotplug::Added(device)) => {
+                    println!("added {}", describe(&device))
+                }
+                Ok(Hotplug::Removed(id)) => println!("removed {}", id),
+                Err(error) => error!("{}", error),
+            }
+        }
+    }
+
+    if let Some(_matches) = matches.subcommand_matches("supported") {
+        for product in libroccat::registry::products() {
+            println!(
+                "{:04x}:{:04x} {}",
+                product.vendor_id, product.product_id, product.name
+            );
         }
     }
 
     if let Some(matches) = matches.subcommand_matches("run") {
         let mut join_handles = Vec::new();
+        let options = libroccat_lua::ScriptOptions {
+            emulate: matches.is_present("emulate"),
+            config: match matches.value_of("config") {
+                Some(path) => libroccat::config::Config::from_file(path)?,
+                None => Default::default(),
+            },
+            layout: matches.value_of("layout").map(str::parse).transpose()?,
+        };
 
         for path in matches.values_of("script").unwrap() {
             let path = path.to_string();
+            let options = options.clone();
             join_handles.push(thread::spawn(move || {
-                libroccat_lua::run_script(&path).unwrap()
+                libroccat_lua::run_script(&path, options).unwrap()
             }));
         }
 
@@ -57,15 +106,32 @@ fn run() -> Result<(), Error> {
         }
     }
 
+    if let Some(matches) = matches.subcommand_matches("bind") {
+        use libroccat::device::Device;
+
+        let bindings =
+            libroccat::bindings::Bindings::from_file(matches.value_of("bindings").unwrap())?;
+        let device = libroccat::open(matches.value_of("device").unwrap_or("ryosmkfx"))?;
+        if let Device::RyosMkFx(ref device) = device {
+            device.set_auto_reopen(true)?;
+        }
+        let events = match device.as_event_source() {
+            Some(source) => source.subscribe(),
+            None => bail!("{} doesn't send events", device.name()),
+        };
+
+        for event in events {
+            match event {
+                Ok(event) => {
+                    bindings.handle(&event);
+                }
+                Err(error) => error!("{}", error),
+            }
+        }
+    }
+
     if let Some(matches) = matches.subcommand_matches("get") {
-        let device_index = matches
-            .value_of("device")
-            .unwrap()
-            .parse::<usize>()
-            .context("Device must be an integer")?;
-        let device = libroccat::find_devices()
-            .context("Device index out of range")?
-            .remove(device_index);
+        let device = libroccat::open(matches.value_of("device").unwrap())?;
 
         println!(
             "{}",
@@ -78,14 +144,7 @@ fn run() -> Result<(), Error> {
     }
 
     if let Some(matches) = matches.subcommand_matches("set") {
-        let device_index = matches
-            .value_of("device")
-            .unwrap()
-            .parse::<usize>()
-            .context("Device must be an integer")?;
-        let device = libroccat::find_devices()
-            .context("Device index out of range")?
-            .remove(device_index);
+        let device = libroccat::open(matches.value_of("device").unwrap())?;
         let value = matches.value_of("value").unwrap();
 
         match matches.value_of("property") {
@@ -100,6 +159,14 @@ fn run() -> Result<(), Error> {
     Ok(())
 }
 
+/// Names a device along with where it's attached
+fn describe(device: &libroccat::device::Device) -> String {
+    match device.id() {
+        Some(id) => format!("{} {}", device.get_common_name(), id),
+        None => device.get_common_name().to_string(),
+    }
+}
+
 fn main() {
     use std::io::Write;
 
@@ -120,6 +187,14 @@ fn main() {
                 error!("Caused by: {}", cause);
             }
 
+            if let Some(libroccat::Error::PermissionDenied { path }) = error.downcast_ref() {
+                error!(
+                    "Make sure your user may access {}, e.g. by installing a udev rule granting \
+                     access to hidraw devices with vendor ID 1e7d",
+                    path.display()
+                );
+            }
+
             let backtrace = format!("{}", error.backtrace());
             if backtrace.is_empty() {
                 writeln!(